@@ -1,8 +1,58 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::CaseMode;
+
+/// 配置文件解析失败时的读取次数
+const CONFIG_READ_ATTEMPTS: u32 = 4;
+/// 第一次重试前的等待时间，之后每次翻倍
+const CONFIG_READ_RETRY_DELAY: Duration = Duration::from_millis(25);
+/// 同一进程内多次保存时区分临时文件名
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 配置文件格式，按扩展名判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// `.json` 为 JSON，`.yaml`/`.yml` 为 YAML，其他扩展名（包括没有扩展名）按 TOML 处理
+    fn from_path(path: &Path) -> Self {
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "json" => ConfigFormat::Json,
+            "yaml" | "yml" => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    fn parse(self, content: &str) -> Result<Config> {
+        Ok(match self {
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml_ng::from_str(content)?,
+        })
+    }
+
+    fn serialize(self, config: &Config) -> Result<String> {
+        Ok(match self {
+            ConfigFormat::Toml => toml::to_string_pretty(config)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(config)? + "\n",
+            ConfigFormat::Yaml => serde_yaml_ng::to_string(config)?,
+        })
+    }
+}
+
 /// 应用程序配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -25,6 +75,26 @@ pub struct SearchConfig {
     pub context_lines: usize,
     /// 是否遵循 .gitignore 规则
     pub respect_gitignore: bool,
+    /// 单行最多收集的匹配数，超出部分将被截断
+    #[serde(default = "default_max_matches_per_line")]
+    pub max_matches_per_line: usize,
+    /// 错误数达到此值时中止搜索，0 表示不限制
+    #[serde(default)]
+    pub max_errors: usize,
+    /// 大小写匹配方式：sensitive、insensitive 或 smart
+    #[serde(default)]
+    pub case_mode: CaseMode,
+    /// 额外的忽略文件名，按 gitignore 语法解析，不受 respect_gitignore 影响；为空字符串时不使用
+    #[serde(default = "default_custom_ignore_file")]
+    pub custom_ignore_file: Option<String>,
+}
+
+fn default_max_matches_per_line() -> usize {
+    1000
+}
+
+fn default_custom_ignore_file() -> Option<String> {
+    Some(".findignore".to_string())
 }
 
 /// 性能配置
@@ -34,6 +104,19 @@ pub struct PerformanceConfig {
     pub cpu_threshold: f32,
     /// 高CPU负载时的搜索延迟毫秒数
     pub search_delay_ms: u64,
+    /// 超过此大小（MB）的文件视为大文件
+    #[serde(default = "default_large_file_threshold_mb")]
+    pub large_file_threshold_mb: u64,
+    /// 最多同时读取的大文件数，0 表示不限制
+    #[serde(default)]
+    pub max_concurrent_large_files: usize,
+    /// 系统已用内存超过此值（MB）时同样限流，0 表示不按内存限流
+    #[serde(default)]
+    pub memory_threshold_mb: u64,
+}
+
+fn default_large_file_threshold_mb() -> u64 {
+    64
 }
 
 /// 排除规则配置
@@ -61,10 +144,17 @@ impl Default for Config {
                 default_search_path: ".".to_string(),
                 context_lines: 5,
                 respect_gitignore: false,
+                max_matches_per_line: default_max_matches_per_line(),
+                max_errors: 0,
+                case_mode: CaseMode::default(),
+                custom_ignore_file: default_custom_ignore_file(),
             },
             performance: PerformanceConfig {
                 cpu_threshold: 80.0,
                 search_delay_ms: 100,
+                large_file_threshold_mb: default_large_file_threshold_mb(),
+                max_concurrent_large_files: 0,
+                memory_threshold_mb: 0,
             },
             exclude: ExcludeConfig {
                 default_dirs: vec![
@@ -92,23 +182,65 @@ impl Config {
         } else {
             let config = Self::default();
             config.save_to_file(config_path)?;
-            println!("已创建默认配置文件: {}", config_path.display());
+            eprintln!("已创建默认配置文件: {}", config_path.display());
             Ok(config)
         }
     }
 
-    /// 从文件加载配置
+    /// 从文件加载配置，按扩展名解析 TOML、JSON（`.json`）或 YAML（`.yaml`/`.yml`）
+    ///
+    /// 解析失败时短暂重试，以容忍另一个实例正在写入配置文件的情况。
     pub fn load_from_file(config_path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(config_path)
-            .with_context(|| format!("无法读取配置文件: {}", config_path.display()))?;
-        
-        let config: Config = toml::from_str(&content)
-            .with_context(|| format!("无法解析配置文件: {}", config_path.display()))?;
-        
-        Ok(config)
+        Self::load_from_file_with_retry(config_path, CONFIG_READ_ATTEMPTS, CONFIG_READ_RETRY_DELAY)
+    }
+
+    /// 从文件加载配置，解析失败时最多尝试 `attempts` 次，每次重试的等待时间从 `delay` 开始翻倍
+    ///
+    /// 重试期间文件内容发生变化，说明有其他进程正在写入；内容不变仍解析失败则视为格式错误。
+    pub fn load_from_file_with_retry(config_path: &Path, attempts: u32, delay: Duration) -> Result<Self> {
+        let format = ConfigFormat::from_path(config_path);
+        let mut delay = delay;
+        let mut previous: Option<String> = None;
+        let mut changed = false;
+
+        for attempt in 1..=attempts.max(1) {
+            let content = fs::read_to_string(config_path)
+                .with_context(|| format!("无法读取配置文件: {}", config_path.display()))?;
+
+            match format.parse(&content) {
+                Ok(config) => return Ok(config),
+                Err(err) => {
+                    if previous.as_ref().is_some_and(|prev| *prev != content) {
+                        changed = true;
+                    }
+                    if attempt >= attempts {
+                        // 空文件通常是写入者刚创建还没写完
+                        return Err(if changed || content.is_empty() {
+                            err.context(format!(
+                                "配置文件可能正在被其他进程写入，请稍后重试: {}",
+                                config_path.display()
+                            ))
+                        } else {
+                            err.context(format!(
+                                "配置文件格式错误: {}",
+                                config_path.display()
+                            ))
+                        });
+                    }
+                    previous = Some(content);
+                }
+            }
+
+            thread::sleep(delay);
+            delay *= 2;
+        }
+
+        unreachable!("至少会尝试一次读取配置")
     }
 
-    /// 保存配置到文件
+    /// 保存配置到文件，格式与 [`Config::load_from_file`] 一样按扩展名决定
+    ///
+    /// 先写入同目录下的临时文件再重命名，其他进程不会读到写了一半的配置。
     pub fn save_to_file(&self, config_path: &Path) -> Result<()> {
         // 确保目录存在
         if let Some(parent) = config_path.parent() {
@@ -116,12 +248,51 @@ impl Config {
                 .with_context(|| format!("无法创建配置目录: {}", parent.display()))?;
         }
 
-        let content = toml::to_string_pretty(self)
+        let content = ConfigFormat::from_path(config_path).serialize(self)
             .context("无法序列化配置")?;
-        
-        fs::write(config_path, content)
-            .with_context(|| format!("无法写入配置文件: {}", config_path.display()))?;
-        
+
+        let file_name = config_path.file_name()
+            .with_context(|| format!("无效的配置文件路径: {}", config_path.display()))?;
+        let temp_path = config_path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let written = fs::write(&temp_path, content)
+            .with_context(|| format!("无法写入临时配置文件: {}", temp_path.display()))
+            .and_then(|()| {
+                fs::rename(&temp_path, config_path)
+                    .with_context(|| format!("无法写入配置文件: {}", config_path.display()))
+            });
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        written
+    }
+
+    /// 用环境变量覆盖配置项，便于在 CI 等环境中不修改配置文件而调整配置
+    ///
+    /// 支持 `FE_CPU_THRESHOLD`、`FE_SEARCH_DELAY_MS`、`FE_CONTEXT_LINES`、`FE_RESPECT_GITIGNORE`（true/false）
+    /// 和 `FE_MAX_LINE_LENGTH`；未设置的变量不影响配置，值无法解析时报错。取值范围仍由 [`Config::validate`] 检查。
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        if let Some(value) = env_override("FE_CPU_THRESHOLD")? {
+            self.performance.cpu_threshold = value;
+        }
+        if let Some(value) = env_override("FE_SEARCH_DELAY_MS")? {
+            self.performance.search_delay_ms = value;
+        }
+        if let Some(value) = env_override("FE_CONTEXT_LINES")? {
+            self.search.context_lines = value;
+        }
+        if let Some(value) = env_override("FE_RESPECT_GITIGNORE")? {
+            self.search.respect_gitignore = value;
+        }
+        if let Some(value) = env_override("FE_MAX_LINE_LENGTH")? {
+            self.display.max_line_length = value;
+        }
         Ok(())
     }
 
@@ -143,6 +314,10 @@ impl Config {
             anyhow::bail!("context_lines 不能超过 50");
         }
         
+        if self.search.max_matches_per_line == 0 {
+            anyhow::bail!("max_matches_per_line 必须大于 0");
+        }
+        
         if self.performance.cpu_threshold < 10.0 || self.performance.cpu_threshold > 100.0 {
             anyhow::bail!("cpu_threshold 必须在 10-100 之间");
         }
@@ -159,10 +334,25 @@ impl Config {
     }
 }
 
+/// 读取并解析环境变量，未设置时返回 None
+fn env_override<T>(name: &str) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = match std::env::var(name) {
+        Ok(value) => value,
+        Err(std::env::VarError::NotPresent) => return Ok(None),
+        Err(err) => anyhow::bail!("环境变量 {} 无效: {}", name, err),
+    };
+    value.trim().parse()
+        .map(Some)
+        .map_err(|err| anyhow::anyhow!("环境变量 {} 的值 '{}' 无效: {}", name, value, err))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use tempfile::tempdir;
 
     #[test]
@@ -184,6 +374,21 @@ mod tests {
         assert_eq!(config.performance.cpu_threshold, deserialized.performance.cpu_threshold);
     }
 
+    #[test]
+    fn test_case_mode_parsing() {
+        let mut config = toml::to_string(&Config::default()).unwrap();
+        assert!(config.contains("case_mode = \"sensitive\""));
+
+        config = config.replace("case_mode = \"sensitive\"", "case_mode = \"smart\"");
+        let parsed: Config = toml::from_str(&config).unwrap();
+        assert_eq!(parsed.search.case_mode, CaseMode::Smart);
+
+        // 旧配置文件没有 case_mode 时默认区分大小写
+        let old = toml::to_string(&Config::default()).unwrap().replace("case_mode = \"sensitive\"\n", "");
+        let parsed: Config = toml::from_str(&old).unwrap();
+        assert_eq!(parsed.search.case_mode, CaseMode::Sensitive);
+    }
+
     #[test]
     fn test_config_file_operations() {
         let temp_dir = tempdir().unwrap();
@@ -197,6 +402,128 @@ mod tests {
         assert_eq!(original_config.search.context_lines, loaded_config.search.context_lines);
     }
 
+    #[test]
+    fn test_config_formats_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.search.context_lines = 7;
+        config.search.case_mode = CaseMode::Smart;
+        config.search.custom_ignore_file = Some(String::new());
+        config.exclude.default_dirs = vec!["vendor".to_string()];
+
+        for (name, marker) in [
+            ("config.toml", "context_lines = 7"),
+            ("config.json", "\"context_lines\": 7"),
+            ("config.yaml", "context_lines: 7"),
+            ("config.yml", "context_lines: 7"),
+            ("config.conf", "context_lines = 7"),
+        ] {
+            let path = temp_dir.path().join(name);
+            config.save_to_file(&path).unwrap();
+            assert!(fs::read_to_string(&path).unwrap().contains(marker), "{}", name);
+
+            let loaded = Config::load_from_file(&path).unwrap();
+            assert_eq!(loaded.search.context_lines, 7, "{}", name);
+            assert_eq!(loaded.search.case_mode, CaseMode::Smart, "{}", name);
+            assert_eq!(loaded.search.custom_ignore_file.as_deref(), Some(""), "{}", name);
+            assert_eq!(loaded.exclude.default_dirs, vec!["vendor"], "{}", name);
+        }
+
+        // 格式不匹配扩展名时报格式错误
+        let path = temp_dir.path().join("wrong.json");
+        fs::write(&path, toml::to_string_pretty(&config).unwrap()).unwrap();
+        let err = Config::load_from_file_with_retry(&path, 1, Duration::from_millis(1)).unwrap_err();
+        assert!(err.to_string().contains("配置文件格式错误"), "{:#}", err);
+    }
+
+    #[test]
+    fn test_env_overrides_take_precedence_over_file() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let mut config = Config::default();
+        config.search.context_lines = 3;
+        config.performance.search_delay_ms = 500;
+        config.save_to_file(&config_path).unwrap();
+
+        // 只有这个测试设置 FE_ 开头的环境变量
+        std::env::set_var("FE_CONTEXT_LINES", "9");
+        std::env::set_var("FE_CPU_THRESHOLD", "55.5");
+        std::env::set_var("FE_RESPECT_GITIGNORE", "true");
+        std::env::set_var("FE_MAX_LINE_LENGTH", " 80 ");
+        let mut config = Config::load_from_file(&config_path).unwrap();
+        config.apply_env_overrides().unwrap();
+        assert_eq!(config.search.context_lines, 9);
+        assert_eq!(config.performance.cpu_threshold, 55.5);
+        assert!(config.search.respect_gitignore);
+        assert_eq!(config.display.max_line_length, 80);
+        // 未设置的变量保留文件中的值
+        assert_eq!(config.performance.search_delay_ms, 500);
+
+        std::env::set_var("FE_SEARCH_DELAY_MS", "fast");
+        let err = config.apply_env_overrides().unwrap_err();
+        assert!(err.to_string().contains("FE_SEARCH_DELAY_MS"), "{:#}", err);
+
+        for name in ["FE_CONTEXT_LINES", "FE_CPU_THRESHOLD", "FE_RESPECT_GITIGNORE", "FE_MAX_LINE_LENGTH", "FE_SEARCH_DELAY_MS"] {
+            std::env::remove_var(name);
+        }
+    }
+
+    #[test]
+    fn test_atomic_save_has_no_partial_read_window() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        Config::default().save_to_file(&config_path).unwrap();
+
+        let writer_path = config_path.clone();
+        let writer = std::thread::spawn(move || {
+            for i in 0..200 {
+                let mut config = Config::default();
+                config.search.context_lines = i % 10;
+                config.exclude.default_dirs = (0..i).map(|n| format!("dir_{}", n)).collect();
+                config.save_to_file(&writer_path).unwrap();
+            }
+        });
+
+        // 不重试地直接读取：任何时刻读到的都必须是完整的配置
+        while !writer.is_finished() {
+            let content = fs::read_to_string(&config_path).unwrap();
+            toml::from_str::<Config>(&content).unwrap();
+        }
+        writer.join().unwrap();
+
+        // 不留下临时文件
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_load_retries_and_reports_malformed_config() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let full = toml::to_string_pretty(&Config::default()).unwrap();
+
+        // 写了一半的文件在重试期间被补全
+        fs::write(&config_path, &full[..full.len() / 2]).unwrap();
+        let writer_path = config_path.clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            fs::write(&writer_path, full).unwrap();
+        });
+        let config = Config::load_from_file_with_retry(&config_path, 10, Duration::from_millis(10)).unwrap();
+        assert_eq!(config.search.context_lines, 5);
+        writer.join().unwrap();
+
+        // 内容一直不变的错误文件是格式错误
+        fs::write(&config_path, "[search\n").unwrap();
+        let err = Config::load_from_file_with_retry(&config_path, 2, Duration::from_millis(1)).unwrap_err();
+        assert!(err.to_string().contains("配置文件格式错误"), "{:#}", err);
+
+        // 空文件说明写入者还没写完
+        fs::write(&config_path, "").unwrap();
+        let err = Config::load_from_file_with_retry(&config_path, 2, Duration::from_millis(1)).unwrap_err();
+        assert!(err.to_string().contains("正在被其他进程写入"), "{:#}", err);
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Config::default();
@@ -1,3 +1,5 @@
 pub mod config;
+pub mod state;
 
 pub use config::Config;
+pub use state::ScanState;
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// 增量扫描状态：记录每个搜索路径上次扫描的开始时间
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanState {
+    /// 搜索路径 -> 上次扫描开始时间（Unix 时间戳，毫秒）
+    #[serde(default)]
+    pub last_scan: BTreeMap<String, u64>,
+}
+
+impl ScanState {
+    /// 获取状态文件的默认路径（与配置文件同目录）
+    pub fn default_state_path(config_path: &Path) -> PathBuf {
+        config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("scan_state.toml")
+    }
+
+    /// 从文件加载状态，文件不存在时返回空状态（即扫描全部文件）
+    pub fn load(state_path: &Path) -> Result<Self> {
+        if !state_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(state_path)
+            .with_context(|| format!("无法读取扫描状态文件: {}", state_path.display()))?;
+
+        toml::from_str(&content)
+            .with_context(|| format!("无法解析扫描状态文件: {}", state_path.display()))
+    }
+
+    /// 保存状态到文件
+    pub fn save(&self, state_path: &Path) -> Result<()> {
+        if let Some(parent) = state_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("无法创建状态目录: {}", parent.display()))?;
+        }
+
+        let content = toml::to_string_pretty(self)
+            .context("无法序列化扫描状态")?;
+
+        fs::write(state_path, content)
+            .with_context(|| format!("无法写入扫描状态文件: {}", state_path.display()))?;
+
+        Ok(())
+    }
+
+    /// 获取搜索路径上次扫描的开始时间
+    pub fn last_scan_time(&self, search_path: &Path) -> Option<SystemTime> {
+        self.last_scan
+            .get(&Self::key(search_path))
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(*millis))
+    }
+
+    /// 记录搜索路径本次扫描的开始时间
+    pub fn record_scan(&mut self, search_path: &Path, time: SystemTime) {
+        let millis = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.last_scan.insert(Self::key(search_path), millis);
+    }
+
+    /// 状态键：尽量使用规范化后的绝对路径，使不同写法的同一路径共享状态
+    fn key(search_path: &Path) -> String {
+        search_path
+            .canonicalize()
+            .unwrap_or_else(|_| search_path.to_path_buf())
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_state_scans_everything() {
+        let temp_dir = tempdir().unwrap();
+        let state = ScanState::load(&temp_dir.path().join("missing.toml")).unwrap();
+        assert!(state.last_scan_time(temp_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let state_path = temp_dir.path().join("state").join("scan_state.toml");
+        let scan_time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+
+        let mut state = ScanState::default();
+        state.record_scan(temp_dir.path(), scan_time);
+        state.save(&state_path).unwrap();
+
+        let loaded = ScanState::load(&state_path).unwrap();
+        assert_eq!(loaded.last_scan_time(temp_dir.path()), Some(scan_time));
+        assert!(loaded.last_scan_time(&temp_dir.path().join("other")).is_none());
+    }
+
+    #[test]
+    fn test_default_state_path() {
+        let path = ScanState::default_state_path(Path::new("/opt/fe/config.toml"));
+        assert_eq!(path, PathBuf::from("/opt/fe/scan_state.toml"));
+    }
+}
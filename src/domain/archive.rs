@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+
+use super::search::{decode_text, search_text, ContextLines, Decoding, FileSearch, LineMatcher, LineRange};
+
+/// 默认跳过的压缩包成员大小上限
+pub const DEFAULT_MAX_MEMBER_SIZE: u64 = 64 * 1024 * 1024;
+
+/// 是否为 gzip 压缩的 tar 包（`.tar.gz` 或 `.tgz`）
+pub fn is_tar_gz(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// 压缩包搜索的输出
+#[derive(Debug, Default)]
+pub struct ArchiveSearch {
+    pub search: FileSearch,
+    /// 超过大小上限或不是文本而被跳过的成员
+    pub skipped_members: Vec<String>,
+}
+
+/// 逐个成员搜索 `.tar.gz` 包，不解压到磁盘
+///
+/// 成员内容直接从解压流中读取，同一时间只有一个成员在内存中，超过 `max_member_size` 的成员
+/// 按头部记录的大小直接跳过、不读取。结果路径为 `压缩包:成员`，因此输出为 `archive.tgz:member:line`，
+/// `range` 对每个成员分别生效，`max_count` 限制整个压缩包的结果数，达到上限后不再读取之后的成员。
+/// 成员只接受 UTF-8 或带 BOM 的 UTF-16，包含 NUL 字节时标记为二进制；
+/// 压缩包中常有二进制成员，因此不做有损解码，无法解码的成员被跳过。
+pub fn search_tar_gz(
+    path: &Path,
+    matcher: &LineMatcher,
+    range: &LineRange,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_member_size: u64,
+    max_count: Option<usize>,
+) -> Result<ArchiveSearch> {
+    let file = File::open(path).with_context(|| format!("无法读取文件: {}", path.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    let mut output = ArchiveSearch::default();
+
+    let entries = archive.entries().with_context(|| format!("无法读取压缩包: {}", path.display()))?;
+    for entry in entries {
+        let remaining = max_count.map(|max| max.saturating_sub(output.search.results.len()));
+        if remaining == Some(0) {
+            break;
+        }
+
+        let mut entry = entry.with_context(|| format!("无法读取压缩包: {}", path.display()))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let member = entry.path()
+            .map(|member| member.to_string_lossy().to_string())
+            .with_context(|| format!("压缩包中的成员路径无效: {}", path.display()))?;
+        let display_path = format!("{}:{}", path.display(), member);
+        if entry.size() > max_member_size {
+            output.skipped_members.push(display_path);
+            continue;
+        }
+
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)
+            .with_context(|| format!("无法读取压缩包成员: {}", display_path))?;
+        let Ok((content, encoding)) = decode_text(bytes, &display_path, Decoding::Strict) else {
+            output.skipped_members.push(display_path);
+            continue;
+        };
+
+        let search = search_text(
+            &display_path,
+            &content,
+            encoding,
+            matcher,
+            range,
+            context,
+            max_matches_per_line,
+            remaining,
+        );
+        output.search.lines_scanned += search.lines_scanned;
+        output.search.results.extend(search.results);
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::search::SearchPattern;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn write_tar_gz(path: &Path, members: &[(&str, &[u8])]) {
+        let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_is_tar_gz() {
+        assert!(is_tar_gz(Path::new("logs/backup.tar.gz")));
+        assert!(is_tar_gz(Path::new("BACKUP.TGZ")));
+        assert!(!is_tar_gz(Path::new("notes.gz")));
+        assert!(!is_tar_gz(Path::new("backup.tar")));
+    }
+
+    #[test]
+    fn test_search_members_without_extraction() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = temp_dir.path().join("logs.tgz");
+        write_tar_gz(&archive, &[
+            ("app/a.log", b"ok\nerror: disk full\n"),
+            ("app/b.log", b"fine\n"),
+            ("huge.log", b"error: too big to read\n"),
+            ("bad.bin", &[0xC3, 0x28, b'e', b'r', b'r', b'o', b'r']),
+        ]);
+
+        let matcher = SearchPattern::Text("error".to_string()).get_line_matcher().unwrap();
+        let output = search_tar_gz(&archive, &matcher, &LineRange::default(), ContextLines::default(), 1000, 20, None).unwrap();
+
+        assert_eq!(output.search.results.len(), 1);
+        let result = &output.search.results[0];
+        assert_eq!(result.path, format!("{}:app/a.log", archive.display()));
+        assert_eq!(result.line_number, 2);
+        assert_eq!(output.search.lines_scanned, 3);
+
+        // 超过大小上限和无法解码的成员被跳过
+        assert_eq!(output.skipped_members, vec![
+            format!("{}:huge.log", archive.display()),
+            format!("{}:bad.bin", archive.display()),
+        ]);
+    }
+
+    #[test]
+    fn test_corrupt_archive_is_an_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let archive = temp_dir.path().join("broken.tar.gz");
+        std::fs::write(&archive, b"not gzip").unwrap();
+
+        let matcher = SearchPattern::Text("error".to_string()).get_line_matcher().unwrap();
+        assert!(search_tar_gz(&archive, &matcher, &LineRange::default(), ContextLines::default(), 1000, DEFAULT_MAX_MEMBER_SIZE, None).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::search::{
+    merge_overlapping_context, read_text_file, search_text, ContextLines, Decoding, FileSearch, LineMatcher, LineRange,
+};
+
+/// 用 `{ ... }` 划分代码块的语言的扩展名
+const BRACE_LANGUAGES: &[&str] = &[
+    "c", "h", "cc", "cpp", "cxx", "hpp", "hh", "cs", "java", "kt", "kts", "scala", "go", "rs", "swift",
+    "js", "jsx", "mjs", "cjs", "ts", "tsx", "php", "css", "scss", "less", "dart", "groovy", "gradle",
+];
+
+/// 文件是否属于用花括号划分代码块的语言（按扩展名判断）
+pub fn is_brace_language(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| BRACE_LANGUAGES.iter().any(|lang| ext.eq_ignore_ascii_case(lang)))
+}
+
+/// 查找包含第 `line_idx` 行（从 0 开始）的最内层 `{ ... }` 代码块，返回首尾行的下标
+///
+/// 从匹配行末尾向上查找未配对的 `{`，再从它向下查找配对的 `}`。匹配行本身打开代码块时，
+/// 代码块从匹配行开始；匹配行是 `}` 时，代码块是它关闭的那一块。
+/// 这是启发式方法：字符串和注释中的花括号也会被计数。
+/// 整个代码块超过 `max_lines` 行、或在此范围内找不到配对的花括号时返回 None。
+pub fn enclosing_block(lines: &[&str], line_idx: usize, max_lines: usize) -> Option<(usize, usize)> {
+    let lowest = line_idx.saturating_sub(max_lines.saturating_sub(1));
+
+    // 向上查找未配对的 `{`，记录它所在的行和字节位置
+    let mut depth = 0usize;
+    let mut opener = None;
+    'up: for idx in (lowest..=line_idx).rev() {
+        for (col, byte) in lines[idx].bytes().enumerate().rev() {
+            match byte {
+                b'}' => depth += 1,
+                b'{' if depth == 0 => {
+                    opener = Some((idx, col));
+                    break 'up;
+                }
+                b'{' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    let (start, col) = opener?;
+
+    // 从 `{` 向下查找与它配对的 `}`
+    let highest = (start + max_lines).min(lines.len());
+    let mut depth = 0usize;
+    for (idx, line) in lines.iter().enumerate().take(highest).skip(start) {
+        let from = if idx == start { col } else { 0 };
+        for byte in line.bytes().skip(from) {
+            match byte {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((start, idx));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// 在单个文件中搜索，代码文件中的匹配以所在的 `{ ... }` 代码块作为上下文
+///
+/// 不是花括号语言的文件、或代码块超过 `max_block_lines` 行时，使用普通的 `context` 行上下文。
+/// `max_count` 不为 None 时最多返回这么多个匹配。
+#[allow(clippy::too_many_arguments)]
+pub fn search_file_with_block_context(
+    path: &Path,
+    matcher: &LineMatcher,
+    range: &LineRange,
+    decoding: Decoding,
+    context: ContextLines,
+    max_block_lines: usize,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+) -> Result<FileSearch> {
+    let (content, encoding) = read_text_file(path, decoding)?;
+    let mut search = search_text(
+        &path.to_string_lossy(),
+        &content,
+        encoding,
+        matcher,
+        range,
+        context,
+        max_matches_per_line,
+        max_count,
+    );
+    if search.results.is_empty() || !is_brace_language(path) {
+        return Ok(search);
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    for result in &mut search.results {
+        let line_idx = (result.line_number - 1) as usize;
+        if let Some((start, end)) = enclosing_block(&lines, line_idx, max_block_lines) {
+            result.context_before = lines[start..line_idx].iter().map(|s| s.to_string()).collect();
+            result.context_after = lines[line_idx + 1..=end].iter().map(|s| s.to_string()).collect();
+        }
+    }
+    merge_overlapping_context(&mut search.results);
+    Ok(search)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SearchPattern;
+
+    const CODE: &str = "\
+use std::io;
+
+fn main() {
+    let x = 1;
+    if x > 0 {
+        println!(\"positive\");
+    } else {
+        println!(\"other\");
+    }
+}
+";
+
+    #[test]
+    fn test_enclosing_block() {
+        let lines: Vec<&str> = CODE.lines().collect();
+
+        // `println!("positive")` 位于 if 分支中
+        assert_eq!(enclosing_block(&lines, 5, 50), Some((4, 6)));
+        // `} else {` 打开 else 分支
+        assert_eq!(enclosing_block(&lines, 6, 50), Some((6, 8)));
+        // 函数签名行打开整个函数体
+        assert_eq!(enclosing_block(&lines, 2, 50), Some((2, 9)));
+        assert_eq!(enclosing_block(&lines, 3, 50), Some((2, 9)));
+        // 不在任何代码块中
+        assert_eq!(enclosing_block(&lines, 0, 50), None);
+    }
+
+    #[test]
+    fn test_block_larger_than_limit_is_rejected() {
+        let lines: Vec<&str> = CODE.lines().collect();
+        assert_eq!(enclosing_block(&lines, 3, 8), Some((2, 9)));
+        assert_eq!(enclosing_block(&lines, 3, 7), None);
+
+        // 不配对的花括号不会导致无限扩展
+        let unbalanced: Vec<&str> = "fn broken() {\n    let a = 1;\n    let b = 2;\n".lines().collect();
+        assert_eq!(enclosing_block(&unbalanced, 1, 50), None);
+    }
+
+    #[test]
+    fn test_block_context_falls_back_for_other_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let code = temp_dir.path().join("main.rs");
+        let text = temp_dir.path().join("main.txt");
+        std::fs::write(&code, CODE).unwrap();
+        std::fs::write(&text, CODE).unwrap();
+        let matcher = SearchPattern::from_input("positive", false, false).unwrap().get_line_matcher().unwrap();
+
+        let search = search_file_with_block_context(&code, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::default(), 50, 100, None).unwrap();
+        assert_eq!(search.results[0].context_before, ["    if x > 0 {"]);
+        assert_eq!(search.results[0].context_after, ["    } else {"]);
+
+        // 非代码文件和超过上限的代码块使用普通上下文
+        let search = search_file_with_block_context(&text, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::default(), 50, 100, None).unwrap();
+        assert!(search.results[0].context_before.is_empty());
+
+        let search = search_file_with_block_context(&code, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::default(), 2, 100, None).unwrap();
+        assert!(search.results[0].context_before.is_empty());
+        assert!(search.results[0].context_after.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use super::file_walker::{scan_directory, FileFilter, WalkOptions};
+use super::search::{search_in_file, ContextLines, MatcherOptions, SearchPattern, SearchResult};
+use crate::infrastructure::Logger;
+
+/// 目录搜索选项
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// 匹配行前后显示的上下文行数
+    pub context: ContextLines,
+    /// 每行最多记录的匹配数
+    pub max_matches_per_line: usize,
+    /// 目录遍历选项
+    pub walk: WalkOptions,
+    /// 匹配器选项
+    pub matcher: MatcherOptions,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            context: ContextLines::default(),
+            max_matches_per_line: 1000,
+            walk: WalkOptions {
+                parallel: true,
+                respect_gitignore: true,
+                ..WalkOptions::default()
+            },
+            matcher: MatcherOptions::default(),
+        }
+    }
+}
+
+/// 搜索目录并收集全部结果
+///
+/// 在 [`scan_directory`] 之上封装了匹配与结果收集，适合小规模搜索和测试。
+/// 结果按路径和行号排序；无法读取的文件会被跳过。
+pub fn search_directory(
+    dir: &Path,
+    pattern: &SearchPattern,
+    filter: FileFilter,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    let matcher = pattern.get_line_matcher_with(&options.matcher)?;
+    let results = Mutex::new(Vec::new());
+
+    scan_directory(
+        dir,
+        filter,
+        &options.walk,
+        Arc::new(Logger::new(false)?),
+        |path| {
+            let file_results = search_in_file(
+                path,
+                &matcher,
+                options.context,
+                options.max_matches_per_line,
+                None,
+            )?;
+            if !file_results.is_empty() {
+                results.lock().unwrap().extend(file_results);
+            }
+            Ok(())
+        },
+        |_, _, _| {},
+    )?;
+
+    let mut results = results.into_inner().unwrap();
+    results.sort();
+    Ok(results)
+}
+
@@ -1,14 +1,21 @@
 use std::collections::HashSet;
-use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::fs::Metadata;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use crossbeam_channel::bounded;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::overrides::OverrideBuilder;
 use ignore::{WalkBuilder, DirEntry};
 use indicatif::{ProgressBar, ProgressStyle};
+use regex::Regex;
 
 // 使用infrastructure层的LoggerTrait
-use crate::infrastructure::LoggerTrait;
+use crate::infrastructure::{ErrorType, LoggerTrait};
 
 /// 文件筛选条件
 #[derive(Debug, Clone)]
@@ -17,6 +24,20 @@ pub struct FileFilter {
     pub max_size: Option<u64>,
     pub excluded_dirs: HashSet<String>,
     pub excluded_paths: HashSet<String>,
+    /// 排除路径的正则表达式，与使用 `/` 分隔的路径比较，匹配其中之一的路径被排除
+    pub excluded_regexes: Vec<Regex>,
+    /// 只处理在此时间之后修改的文件
+    pub modified_after: Option<SystemTime>,
+    /// 只处理在此时间之前修改的文件
+    pub modified_before: Option<SystemTime>,
+    /// 包含的 glob 模式（如 `*.rs`），不为空时只处理匹配其中之一的文件
+    pub include_globs: Vec<String>,
+    /// 排除的 glob 模式（如 `target/**`），匹配其中之一的文件被跳过
+    pub exclude_globs: Vec<String>,
+    include_set: Option<GlobSet>,
+    exclude_set: Option<GlobSet>,
+    /// 只处理扩展名（小写，不含点）在此集合中的文件，没有扩展名的文件也被跳过
+    pub extensions: Option<HashSet<String>>,
 }
 
 impl FileFilter {
@@ -32,13 +53,94 @@ impl FileFilter {
             max_size,
             excluded_dirs: excluded_dirs.into_iter().collect(),
             excluded_paths: excluded_paths.into_iter().collect(),
+            excluded_regexes: Vec::new(),
+            modified_after: None,
+            modified_before: None,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            include_set: None,
+            exclude_set: None,
+            extensions: None,
         }
     }
 
+    /// 只处理指定扩展名的文件（不区分大小写，可带前导的点），为空时不过滤
+    pub fn with_extensions(mut self, extensions: &[String]) -> Self {
+        let extensions: HashSet<String> = extensions.iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        self.extensions = (!extensions.is_empty()).then_some(extensions);
+        self
+    }
+
+    /// 检查文件扩展名是否符合要求
+    pub fn matches_extension(&self, path: &Path) -> bool {
+        let Some(extensions) = &self.extensions else {
+            return true;
+        };
+        path.extension()
+            .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()))
+    }
+
+    /// 按正则表达式排除路径，表达式无效时报错
+    pub fn with_exclude_regexes(mut self, patterns: &[String]) -> Result<Self> {
+        self.excluded_regexes = patterns.iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("无效的 --exclude-regex 正则表达式: {}", pattern))
+            })
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    /// 按 glob 模式包含或排除文件，模式无效时报错
+    pub fn with_globs(mut self, include_globs: Vec<String>, exclude_globs: Vec<String>) -> Result<Self> {
+        self.include_set = build_glob_set(&include_globs, "--include")?;
+        self.exclude_set = build_glob_set(&exclude_globs, "--exclude")?;
+        self.include_globs = include_globs;
+        self.exclude_globs = exclude_globs;
+        Ok(self)
+    }
+
+    /// 检查文件是否符合 glob 包含和排除模式
+    ///
+    /// 模式与文件名以及路径的每个后缀（`a/b/c.rs`、`b/c.rs`、`c.rs`）比较，因此不依赖搜索根目录的写法：
+    /// `target/**` 排除任意位置的 target 目录下的文件。
+    pub fn matches_globs(&self, path: &Path) -> bool {
+        let matches = |set: &GlobSet| path_suffixes(path).any(|suffix| set.is_match(suffix));
+        self.include_set.as_ref().is_none_or(matches) && !self.exclude_set.as_ref().is_some_and(matches)
+    }
+
+    /// 只处理在给定时间之后修改的文件
+    pub fn with_modified_after(mut self, modified_after: Option<SystemTime>) -> Self {
+        self.modified_after = modified_after;
+        self
+    }
+
+    /// 只处理在给定时间之前修改的文件
+    pub fn with_modified_before(mut self, modified_before: Option<SystemTime>) -> Self {
+        self.modified_before = modified_before;
+        self
+    }
+
+    /// 是否按修改时间过滤
+    pub fn has_time_filter(&self) -> bool {
+        self.modified_after.is_some() || self.modified_before.is_some()
+    }
+
+    /// 检查文件修改时间是否符合要求（无法获取修改时间时视为符合）
+    pub fn matches_modified(&self, modified: Option<SystemTime>) -> bool {
+        let Some(modified) = modified else {
+            return true;
+        };
+        self.modified_after.is_none_or(|after| modified > after)
+            && self.modified_before.is_none_or(|before| modified < before)
+    }
+
     /// 检查文件是否符合大小要求
     pub fn matches_size(&self, size: u64) -> bool {
-        let min_ok = self.min_size.map_or(true, |min| size >= min);
-        let max_ok = self.max_size.map_or(true, |max| size <= max);
+        let min_ok = self.min_size.is_none_or(|min| size >= min);
+        let max_ok = self.max_size.is_none_or(|max| size <= max);
         min_ok && max_ok
     }
 
@@ -68,6 +170,11 @@ impl FileFilter {
                 return true;
             }
         }
+
+        // 检查是否匹配排除路径的正则表达式
+        if self.excluded_regexes.iter().any(|regex| regex.is_match(&normalized_path)) {
+            return true;
+        }
         
         // 检查是否在排除目录中
         for component in path.components() {
@@ -83,43 +190,73 @@ impl FileFilter {
 
     /// 检查文件是否应该被处理
     pub fn should_process(&self, entry: &DirEntry) -> Result<bool> {
+        Ok(self.skip_reason(entry.path(), entry.metadata().ok().as_ref()).is_none())
+    }
+
+    /// 检查给定路径的文件是否应该被处理（用于不经过目录遍历的路径）
+    pub fn should_process_path(&self, path: &Path) -> Result<bool> {
+        Ok(self.skip_reason(path, std::fs::metadata(path).ok().as_ref()).is_none())
+    }
+
+    /// 返回文件被跳过的原因，应该处理时返回 None
+    pub fn skip_reason(&self, path: &Path, metadata: Option<&Metadata>) -> Option<&'static str> {
         // 检查路径排除
-        if self.is_path_excluded(entry.path()) {
-            return Ok(false);
+        if self.is_path_excluded(path) {
+            return Some("路径排除");
+        }
+
+        if !self.matches_globs(path) {
+            return Some("glob 过滤");
+        }
+
+        if !self.matches_extension(path) {
+            return Some("扩展名过滤");
         }
 
-        // 检查文件大小
-        if let Ok(metadata) = entry.metadata() {
+        if let Some(metadata) = metadata {
+            // 检查文件大小
             if !self.matches_size(metadata.len()) {
-                return Ok(false);
+                return Some("大小过滤");
+            }
+
+            // 检查修改时间
+            if !self.matches_modified(metadata.modified().ok()) {
+                return Some("时间过滤");
             }
         }
 
-        Ok(true)
+        None
     }
 }
 
+/// 编译 glob 模式，没有模式时返回 None
+fn build_glob_set(globs: &[String], option: &str) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).with_context(|| format!("无效的 {} 模式: {}", option, glob))?);
+    }
+    builder.build().map(Some).with_context(|| format!("无法编译 {} 模式", option))
+}
+
+/// 路径由普通部分组成的每个后缀，从完整路径到文件名
+fn path_suffixes(path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    let components: Vec<_> = path.components()
+        .filter(|component| matches!(component, std::path::Component::Normal(_)))
+        .collect();
+    (0..components.len()).map(move |start| components[start..].iter().collect())
+}
+
 /// 向后兼容的类型别名
 pub type FileSizeFilter = FileFilter;
 
-/// 扫描并执行回调函数处理文件
-pub fn scan_directory<F>(
-    dir: &Path,
-    filter: FileSizeFilter,
-    parallel: bool,
-    respect_gitignore: bool,
-    logger: Arc<dyn LoggerTrait>,
-    callback: F,
-) -> Result<(u64, u64)>
-where
-    F: Fn(&DirEntry) -> Result<()> + Send + Sync + 'static,
-{
-    let callback = Arc::new(callback);
-    let filter = Arc::new(filter);
-    let total_files = Arc::new(AtomicU64::new(0));
-    let processed_files = Arc::new(AtomicU64::new(0));
+/// 遍历中出现的路径集合
+pub type VisiblePaths = HashSet<PathBuf>;
 
-    // 创建进度条
+/// 创建进度条
+fn create_progress_bar() -> ProgressBar {
     let progress = ProgressBar::new_spinner();
     progress.set_style(
         ProgressStyle::default_spinner()
@@ -128,116 +265,527 @@ where
             .unwrap()
     );
     progress.set_message("已处理 0 文件");
+    progress
+}
+
+/// 回调返回此错误时停止整个扫描，已开始处理的文件不受影响
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct ScanAborted(pub String);
+
+/// 单个文件的处理流程：过滤、计数、更新进度、执行回调
+struct FileProcessor<F, E> {
+    callback: F,
+    report_error: E,
+    filter: FileFilter,
+    total_files: AtomicU64,
+    processed_files: AtomicU64,
+    aborted: AtomicBool,
+    /// 遍历中出现的路径（文件和目录），只在需要时记录
+    visible: Option<Mutex<HashSet<PathBuf>>>,
+    logger: Arc<dyn LoggerTrait>,
+    progress: ProgressBar,
+}
+
+impl<F, E> FileProcessor<F, E>
+where
+    F: Fn(&Path) -> Result<()>,
+    E: Fn(ErrorType, &Path, &str),
+{
+    fn new(callback: F, report_error: E, filter: FileFilter, logger: Arc<dyn LoggerTrait>) -> Self {
+        Self {
+            callback,
+            report_error,
+            filter,
+            total_files: AtomicU64::new(0),
+            processed_files: AtomicU64::new(0),
+            aborted: AtomicBool::new(false),
+            visible: None,
+            logger,
+            progress: create_progress_bar(),
+        }
+    }
+
+    /// 记录遍历中出现的所有路径
+    fn recording_visible(mut self) -> Self {
+        self.visible = Some(Mutex::new(HashSet::new()));
+        self
+    }
+
+    fn record_visible(&self, path: &Path) {
+        if let Some(visible) = &self.visible {
+            visible.lock().unwrap().insert(path.to_path_buf());
+        }
+    }
+
+    /// 处理单个文件，`metadata` 为遍历时取得的元数据，过滤检查和日志都使用它，不再重复读取
+    ///
+    /// 回调返回 [`ScanAborted`] 后不再处理任何文件，返回 false 通知调用方停止遍历。
+    fn process(&self, path: &Path, metadata: std::io::Result<Metadata>) -> bool {
+        if self.is_aborted() {
+            return false;
+        }
+
+        let logger = &self.logger;
+
+        // 检查是否应该处理此文件（包括排除规则和大小过滤）
+        if let Some(reason) = self.filter.skip_reason(path, metadata.as_ref().ok()) {
+            // 记录被过滤的文件
+            if logger.is_enabled() {
+                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+                let _ = logger.log_file(path, size, &format!("已跳过({})", reason));
+            }
+            return true;
+        }
+
+        // 无法获取修改时间的文件不按时间过滤，记录原因以免误以为已被过滤
+        if self.filter.has_time_filter() {
+            let modified_error = metadata.as_ref().ok().and_then(|metadata| metadata.modified().err());
+            if let Some(err) = modified_error.as_ref().or(metadata.as_ref().err()) {
+                let message = format!("无法获取修改时间，不按时间过滤: {}", err);
+                (self.report_error)(ErrorType::from_io_error(err, ErrorType::MetadataRead), path, &message);
+                if logger.is_enabled() {
+                    let _ = logger.log_message(&format!("{} {}", message, path.display()));
+                }
+            }
+        }
+
+        // 更新计数器
+        let current_total = self.total_files.fetch_add(1, Ordering::Relaxed) + 1;
+
+        // 更新进度条
+        self.progress.set_message(format!("已处理 {} 文件", current_total));
+        self.progress.tick();
+
+        // 记录文件处理
+        if logger.is_enabled() {
+            let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+            let _ = logger.log_file(path, size, "正在处理");
+        }
+
+        // 执行回调函数
+        if let Err(err) = (self.callback)(path) {
+            if err.is::<ScanAborted>() {
+                self.aborted.store(true, Ordering::Relaxed);
+                if logger.is_enabled() {
+                    let _ = logger.log_message(&format!("扫描中止: {}", err));
+                }
+                return false;
+            }
+
+            // 记录回调错误
+            if logger.is_enabled() {
+                let _ = logger.log_message(
+                    &format!("处理文件失败 {}: {}", path.display(), err)
+                );
+            }
+        } else {
+            self.processed_files.fetch_add(1, Ordering::Relaxed);
+        }
+
+        true
+    }
+
+    /// 是否已被回调中止
+    fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// 完成进度条并返回 (总文件数, 成功处理的文件数)
+    fn finish(&self) -> (u64, u64) {
+        let final_total = self.total_files.load(Ordering::Relaxed);
+        let final_processed = self.processed_files.load(Ordering::Relaxed);
 
-    // 创建文件遍历器
+        self.progress.finish_with_message(format!("完成! 已处理 {} 文件", final_total));
+
+        (final_total, final_processed)
+    }
+}
+
+/// 目录遍历选项
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// 是否并行遍历
+    pub parallel: bool,
+    /// 并行遍历的线程数，None 时使用所有 CPU；不并行时总是 1
+    pub threads: Option<usize>,
+    /// 是否遵守 .gitignore 规则
+    pub respect_gitignore: bool,
+    /// 是否只在同一文件系统内遍历
+    pub one_file_system: bool,
+    /// 即使被 .gitignore 排除也要搜索的文件或目录
+    pub unrestricted_paths: Vec<PathBuf>,
+    /// 最大遍历深度：0 只处理搜索目录下直接的文件，None 不限制
+    pub max_depth: Option<usize>,
+    /// 是否跟随符号链接进入其指向的目录；形成循环的链接由 ignore 检测并跳过
+    pub follow_links: bool,
+    /// 额外的忽略文件名（如 `.findignore`），按 gitignore 语法解析，不受 `respect_gitignore` 影响
+    pub custom_ignore_filename: Option<String>,
+}
+
+impl WalkOptions {
+    /// 遍历和搜索使用的线程数
+    pub fn thread_count(&self) -> usize {
+        if !self.parallel {
+            return 1;
+        }
+        self.threads.unwrap_or_else(num_cpus::get).max(1)
+    }
+
+    /// 解析强制包含的路径，返回相对于搜索目录的路径
+    ///
+    /// 不遵守 .gitignore 时所有路径本来就会被搜索，返回空列表。
+    pub(crate) fn unrestricted_relative_paths(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        if !self.respect_gitignore {
+            return Ok(Vec::new());
+        }
+
+        let root = dir.canonicalize()
+            .with_context(|| format!("无法访问搜索目录: {}", dir.display()))?;
+
+        self.unrestricted_paths
+            .iter()
+            .map(|path| {
+                let resolved = path.canonicalize()
+                    .with_context(|| format!("无法访问强制包含的路径: {}", path.display()))?;
+                resolved
+                    .strip_prefix(&root)
+                    .map(Path::to_path_buf)
+                    .map_err(|_| anyhow::anyhow!("强制包含的路径不在搜索目录中: {}", path.display()))
+            })
+            .collect()
+    }
+}
+
+/// 按搜索设置创建文件遍历器
+///
+/// `unrestricted` 中的路径（相对于 `dir`）通过覆盖规则从本次遍历中排除，
+/// 由调用方另行以不遵守 .gitignore 的方式遍历，避免重复搜索。
+pub(crate) fn new_walk_builder(dir: &Path, options: &WalkOptions, unrestricted: &[PathBuf]) -> Result<WalkBuilder> {
     let mut walker = WalkBuilder::new(dir);
     walker
         .hidden(false)
-        .git_ignore(respect_gitignore)
-        .git_global(respect_gitignore)
-        .git_exclude(respect_gitignore);
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .same_file_system(options.one_file_system)
+        .follow_links(options.follow_links)
+        // ignore 的深度 0 是搜索目录本身
+        .max_depth(options.max_depth.map(|depth| depth + 1));
+    if let Some(name) = &options.custom_ignore_filename {
+        walker.add_custom_ignore_filename(name);
+    }
+
+    if !unrestricted.is_empty() {
+        let mut overrides = OverrideBuilder::new(dir);
+        for relative in unrestricted {
+            overrides.add(&exclude_override_glob(relative))
+                .with_context(|| format!("无效的强制包含路径: {}", relative.display()))?;
+        }
+        walker.overrides(overrides.build().context("无法创建路径覆盖规则")?);
+    }
+
+    Ok(walker)
+}
+
+/// 生成排除某个相对路径的覆盖规则，如 `!/gen/out\[1\].rs`
+fn exclude_override_glob(relative: &Path) -> String {
+    let mut glob = String::from("!");
+    for component in relative.components() {
+        glob.push('/');
+        for c in component.as_os_str().to_string_lossy().chars() {
+            if matches!(c, '\\' | '*' | '?' | '[' | ']' | '{' | '}') {
+                glob.push('\\');
+            }
+            glob.push(c);
+        }
+    }
+    glob
+}
+
+/// 扫描并执行回调函数处理文件
+///
+/// 遵守 .gitignore 时，`unrestricted_paths` 中的路径会在主遍历之后单独遍历，不受忽略规则影响。
+/// 遍历错误和无法读取的元数据交给 `report_error`（错误类型、相关路径、说明），遍历继续进行。
+pub fn scan_directory<F, E>(
+    dir: &Path,
+    filter: FileSizeFilter,
+    options: &WalkOptions,
+    logger: Arc<dyn LoggerTrait>,
+    callback: F,
+    report_error: E,
+) -> Result<(u64, u64)>
+where
+    F: Fn(&Path) -> Result<()> + Send + Sync,
+    E: Fn(ErrorType, &Path, &str) + Send + Sync,
+{
+    let processor = FileProcessor::new(callback, report_error, filter, Arc::clone(&logger));
+    walk_directory(dir, options, &processor, &logger)?;
+    Ok(processor.finish())
+}
 
-    if parallel {
-        walker.threads(num_cpus::get());
+/// 与 [`scan_directory`] 相同，同时返回遍历中出现的所有路径（文件和目录）
+///
+/// 供 [`find_excluded`](super::ignore_report::find_excluded) 找出被忽略规则排除的路径，
+/// 无需为此再遍历一次。遍历被中止时记录不完整，返回 None。
+pub fn scan_directory_recording_visible<F, E>(
+    dir: &Path,
+    filter: FileSizeFilter,
+    options: &WalkOptions,
+    logger: Arc<dyn LoggerTrait>,
+    callback: F,
+    report_error: E,
+) -> Result<((u64, u64), Option<VisiblePaths>)>
+where
+    F: Fn(&Path) -> Result<()> + Send + Sync,
+    E: Fn(ErrorType, &Path, &str) + Send + Sync,
+{
+    let processor = FileProcessor::new(callback, report_error, filter, Arc::clone(&logger))
+        .recording_visible();
+    walk_directory(dir, options, &processor, &logger)?;
+    let counts = processor.finish();
+    let visible = if processor.is_aborted() {
+        None
     } else {
-        walker.threads(1);
+        processor.visible.map(|visible| visible.into_inner().unwrap())
+    };
+    Ok((counts, visible))
+}
+
+/// 按遍历选项遍历目录，强制包含的路径在主遍历之后单独遍历
+fn walk_directory<F, E>(
+    dir: &Path,
+    options: &WalkOptions,
+    processor: &FileProcessor<F, E>,
+    logger: &Arc<dyn LoggerTrait>,
+) -> Result<()>
+where
+    F: Fn(&Path) -> Result<()> + Send + Sync,
+    E: Fn(ErrorType, &Path, &str) + Send + Sync,
+{
+    let unrestricted = options.unrestricted_relative_paths(dir)?;
+
+    // 整个搜索目录都被强制包含时，等同于不遵守 .gitignore
+    if unrestricted.iter().any(|relative| relative.as_os_str().is_empty()) {
+        let options = WalkOptions { respect_gitignore: false, ..options.clone() };
+        run_walk(new_walk_builder(dir, &options, &[])?, options.thread_count(), processor, logger);
+        return Ok(());
     }
 
+    run_walk(new_walk_builder(dir, options, &unrestricted)?, options.thread_count(), processor, logger);
+
+    let unrestricted_options = WalkOptions {
+        respect_gitignore: false,
+        unrestricted_paths: Vec::new(),
+        ..options.clone()
+    };
+    for relative in &unrestricted {
+        if processor.is_aborted() {
+            break;
+        }
+        let mut walker = new_walk_builder(&dir.join(relative), &unrestricted_options, &[])?;
+        // 深度限制从搜索目录算起，超出深度的强制包含路径不搜索
+        if let Some(max_depth) = options.max_depth {
+            match (max_depth + 1).checked_sub(relative.components().count()) {
+                Some(depth) => {
+                    walker.max_depth(Some(depth));
+                }
+                None => continue,
+            }
+        }
+        run_walk(walker, options.thread_count(), processor, logger);
+    }
+
+    Ok(())
+}
+
+/// 执行一次遍历，将其中的文件交给处理器
+fn run_walk<F, E>(
+    mut walker: WalkBuilder,
+    threads: usize,
+    processor: &FileProcessor<F, E>,
+    logger: &Arc<dyn LoggerTrait>,
+) where
+    F: Fn(&Path) -> Result<()> + Send + Sync,
+    E: Fn(ErrorType, &Path, &str) + Send + Sync,
+{
+    walker.threads(threads);
+
     // 执行并行遍历
-    let logger_clone = Arc::clone(&logger);
     walker.build_parallel().run(|| {
-        let callback = Arc::clone(&callback);
-        let filter = Arc::clone(&filter);
-        let total_files = Arc::clone(&total_files);
-        let processed_files = Arc::clone(&processed_files);
-        let logger = Arc::clone(&logger_clone);
-        let progress = progress.clone();
+        let logger = Arc::clone(logger);
 
         Box::new(move |result| {
             let entry = match result {
                 Ok(entry) => entry,
                 Err(err) => {
-                    // 记录遍历错误
+                    // 形成循环的符号链接是预期的跳过，只记录到日志；其他遍历错误同时报告
+                    let message = match symlink_loop(&err) {
+                        Some((child, ancestor)) => format!(
+                            "跳过形成循环的符号链接: {} -> {}",
+                            child.display(),
+                            ancestor.display()
+                        ),
+                        None => {
+                            let path = walk_error_path(&err).unwrap_or(Path::new(""));
+                            (processor.report_error)(walk_error_type(&err), path, &err.to_string());
+                            format!("遍历错误: {}", err)
+                        }
+                    };
                     if logger.is_enabled() {
-                        let _ = logger.log_message(&format!("遍历错误: {}", err));
+                        let _ = logger.log_message(&message);
                     }
                     return ignore::WalkState::Continue;
                 }
             };
 
+            processor.record_visible(entry.path());
+
             // 只处理文件
-            if !entry.file_type().map_or(false, |ft| ft.is_file()) {
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
                 return ignore::WalkState::Continue;
             }
-            
-            // 检查是否应该处理此文件（包括排除规则和大小过滤）
-            match filter.should_process(&entry) {
-                Ok(false) => {
-                    // 记录被过滤的文件
-                    if logger.is_enabled() {
-                        let reason = if filter.is_path_excluded(entry.path()) {
-                            "已跳过(路径排除)"
-                        } else {
-                            "已跳过(大小过滤)"
-                        };
-                        
-                        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                        let _ = logger.log_file(entry.path(), size, reason);
+
+            let metadata = entry.metadata().map_err(|err| {
+                let message = err.to_string();
+                err.into_io_error().unwrap_or_else(|| std::io::Error::other(message))
+            });
+            if !processor.process(entry.path(), metadata) {
+                return ignore::WalkState::Quit;
+            }
+
+            ignore::WalkState::Continue
+        })
+    });
+}
+
+/// 跟随符号链接时检测到的循环，返回 (链接路径, 它指向的祖先目录)
+fn symlink_loop(err: &ignore::Error) -> Option<(&Path, &Path)> {
+    match err {
+        ignore::Error::Loop { ancestor, child } => Some((child.as_path(), ancestor.as_path())),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
+
+/// 遍历错误的分类：没有访问权限的目录单独计数，其余归为遍历错误
+pub fn walk_error_type(err: &ignore::Error) -> ErrorType {
+    err.io_error()
+        .map(|io_err| ErrorType::from_io_error(io_err, ErrorType::Traversal))
+        .unwrap_or(ErrorType::Traversal)
+}
+
+/// 遍历错误涉及的路径
+fn walk_error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path.as_path()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => walk_error_path(err),
+        _ => None,
+    }
+}
+
+/// 从读取器中逐条读取文件路径并执行回调函数处理文件
+///
+/// 路径以 `separator` 分隔（通常为 `\n` 或 `\0`）。读取与搜索以流水线方式进行：
+/// 每读到一条路径就立即交给工作线程处理，通道容量有限，因此搜索跟不上时会阻塞读取。
+/// 不存在的路径作为文件读取错误交给 `report_error`，存在但不是文件的路径只记录到日志。
+pub fn scan_paths<R, F, E>(
+    reader: R,
+    separator: u8,
+    filter: FileSizeFilter,
+    threads: usize,
+    logger: Arc<dyn LoggerTrait>,
+    callback: F,
+    report_error: E,
+) -> Result<(u64, u64)>
+where
+    R: BufRead,
+    F: Fn(&Path) -> Result<()> + Send + Sync,
+    E: Fn(ErrorType, &Path, &str) + Send + Sync,
+{
+    let processor = FileProcessor::new(callback, report_error, filter, Arc::clone(&logger));
+    let (tx, rx) = bounded::<PathBuf>(100);
+
+    std::thread::scope(|scope| -> Result<()> {
+        for _ in 0..threads.max(1) {
+            let rx = rx.clone();
+            let processor = &processor;
+            let logger = &logger;
+
+            scope.spawn(move || {
+                while let Ok(path) = rx.recv() {
+                    if !path.exists() {
+                        (processor.report_error)(ErrorType::FileRead, &path, "文件不存在");
+                        continue;
                     }
-                    return ignore::WalkState::Continue;
-                }
-                Err(err) => {
-                    // 记录错误
-                    if logger.is_enabled() {
-                        let _ = logger.log_message(
-                            &format!("检查文件过滤条件失败 {}: {}", entry.path().display(), err)
-                        );
+                    if !path.is_file() {
+                        if logger.is_enabled() {
+                            let _ = logger.log_message(
+                                &format!("跳过非文件路径: {}", path.display())
+                            );
+                        }
+                        continue;
+                    }
+
+                    if !processor.process(&path, std::fs::metadata(&path)) {
+                        break;
                     }
-                    return ignore::WalkState::Continue;
-                }
-                Ok(true) => {
-                    // 继续处理
                 }
-            }
-            
-            // 获取文件大小用于日志记录
-            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            });
+        }
+        // 所有工作线程退出后发送失败，读取随之停止
+        drop(rx);
 
-            // 更新计数器
-            let current_total = total_files.fetch_add(1, Ordering::Relaxed) + 1;
-            
-            // 更新进度条
-            progress.set_message(format!("已处理 {} 文件", current_total));
-            progress.tick();
+        // 在当前线程读取路径，读取失败时关闭通道让工作线程退出
+        let read_result = read_paths(reader, separator, |path| tx.send(path).is_ok());
+        drop(tx);
+        read_result
+    })?;
 
-            // 记录文件处理
-            if logger.is_enabled() {
-                let _ = logger.log_file(entry.path(), size, "正在处理");
-            }
+    Ok(processor.finish())
+}
 
-            // 执行回调函数
-            if let Err(err) = callback(&entry) {
-                // 记录回调错误
-                if logger.is_enabled() {
-                    let _ = logger.log_message(
-                        &format!("处理文件失败 {}: {}", entry.path().display(), err)
-                    );
-                }
-            } else {
-                processed_files.fetch_add(1, Ordering::Relaxed);
-            }
+/// 按分隔符读取路径，`send` 返回 false 时停止读取
+fn read_paths<R, S>(mut reader: R, separator: u8, mut send: S) -> Result<()>
+where
+    R: BufRead,
+    S: FnMut(PathBuf) -> bool,
+{
+    let mut buf = Vec::new();
 
-            ignore::WalkState::Continue
-        })
-    });
+    loop {
+        buf.clear();
+        if reader.read_until(separator, &mut buf).context("读取路径列表失败")? == 0 {
+            return Ok(());
+        }
+
+        // 去掉分隔符以及 Windows 换行符
+        while matches!(buf.last(), Some(&b) if b == separator || (separator == b'\n' && b == b'\r')) {
+            buf.pop();
+        }
+        if buf.is_empty() {
+            continue;
+        }
+
+        if !send(path_from_bytes(buf.clone())) {
+            return Ok(());
+        }
+    }
+}
 
-    // 完成进度条
-    let final_total = total_files.load(Ordering::Relaxed);
-    let final_processed = processed_files.load(Ordering::Relaxed);
-    
-    progress.finish_with_message(format!("完成! 已处理 {} 文件", final_total));
+/// 由路径列表中的字节构造路径：Unix 上文件名可以是任意字节，原样使用
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
 
-    Ok((final_total, final_processed))
+/// 由路径列表中的字节构造路径：其他平台的路径按 UTF-8 解释，无效的字节序列替换为 U+FFFD
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
 }
 
 
@@ -245,7 +793,6 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_file_filter_creation() {
@@ -284,4 +831,330 @@ mod tests {
         assert!(filter.is_path_excluded(&PathBuf::from("test.txt")));
         assert!(!filter.is_path_excluded(&PathBuf::from("src/main.rs")));
     }
+
+    #[test]
+    fn test_path_exclusion_by_regex() {
+        let filter = FileFilter::new(None, None, vec![], vec!["test.txt".to_string()])
+            .with_exclude_regexes(&[".*/generated/.*".to_string(), r"\.snap$".to_string()])
+            .unwrap();
+
+        assert!(filter.is_path_excluded(&PathBuf::from("./src/generated/schema.rs")));
+        assert!(filter.is_path_excluded(Path::new("src\\generated\\schema.rs")));
+        assert!(filter.is_path_excluded(&PathBuf::from("tests/output.snap")));
+        // 原有的字符串规则仍然生效
+        assert!(filter.is_path_excluded(&PathBuf::from("test.txt")));
+        assert!(!filter.is_path_excluded(&PathBuf::from("generated/schema.rs")));
+        assert!(!filter.is_path_excluded(&PathBuf::from("src/main.rs")));
+
+        let err = FileFilter::new(None, None, vec![], vec![])
+            .with_exclude_regexes(&["(".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("--exclude-regex"));
+    }
+
+    #[test]
+    fn test_glob_filtering() {
+        let filter = FileFilter::new(None, None, vec![], vec![])
+            .with_globs(vec!["*.rs".to_string()], vec!["target/**".to_string(), "*.min.js".to_string()])
+            .unwrap();
+
+        assert!(filter.matches_globs(Path::new("./src/main.rs")));
+        assert!(!filter.matches_globs(Path::new("README.md")));
+        // 排除模式与路径的任意后缀比较
+        assert!(!filter.matches_globs(Path::new("/work/target/debug/build.rs")));
+        assert!(filter.matches_globs(Path::new("/work/src/target.rs")));
+        assert_eq!(filter.skip_reason(Path::new("target/gen.rs"), None), Some("glob 过滤"));
+
+        let exclude_only = FileFilter::new(None, None, vec![], vec![])
+            .with_globs(vec![], vec!["*.min.js".to_string()])
+            .unwrap();
+        assert!(exclude_only.matches_globs(Path::new("app.js")));
+        assert!(!exclude_only.matches_globs(Path::new("dist/app.min.js")));
+
+        assert!(FileFilter::new(None, None, vec![], vec![]).with_globs(vec!["a[".to_string()], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_extension_filtering() {
+        let filter = FileFilter::new(None, None, vec![], vec![])
+            .with_extensions(&["rs".to_string(), ".TOML".to_string()]);
+
+        assert!(filter.matches_extension(Path::new("src/main.rs")));
+        assert!(filter.matches_extension(Path::new("Cargo.toml")));
+        assert!(!filter.matches_extension(Path::new("README.md")));
+        assert_eq!(filter.skip_reason(Path::new("README.md"), None), Some("扩展名过滤"));
+
+        // 不区分大小写
+        assert!(filter.matches_extension(Path::new("LIB.RS")));
+        assert!(filter.matches_extension(Path::new("Config.Toml")));
+
+        // 有过滤时没有扩展名的文件被跳过，没有过滤时都处理
+        assert!(!filter.matches_extension(Path::new("Makefile")));
+        assert!(!filter.matches_extension(Path::new(".rs")));
+        let unfiltered = FileFilter::new(None, None, vec![], vec![]).with_extensions(&[]);
+        assert!(unfiltered.matches_extension(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn test_modified_within_and_before() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let hour = std::time::Duration::from_secs(3600);
+        let recent = temp_dir.path().join("recent.log");
+        let old = temp_dir.path().join("old.log");
+        for (path, age) in [(&recent, 3 * hour), (&old, 72 * hour)] {
+            std::fs::write(path, "x").unwrap();
+            filetime::set_file_mtime(path, filetime::FileTime::from_system_time(now - age)).unwrap();
+        }
+
+        // 最近 1 天内修改
+        let within = FileFilter::new(None, None, vec![], vec![])
+            .with_modified_after(Some(now - 24 * hour));
+        assert!(within.should_process_path(&recent).unwrap());
+        assert!(!within.should_process_path(&old).unwrap());
+
+        // 1 天之前修改
+        let before = FileFilter::new(None, None, vec![], vec![])
+            .with_modified_before(Some(now - 24 * hour));
+        assert!(!before.should_process_path(&recent).unwrap());
+        assert!(before.should_process_path(&old).unwrap());
+
+        // 两者组合为时间窗口
+        let window = before.with_modified_after(Some(now - 48 * hour));
+        assert!(!window.should_process_path(&recent).unwrap());
+        assert!(!window.should_process_path(&old).unwrap());
+
+        // 无法获取元数据时保留文件
+        assert!(window.skip_reason(&temp_dir.path().join("missing.log"), None).is_none());
+    }
+
+    #[test]
+    fn test_read_paths_separators() {
+        let mut paths = Vec::new();
+        read_paths(&b"a.txt\r\n\nsub/b.txt\n"[..], b'\n', |p| { paths.push(p); true }).unwrap();
+        assert_eq!(paths, vec![PathBuf::from("a.txt"), PathBuf::from("sub/b.txt")]);
+
+        let mut paths = Vec::new();
+        read_paths(&b"with\nnewline\0c.txt\0"[..], 0, |p| { paths.push(p); true }).unwrap();
+        assert_eq!(paths, vec![PathBuf::from("with\nnewline"), PathBuf::from("c.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_paths_keeps_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join(std::ffi::OsStr::from_bytes(b"f\xff.txt"));
+        std::fs::write(&path, "needle").unwrap();
+        let mut list = path.as_os_str().as_bytes().to_vec();
+        list.push(0);
+
+        let mut paths = Vec::new();
+        read_paths(&list[..], 0, |p| { paths.push(p); true }).unwrap();
+        assert_eq!(paths, vec![path.clone()]);
+
+        let filter = FileFilter::new(None, None, vec![], vec![]);
+        let logger: Arc<dyn LoggerTrait> = Arc::new(crate::infrastructure::Logger::new(false).unwrap());
+        let (total, processed) = scan_paths(&list[..], 0, filter, 1, logger, |_| Ok(()), |_, _, _| {}).unwrap();
+        assert_eq!((total, processed), (1, 1));
+    }
+
+    #[test]
+    fn test_modified_after_filtering() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("fresh.txt");
+        std::fs::write(&file_path, "x").unwrap();
+
+        let hour = std::time::Duration::from_secs(3600);
+        let past = FileFilter::new(None, None, vec![], vec![])
+            .with_modified_after(Some(SystemTime::now() - hour));
+        assert!(past.should_process_path(&file_path).unwrap());
+
+        let future = FileFilter::new(None, None, vec![], vec![])
+            .with_modified_after(Some(SystemTime::now() + hour));
+        assert!(!future.should_process_path(&file_path).unwrap());
+        let metadata = std::fs::metadata(&file_path).unwrap();
+        assert_eq!(future.skip_reason(&file_path, Some(&metadata)), Some("时间过滤"));
+
+        // 无法获取修改时间时不过滤
+        assert!(future.matches_modified(None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("main.rs"), "x").unwrap();
+        std::fs::write(outside.path().join("linked.rs"), "x").unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+        // 指向祖先目录的链接形成循环
+        std::os::unix::fs::symlink(root, root.join("loop")).unwrap();
+
+        let scan = |follow_links: bool| {
+            let options = WalkOptions { follow_links, ..WalkOptions::default() };
+            let visited = std::sync::Mutex::new(Vec::new());
+            let filter = FileFilter::new(None, None, vec![], vec![]);
+            let logger = Arc::new(crate::infrastructure::Logger::new(false).unwrap());
+            scan_directory(root, filter, &options, logger, |path| {
+                visited.lock().unwrap().push(path.strip_prefix(root).unwrap().to_path_buf());
+                Ok(())
+            }, |_, _, _| {})
+            .unwrap();
+            let mut visited = visited.into_inner().unwrap();
+            visited.sort();
+            visited
+        };
+
+        assert_eq!(scan(false), vec![PathBuf::from("main.rs")]);
+        // 循环链接被跳过，每个文件只处理一次
+        assert_eq!(scan(true), vec![Path::new("link").join("linked.rs"), PathBuf::from("main.rs")]);
+    }
+
+    #[test]
+    fn test_thread_count() {
+        let options = WalkOptions { parallel: true, threads: Some(3), ..WalkOptions::default() };
+        assert_eq!(options.thread_count(), 3);
+        assert_eq!(WalkOptions { threads: None, ..options.clone() }.thread_count(), num_cpus::get());
+        // 不并行时忽略线程数
+        assert_eq!(WalkOptions { parallel: false, ..options }.thread_count(), 1);
+    }
+
+    #[test]
+    fn test_walk_error_classification() {
+        use std::io::{Error, ErrorKind};
+
+        let denied = ignore::Error::WithPath {
+            path: PathBuf::from("secret"),
+            err: Box::new(ignore::Error::Io(Error::from(ErrorKind::PermissionDenied))),
+        };
+        assert_eq!(walk_error_type(&denied), ErrorType::PermissionDenied);
+        assert_eq!(walk_error_path(&denied), Some(Path::new("secret")));
+
+        let other = ignore::Error::WithDepth {
+            depth: 1,
+            err: Box::new(ignore::Error::Io(Error::from(ErrorKind::NotFound))),
+        };
+        assert_eq!(walk_error_type(&other), ErrorType::Traversal);
+        assert_eq!(walk_error_path(&other), None);
+    }
+
+    #[test]
+    fn test_custom_ignore_file_excludes_subdirectory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("fixtures")).unwrap();
+        std::fs::write(root.join(".findignore"), "fixtures/\n").unwrap();
+        std::fs::write(root.join("main.rs"), "x").unwrap();
+        std::fs::write(root.join("fixtures").join("big.json"), "x").unwrap();
+
+        let scan = |custom_ignore_filename: Option<String>| {
+            // 不遵守 .gitignore 时自定义忽略文件仍然生效
+            let options = WalkOptions { custom_ignore_filename, ..WalkOptions::default() };
+            let visited = std::sync::Mutex::new(Vec::new());
+            let filter = FileFilter::new(None, None, vec![], vec![".findignore".to_string()]);
+            let logger = Arc::new(crate::infrastructure::Logger::new(false).unwrap());
+            scan_directory(root, filter, &options, logger, |path| {
+                visited.lock().unwrap().push(path.strip_prefix(root).unwrap().to_path_buf());
+                Ok(())
+            }, |_, _, _| {})
+            .unwrap();
+            let mut visited = visited.into_inner().unwrap();
+            visited.sort();
+            visited
+        };
+
+        assert_eq!(scan(Some(".findignore".to_string())), vec![PathBuf::from("main.rs")]);
+        assert_eq!(scan(None), vec![Path::new("fixtures").join("big.json"), PathBuf::from("main.rs")]);
+    }
+
+    #[test]
+    fn test_unrestricted_path_overrides_gitignore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join(".git")).unwrap();
+        std::fs::create_dir(root.join("gen")).unwrap();
+        std::fs::write(root.join(".gitignore"), "gen/\n*.log\n").unwrap();
+        std::fs::write(root.join("main.rs"), "x").unwrap();
+        std::fs::write(root.join("app.log"), "x").unwrap();
+        std::fs::write(root.join("gen").join("schema.rs"), "x").unwrap();
+
+        let scan = |unrestricted_paths: Vec<PathBuf>| {
+            let options = WalkOptions {
+                respect_gitignore: true,
+                unrestricted_paths,
+                ..WalkOptions::default()
+            };
+            let visited = std::sync::Mutex::new(Vec::new());
+            let filter = FileFilter::new(None, None, vec![".git".to_string()], vec![]);
+            let logger = Arc::new(crate::infrastructure::Logger::new(false).unwrap());
+            scan_directory(root, filter, &options, logger, |path| {
+                let relative = path.strip_prefix(root).unwrap().to_path_buf();
+                visited.lock().unwrap().push(relative);
+                Ok(())
+            }, |_, _, _| {})
+            .unwrap();
+            let mut visited = visited.into_inner().unwrap();
+            visited.sort();
+            visited
+        };
+
+        let normal = scan(vec![]);
+        assert!(!normal.contains(&Path::new("gen").join("schema.rs")));
+
+        // 被忽略的目录通过强制包含变得可搜索，且不会重复处理
+        let unrestricted = scan(vec![root.join("gen"), root.join("main.rs")]);
+        assert_eq!(
+            unrestricted,
+            vec![
+                PathBuf::from(".gitignore"),
+                Path::new("gen").join("schema.rs"),
+                PathBuf::from("main.rs"),
+            ]
+        );
+
+        // 不在搜索目录中的路径报错
+        let outside = tempfile::tempdir().unwrap();
+        let options = WalkOptions {
+            respect_gitignore: true,
+            unrestricted_paths: vec![outside.path().to_path_buf()],
+            ..WalkOptions::default()
+        };
+        assert!(options.unrestricted_relative_paths(root).is_err());
+    }
+
+    #[test]
+    fn test_scan_aborted_stops_walk_and_path_reading() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut paths = String::new();
+        for i in 0..20 {
+            let path = temp_dir.path().join(format!("{}.txt", i));
+            std::fs::write(&path, "x").unwrap();
+            paths.push_str(&format!("{}\n", path.display()));
+        }
+
+        let abort_after_two = || {
+            let calls = AtomicU64::new(0);
+            move |_: &Path| -> Result<()> {
+                if calls.fetch_add(1, Ordering::Relaxed) + 1 >= 2 {
+                    return Err(ScanAborted("错误数达到上限 2".to_string()).into());
+                }
+                Ok(())
+            }
+        };
+        let filter = FileFilter::new(None, None, vec![], vec![]);
+        let logger: Arc<dyn LoggerTrait> = Arc::new(crate::infrastructure::Logger::new(false).unwrap());
+
+        let options = WalkOptions::default();
+        let (total, processed) =
+            scan_directory(temp_dir.path(), filter.clone(), &options, Arc::clone(&logger), abort_after_two(), |_, _, _| {})
+                .unwrap();
+        assert_eq!((total, processed), (2, 1));
+
+        let (total, processed) =
+            scan_paths(paths.as_bytes(), b'\n', filter, 1, logger, abort_after_two(), |_, _, _| {}).unwrap();
+        assert_eq!((total, processed), (2, 1));
+    }
 }
@@ -0,0 +1,214 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use super::search::{matched_text, select_line, LineMatcher, SearchResult};
+
+/// 一次轮询的结果
+#[derive(Debug, Default)]
+pub struct FollowPoll {
+    /// 新增完整行中的匹配
+    pub results: Vec<SearchResult>,
+    /// 本次轮询前文件被截断或替换，已从头重新读取
+    pub rotated: bool,
+}
+
+/// 跟踪不断增长的文件（类似 `tail -f | grep`），每次轮询只搜索新追加的完整行
+///
+/// 行号在多次轮询之间连续递增。文件被截断（大小小于已读位置）或被替换（inode 改变）时视为日志轮转，
+/// 重新打开文件并从第 1 行开始。跟踪模式不收集上下文行。
+pub struct FileFollower {
+    path: PathBuf,
+    matcher: LineMatcher,
+    max_matches_per_line: usize,
+    file: Option<File>,
+    identity: Option<FileIdentity>,
+    offset: u64,
+    line_number: u64,
+    /// 尚未以换行符结尾的行
+    pending: Vec<u8>,
+}
+
+impl FileFollower {
+    pub fn new(path: &Path, matcher: LineMatcher, max_matches_per_line: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            matcher,
+            max_matches_per_line,
+            file: None,
+            identity: None,
+            offset: 0,
+            line_number: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// 读取自上次轮询以来追加的内容并返回其中的匹配，首次轮询搜索现有的全部内容
+    ///
+    /// 文件暂时不存在（轮转进行中）时返回空结果，等待下次轮询。
+    pub fn poll(&mut self) -> Result<FollowPoll> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && self.file.is_some() => {
+                return Ok(FollowPoll::default());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("无法访问文件: {}", self.path.display()));
+            }
+        };
+
+        let mut poll = FollowPoll::default();
+        let identity = FileIdentity::of(&metadata);
+        let replaced = self.identity.is_some() && identity.is_some() && self.identity != identity;
+        if self.file.is_some() && (replaced || metadata.len() < self.offset) {
+            self.file = None;
+            self.offset = 0;
+            self.line_number = 0;
+            self.pending.clear();
+            poll.rotated = true;
+        }
+
+        if self.file.is_none() {
+            let file = File::open(&self.path)
+                .with_context(|| format!("无法打开文件: {}", self.path.display()))?;
+            self.file = Some(file);
+            self.identity = identity;
+        }
+
+        if metadata.len() == self.offset {
+            return Ok(poll);
+        }
+
+        let file = self.file.as_mut().expect("文件已打开");
+        file.seek(SeekFrom::Start(self.offset))
+            .with_context(|| format!("无法定位文件: {}", self.path.display()))?;
+        let read = file.read_to_end(&mut self.pending)
+            .with_context(|| format!("无法读取文件: {}", self.path.display()))?;
+        self.offset += read as u64;
+
+        // 只处理完整的行，最后一个换行符之后的内容留到下次
+        let Some(last_newline) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Ok(poll);
+        };
+        // 未处理的内容紧接在已读位置之前
+        let mut line_offset = self.offset - self.pending.len() as u64;
+        let remainder = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, remainder);
+
+        for raw_line in complete[..complete.len() - 1].split(|&b| b == b'\n') {
+            self.line_number += 1;
+            let current_offset = line_offset;
+            line_offset += raw_line.len() as u64 + 1;
+
+            let line = String::from_utf8_lossy(raw_line.strip_suffix(b"\r").unwrap_or(raw_line)).into_owned();
+            if let Some((matches, matches_truncated)) =
+                select_line(&self.matcher, line.as_bytes(), self.max_matches_per_line)
+            {
+                poll.results.push(SearchResult {
+                    path: self.path.to_string_lossy().to_string(),
+                    line_number: self.line_number,
+                    matched_text: matched_text(&line, &matches),
+                    line,
+                    context_before: Vec::new(),
+                    context_after: Vec::new(),
+                    matches,
+                    matches_truncated,
+                    total_lines: self.line_number,
+                    line_offset: current_offset,
+                    encoding: None,
+                    is_binary: false,
+                });
+            }
+        }
+
+        Ok(poll)
+    }
+}
+
+/// 用于识别文件是否被替换的标识（设备号 + inode），非 Unix 平台只能依赖大小检测
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    dev: u64,
+    ino: u64,
+}
+
+impl FileIdentity {
+    #[cfg(unix)]
+    fn of(metadata: &fs::Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self { dev: metadata.dev(), ino: metadata.ino() })
+    }
+
+    #[cfg(not(unix))]
+    fn of(_metadata: &fs::Metadata) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SearchPattern;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn follower(path: &Path) -> FileFollower {
+        let matcher = SearchPattern::Text("error".to_string()).get_line_matcher().unwrap();
+        FileFollower::new(path, matcher, 1000)
+    }
+
+    fn line_numbers(poll: &FollowPoll) -> Vec<u64> {
+        poll.results.iter().map(|r| r.line_number).collect()
+    }
+
+    #[test]
+    fn test_follow_appended_lines() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("app.log");
+        fs::write(&path, "start\nerror one\n").unwrap();
+
+        let mut follower = follower(&path);
+        assert_eq!(line_numbers(&follower.poll().unwrap()), vec![2]);
+        assert!(follower.poll().unwrap().results.is_empty());
+
+        // 未写完的行等到换行符出现后再匹配，行号接着上次继续
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"ok\nerror tw").unwrap();
+        assert!(follower.poll().unwrap().results.is_empty());
+        file.write_all(b"o\r\n").unwrap();
+        let poll = follower.poll().unwrap();
+        assert_eq!(line_numbers(&poll), vec![4]);
+        assert_eq!(poll.results[0].line, "error two");
+        assert!(!poll.rotated);
+    }
+
+    #[test]
+    fn test_follow_truncated_and_replaced_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("app.log");
+        fs::write(&path, "a\nb\nerror\n").unwrap();
+
+        let mut follower = follower(&path);
+        assert_eq!(line_numbers(&follower.poll().unwrap()), vec![3]);
+
+        // 截断后从第 1 行重新开始
+        fs::write(&path, "error\n").unwrap();
+        let poll = follower.poll().unwrap();
+        assert!(poll.rotated);
+        assert_eq!(line_numbers(&poll), vec![1]);
+
+        // 轮转期间文件暂时不存在
+        fs::rename(&path, temp_dir.path().join("app.log.1")).unwrap();
+        assert!(follower.poll().unwrap().results.is_empty());
+
+        // 新文件比已读位置更长时依靠 inode 识别替换
+        fs::write(&path, "x\nerror again\nmore\n").unwrap();
+        let poll = follower.poll().unwrap();
+        if cfg!(unix) {
+            assert!(poll.rotated);
+            assert_eq!(line_numbers(&poll), vec![2]);
+        }
+    }
+}
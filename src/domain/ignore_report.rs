@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+
+use super::file_walker::{FileFilter, WalkOptions};
+
+/// 被忽略规则排除的路径
+#[derive(Debug, Clone)]
+pub struct ExcludedEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// 排除该路径的规则，无法确定时为 None
+    pub rule: Option<IgnoreRule>,
+}
+
+/// 一条忽略规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    /// 规则所在的忽略文件
+    pub source: Option<PathBuf>,
+    /// 规则在忽略文件中的行号（1 基）
+    pub line: Option<usize>,
+    /// 规则原文
+    pub pattern: String,
+}
+
+impl IgnoreRule {
+    /// 格式化为 `文件:行号: 规则`
+    pub fn format(&self) -> String {
+        match (&self.source, self.line) {
+            (Some(source), Some(line)) => format!("{}:{}: {}", source.display(), line, self.pattern),
+            (Some(source), None) => format!("{}: {}", source.display(), self.pattern),
+            _ => self.pattern.clone(),
+        }
+    }
+}
+
+/// 查找在遍历中被忽略规则排除的文件和目录，并尽量给出具体规则
+///
+/// `visible` 是正常遍历中出现的路径（见 [`scan_directory_recording_visible`]），
+/// 关闭忽略规则遍历一次，不在其中的路径即为被排除的路径。
+/// 被排除的目录只报告目录本身，不再展开其中的文件；强制包含的路径不会被报告。
+/// 不遵守忽略规则时没有路径被排除。
+///
+/// [`scan_directory_recording_visible`]: super::file_walker::scan_directory_recording_visible
+pub fn find_excluded(
+    dir: &Path,
+    options: &WalkOptions,
+    filter: &FileFilter,
+    visible: &HashSet<PathBuf>,
+) -> Result<Vec<ExcludedEntry>> {
+    if !options.respect_gitignore {
+        return Ok(Vec::new());
+    }
+
+    let unrestricted: Vec<PathBuf> = options
+        .unrestricted_relative_paths(dir)?
+        .iter()
+        .map(|relative| dir.join(relative))
+        .collect();
+
+    // 被配置的排除规则过滤的路径不属于忽略规则的范畴，直接跳过
+    let config_filter = filter.clone();
+    let mut unfiltered = WalkBuilder::new(dir);
+    unfiltered
+        .standard_filters(false)
+        .same_file_system(options.one_file_system)
+        .follow_links(options.follow_links)
+        .max_depth(options.max_depth.map(|depth| depth + 1))
+        .filter_entry(move |entry| !config_filter.is_path_excluded(entry.path()));
+
+    let mut explainer = IgnoreExplainer::new(dir)
+        .with_custom_ignore_filename(options.custom_ignore_filename.clone());
+    let mut excluded: Vec<ExcludedEntry> = Vec::new();
+
+    for entry in unfiltered.build().filter_map(|entry| entry.ok()) {
+        if entry.depth() == 0
+            || visible.contains(entry.path())
+            || unrestricted.iter().any(|path| entry.path().starts_with(path))
+        {
+            continue;
+        }
+
+        // 深度优先遍历，已报告目录下的内容紧随其后，跳过即可
+        if excluded.last().is_some_and(|last| last.is_dir && entry.path().starts_with(&last.path)) {
+            continue;
+        }
+
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        excluded.push(ExcludedEntry {
+            path: entry.path().to_path_buf(),
+            is_dir,
+            rule: explainer.explain(entry.path(), is_dir),
+        });
+    }
+
+    Ok(excluded)
+}
+
+/// 忽略规则解释器：按目录缓存 `.gitignore`、`.ignore`、`.git/info/exclude` 和自定义忽略文件的规则
+pub struct IgnoreExplainer {
+    root: PathBuf,
+    cache: HashMap<PathBuf, Gitignore>,
+    custom_ignore_filename: Option<String>,
+}
+
+impl IgnoreExplainer {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            cache: HashMap::new(),
+            custom_ignore_filename: None,
+        }
+    }
+
+    /// 同时解释自定义忽略文件（如 `.findignore`）中的规则
+    pub fn with_custom_ignore_filename(mut self, name: Option<String>) -> Self {
+        self.custom_ignore_filename = name;
+        self
+    }
+
+    /// 查找排除给定路径的规则，从最深的目录向上查找，第一个命中的规则生效
+    pub fn explain(&mut self, path: &Path, is_dir: bool) -> Option<IgnoreRule> {
+        let root = self.root.clone();
+
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(&root) {
+                break;
+            }
+
+            match self.matcher_for(dir).matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    let source = glob.from().map(Path::to_path_buf);
+                    let line = source.as_deref().and_then(|s| find_rule_line(s, glob.original()));
+                    return Some(IgnoreRule {
+                        source,
+                        line,
+                        pattern: glob.original().to_string(),
+                    });
+                }
+                Match::Whitelist(_) => return None,
+                Match::None => {}
+            }
+        }
+
+        None
+    }
+
+    fn matcher_for(&mut self, dir: &Path) -> &Gitignore {
+        self.cache.entry(dir.to_path_buf()).or_insert_with(|| {
+            let mut builder = GitignoreBuilder::new(dir);
+            // 后添加的文件优先级更高，自定义忽略文件优先级最高
+            let custom = self.custom_ignore_filename.as_deref();
+            for name in [".git/info/exclude", ".gitignore", ".ignore"].into_iter().chain(custom) {
+                let file = dir.join(name);
+                if file.is_file() {
+                    let _ = builder.add(file);
+                }
+            }
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        })
+    }
+}
+
+/// 在忽略文件中查找规则原文所在的行号
+fn find_rule_line(source: &Path, pattern: &str) -> Option<usize> {
+    let content = fs::read_to_string(source).ok()?;
+    content
+        .lines()
+        .position(|line| line.trim() == pattern)
+        .map(|idx| idx + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    use crate::domain::file_walker::scan_directory_recording_visible;
+    use crate::infrastructure::Logger;
+
+    fn excluded_after_scan(root: &Path, options: &WalkOptions, filter: &FileFilter) -> Vec<ExcludedEntry> {
+        let (_, visible) = scan_directory_recording_visible(
+            root,
+            filter.clone(),
+            options,
+            Arc::new(Logger::new(false).unwrap()),
+            |_| Ok(()),
+            |_, _, _| {},
+        )
+        .unwrap();
+        let mut excluded = find_excluded(root, options, filter, &visible.unwrap()).unwrap();
+        excluded.sort_by(|a, b| a.path.cmp(&b.path));
+        excluded
+    }
+
+    #[test]
+    fn test_find_excluded_reports_rule() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir(root.join("build")).unwrap();
+        fs::write(root.join(".gitignore"), "# 注释\n*.log\nbuild/\n").unwrap();
+        fs::write(root.join("app.log"), "x").unwrap();
+        fs::write(root.join("main.rs"), "x").unwrap();
+        fs::write(root.join("build").join("out.txt"), "x").unwrap();
+
+        let filter = FileFilter::new(None, None, vec![".git".to_string()], vec![]);
+        let options = WalkOptions { respect_gitignore: true, ..WalkOptions::default() };
+        let excluded = excluded_after_scan(root, &options, &filter);
+
+        assert_eq!(excluded.len(), 2);
+        assert_eq!(excluded[0].path, root.join("app.log"));
+        let rule = excluded[0].rule.as_ref().unwrap();
+        assert_eq!(rule.pattern, "*.log");
+        assert_eq!(rule.line, Some(2));
+
+        assert_eq!(excluded[1].path, root.join("build"));
+        assert!(excluded[1].is_dir);
+        assert_eq!(excluded[1].rule.as_ref().unwrap().line, Some(3));
+
+        // 不遵守忽略规则时不报告
+        let options = WalkOptions { respect_gitignore: false, ..WalkOptions::default() };
+        assert!(excluded_after_scan(root, &options, &filter).is_empty());
+    }
+}
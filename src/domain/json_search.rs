@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::value::RawValue;
+
+use super::search::{
+    find_line_matches, get_context_lines, merge_overlapping_context, ContextLines, FileSearch, LineMatcher, SearchResult,
+};
+
+/// JSONPath 中的一步
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathStep {
+    /// `.name` 或 `['name']`
+    Key(String),
+    /// `[n]`
+    Index(usize),
+    /// `.*` 或 `[*]`
+    Wildcard,
+}
+
+/// 简化的 JSONPath，支持 `$`、`.name`、`['name']`、`[n]`、`.*` 和 `[*]`
+///
+/// 选中的节点为对象或数组时，搜索其下所有的标量值，因此 `$` 表示搜索整个文件的所有值。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    steps: Vec<PathStep>,
+}
+
+impl JsonPath {
+    /// 解析 JSONPath 表达式
+    pub fn parse(input: &str) -> Result<Self> {
+        let rest = input.trim().strip_prefix('$')
+            .ok_or_else(|| anyhow::anyhow!("JSONPath 必须以 $ 开头: {}", input))?;
+        let chars: Vec<char> = rest.chars().collect();
+        let mut steps = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '.' => {
+                    i += 1;
+                    if chars.get(i) == Some(&'*') {
+                        i += 1;
+                        steps.push(PathStep::Wildcard);
+                        continue;
+                    }
+
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+                    if start == i {
+                        anyhow::bail!("JSONPath 中缺少字段名（不支持递归下降 ..）: {}", input);
+                    }
+                    steps.push(PathStep::Key(chars[start..i].iter().collect()));
+                }
+                '[' => {
+                    let close = chars[i..].iter().position(|&c| c == ']')
+                        .map(|offset| i + offset)
+                        .ok_or_else(|| anyhow::anyhow!("JSONPath 中的 [ 缺少 ]: {}", input))?;
+                    let inner: String = chars[i + 1..close].iter().collect();
+                    let inner = inner.trim();
+
+                    let step = if inner == "*" {
+                        PathStep::Wildcard
+                    } else if let Some(key) = strip_quotes(inner) {
+                        PathStep::Key(key.to_string())
+                    } else {
+                        let index = inner.parse()
+                            .map_err(|_| anyhow::anyhow!("无效的 JSONPath 下标 [{}]: {}", inner, input))?;
+                        PathStep::Index(index)
+                    };
+                    steps.push(step);
+                    i = close + 1;
+                }
+                c => anyhow::bail!("JSONPath 中有无法识别的字符 '{}': {}", c, input),
+            }
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+fn strip_quotes(s: &str) -> Option<&str> {
+    ['\'', '"'].iter().find_map(|&quote| s.strip_prefix(quote)?.strip_suffix(quote))
+}
+
+/// 在 JSON 文件中搜索指定路径下的值
+///
+/// 匹配针对解码后的值进行（字符串去掉引号和转义），结果的行号和行内容来自原始文件，
+/// 因此上下文和高亮与普通文本搜索一致。同一行的多个值合并为一个结果，
+/// 最多保留 `max_count` 个结果，截断后再合并上下文。
+pub fn search_json_file(
+    path: &Path,
+    matcher: &LineMatcher,
+    json_path: &JsonPath,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+) -> Result<FileSearch> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?;
+    let root: &RawValue = serde_json::from_str(&content)
+        .with_context(|| format!("无法解析 JSON: {}", path.display()))?;
+
+    let root = JsonParser::new(root.get()).parse()?;
+
+    let mut selected = Vec::new();
+    select(&root, &json_path.steps, &mut selected);
+    let mut values = Vec::new();
+    for node in selected {
+        collect_scalars(node, &mut values);
+    }
+    // 通配符可能按不同的顺序选中节点，按在文件中的位置排序
+    values.sort_by_key(|raw| raw.as_ptr());
+
+    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+    // 每行的起始字节偏移，值所在的行按偏移二分查找
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(memchr::memchr_iter(b'\n', content.as_bytes()).map(|pos| pos + 1))
+        .collect();
+    let mut matched_lines: BTreeMap<usize, LineMatches> = BTreeMap::new();
+
+    for raw_text in values {
+        let offset = raw_text.as_ptr() as usize - content.as_ptr() as usize;
+        let line_idx = line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = offset - line_starts[line_idx];
+        let value = scalar_text(raw_text)?;
+
+        let (matches, truncated) = find_line_matches(matcher, value.as_bytes(), max_matches_per_line);
+        let Some(&(first_start, first_end)) = matches.first() else {
+            continue;
+        };
+
+        // 字符串没有转义时解码后的值与原文逐字节对应，可以精确高亮；否则高亮整个值
+        let (value_start, verbatim_text) = match raw_text.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Some(inner) => (column + 1, inner),
+            None => (column, raw_text),
+        };
+        let matches: Vec<(usize, usize)> = if verbatim_text == value {
+            matches.iter().map(|&(start, end)| (value_start + start, value_start + end)).collect()
+        } else {
+            vec![(column, column + raw_text.len())]
+        };
+
+        let entry = matched_lines.entry(line_idx).or_insert_with(|| LineMatches {
+            matches: Vec::new(),
+            truncated: false,
+            matched_text: String::from_utf8_lossy(&value.as_bytes()[first_start..first_end]).to_string(),
+            line_offset: offset - column,
+        });
+        entry.matches.extend(matches);
+        entry.truncated |= truncated;
+    }
+
+    let mut results: Vec<SearchResult> = matched_lines
+        .into_iter()
+        .map(|(line_idx, mut line_matches)| {
+            line_matches.matches.sort_unstable();
+            SearchResult {
+                path: path.to_string_lossy().to_string(),
+                line_number: (line_idx + 1) as u64,
+                line: lines.get(line_idx).cloned().unwrap_or_default(),
+                matched_text: line_matches.matched_text,
+                context_before: get_context_lines(&lines, line_idx, context, true),
+                context_after: get_context_lines(&lines, line_idx, context, false),
+                matches: line_matches.matches,
+                matches_truncated: line_matches.truncated,
+                total_lines: lines.len() as u64,
+                line_offset: line_matches.line_offset as u64,
+                encoding: None,
+                is_binary: false,
+            }
+        })
+        .collect();
+
+    if let Some(max) = max_count {
+        results.truncate(max);
+    }
+    merge_overlapping_context(&mut results);
+
+    Ok(FileSearch {
+        results,
+        lines_scanned: lines.len() as u64,
+    })
+}
+
+/// 同一行中所有匹配值的汇总
+struct LineMatches {
+    matches: Vec<(usize, usize)>,
+    truncated: bool,
+    /// 该行第一个匹配的文本
+    matched_text: String,
+    /// 该行的起始字节偏移
+    line_offset: usize,
+}
+
+/// JSON 节点，标量保留原文中的切片，用于计算所在的行和列
+enum JsonNode<'a> {
+    /// 字段按在文件中的顺序保存
+    Object(Vec<(String, JsonNode<'a>)>),
+    Array(Vec<JsonNode<'a>>),
+    Scalar(&'a str),
+}
+
+/// 在已经由 serde_json 校验过的文本上一次建立节点树，每个字节只扫描一次
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<JsonNode<'a>> {
+        self.value()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// 跳过空白后期望 `expected` 之一，返回遇到的字符
+    fn expect(&mut self, expected: &[u8]) -> Result<u8> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if expected.contains(&c) => {
+                self.pos += 1;
+                Ok(c)
+            }
+            _ => anyhow::bail!("无法解析 JSON：位置 {} 处的内容不符合预期", self.pos),
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonNode<'a>> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(JsonNode::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = serde_json::from_str(self.string()?).context("无法解析 JSON 字段名")?;
+                    self.expect(b":")?;
+                    fields.push((key, self.value()?));
+                    if self.expect(b",}")? == b'}' {
+                        return Ok(JsonNode::Object(fields));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(JsonNode::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.expect(b",]")? == b']' {
+                        return Ok(JsonNode::Array(items));
+                    }
+                }
+            }
+            Some(b'"') => Ok(JsonNode::Scalar(self.string()?)),
+            Some(_) => {
+                // 数字、true、false、null 到下一个分隔符为止
+                let start = self.pos;
+                while !matches!(self.peek(), None | Some(b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r')) {
+                    self.pos += 1;
+                }
+                Ok(JsonNode::Scalar(&self.text[start..self.pos]))
+            }
+            None => anyhow::bail!("无法解析 JSON：内容意外结束"),
+        }
+    }
+
+    /// 读取一个字符串，返回包括引号的原文
+    fn string(&mut self) -> Result<&'a str> {
+        let start = self.pos;
+        if self.peek() != Some(b'"') {
+            anyhow::bail!("无法解析 JSON：位置 {} 处应为字符串", self.pos);
+        }
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(&self.text[start..self.pos]);
+                }
+                Some(_) => self.pos += 1,
+                None => anyhow::bail!("无法解析 JSON：字符串没有结束"),
+            }
+        }
+    }
+}
+
+/// 按路径选择节点，不存在的路径不选中任何节点
+fn select<'n, 'a>(node: &'n JsonNode<'a>, steps: &[PathStep], selected: &mut Vec<&'n JsonNode<'a>>) {
+    let Some((step, rest)) = steps.split_first() else {
+        selected.push(node);
+        return;
+    };
+
+    match (step, node) {
+        // 重复的字段名以最后一个为准，与 serde_json 一致
+        (PathStep::Key(key), JsonNode::Object(fields)) => {
+            if let Some((_, child)) = fields.iter().rev().find(|(name, _)| name == key) {
+                select(child, rest, selected);
+            }
+        }
+        (PathStep::Index(index), JsonNode::Array(items)) => {
+            if let Some(child) = items.get(*index) {
+                select(child, rest, selected);
+            }
+        }
+        (PathStep::Wildcard, JsonNode::Object(fields)) => {
+            for (_, child) in fields {
+                select(child, rest, selected);
+            }
+        }
+        (PathStep::Wildcard, JsonNode::Array(items)) => {
+            for child in items {
+                select(child, rest, selected);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 收集节点下的所有标量值的原文
+fn collect_scalars<'a>(node: &JsonNode<'a>, scalars: &mut Vec<&'a str>) {
+    match node {
+        JsonNode::Object(fields) => {
+            for (_, child) in fields {
+                collect_scalars(child, scalars);
+            }
+        }
+        JsonNode::Array(items) => {
+            for child in items {
+                collect_scalars(child, scalars);
+            }
+        }
+        JsonNode::Scalar(raw) => scalars.push(raw),
+    }
+}
+
+/// 标量值的文本：字符串解码后的内容，其他类型为原文
+fn scalar_text(raw: &str) -> Result<String> {
+    if raw.starts_with('"') {
+        serde_json::from_str(raw).context("无法解析 JSON 字符串")
+    } else {
+        Ok(raw.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SearchPattern;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const MANIFEST: &str = r#"{
+  "spec": {
+    "image": "nginx:1.25",
+    "containers": [
+      { "name": "web", "image": "nginx:1.24" },
+      { "name": "sidecar", "image": "envoy:1.0" }
+    ]
+  },
+  "note": "nginx \u00e9"
+}
+"#;
+
+    fn search(content: &str, pattern: &str, json_path: &str) -> Result<Vec<SearchResult>> {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("manifest.json");
+        fs::write(&path, content).unwrap();
+        let matcher = SearchPattern::Text(pattern.to_string()).get_line_matcher().unwrap();
+        search_json_file(&path, &matcher, &JsonPath::parse(json_path).unwrap(), ContextLines::default(), 1000, None).map(|search| search.results)
+    }
+
+    fn line_numbers(results: &[SearchResult]) -> Vec<u64> {
+        results.iter().map(|r| r.line_number).collect()
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(JsonPath::parse("$").unwrap().steps, vec![]);
+        assert_eq!(
+            JsonPath::parse("$.spec['containers'][1].*").unwrap().steps,
+            vec![
+                PathStep::Key("spec".to_string()),
+                PathStep::Key("containers".to_string()),
+                PathStep::Index(1),
+                PathStep::Wildcard,
+            ]
+        );
+        assert!(JsonPath::parse("spec.image").is_err());
+        assert!(JsonPath::parse("$..image").is_err());
+        assert!(JsonPath::parse("$[abc]").is_err());
+    }
+
+    #[test]
+    fn test_search_only_selected_field() {
+        let results = search(MANIFEST, "nginx", "$.spec.image").unwrap();
+        assert_eq!(line_numbers(&results), vec![3]);
+        assert_eq!(results[0].line, r#"    "image": "nginx:1.25","#);
+        assert_eq!(results[0].matched_text, "nginx");
+        assert_eq!(results[0].matches, vec![(14, 19)]);
+
+        let results = search(MANIFEST, "nginx", "$.spec.containers[*].image").unwrap();
+        assert_eq!(line_numbers(&results), vec![5]);
+
+        // 整个文件：字符串中的转义在匹配前解码
+        let results = search(MANIFEST, "nginx é", "$").unwrap();
+        assert_eq!(line_numbers(&results), vec![9]);
+        assert_eq!(results[0].matches, vec![(10, 24)]);
+    }
+
+    #[test]
+    fn test_same_line_values_and_invalid_json() {
+        let results = search(r#"[{"a": "x1", "b": "x2"}]"#, "x", "$[0]").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].matches, vec![(8, 9), (19, 20)]);
+
+        assert!(search("{ not json", "x", "$").is_err());
+    }
+
+    #[test]
+    fn test_positions_after_escapes_and_crlf() {
+        let content = "{\r\n  \"a\\\"b\": \"x \\\" ]\",\r\n  \"list\": [1, {}, []],\r\n  \"k\": \"old\",\r\n  \"k\": \"xyz\"\r\n}";
+        // 字段名和字符串中的转义引号、括号不影响后续值的位置
+        let results = search(content, "xyz", "$.k").unwrap();
+        assert_eq!(line_numbers(&results), vec![5]);
+        assert_eq!(results[0].matches, vec![(8, 11)]);
+
+        let results = search(content, "x", "$['a\"b']").unwrap();
+        assert_eq!(line_numbers(&results), vec![2]);
+        assert_eq!(search(content, "1", "$.list").map(|r| line_numbers(&r)).unwrap(), vec![3]);
+    }
+}
@@ -1,5 +1,16 @@
 pub mod search;
 pub mod file_walker;
+pub mod ignore_report;
+pub mod collect;
+pub mod follow;
+pub mod json_search;
+pub mod replace;
+pub mod sequence;
+pub mod archive;
+pub mod numeric;
+pub mod block_context;
+pub mod path_match;
 
-pub use search::{SearchPattern, SearchResult};
-pub use file_walker::FileFilter;
+pub use search::{SearchPattern, SearchResult, ContextLines, LineMatcher, MatcherOptions, CaseMode, LineRange, LineCountFilter};
+pub use file_walker::{FileFilter, WalkOptions};
+pub use collect::{search_directory, SearchOptions};
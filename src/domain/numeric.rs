@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+
+use super::search::SearchPattern;
+
+/// 整数的字节宽度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntWidth {
+    U16,
+    U32,
+    U64,
+}
+
+impl IntWidth {
+    fn bytes(self) -> usize {
+        match self {
+            Self::U16 => 2,
+            Self::U32 => 4,
+            Self::U64 => 8,
+        }
+    }
+}
+
+/// 字节序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// 将整数按指定宽度和字节序转换为字节序列
+///
+/// 负数按补码处理。值超出该宽度的范围（无符号或有符号）时报错。
+pub fn int_to_bytes(value: i128, width: IntWidth, endian: Endian) -> Result<Vec<u8>> {
+    let bits = width.bytes() as u32 * 8;
+    let min = -(1i128 << (bits - 1));
+    let max = (1i128 << bits) - 1;
+    if value < min || value > max {
+        anyhow::bail!("{} 超出 {} 位整数的范围 ({} ~ {})", value, bits, min, max);
+    }
+
+    let mut bytes = (value as u128).to_le_bytes()[..width.bytes()].to_vec();
+    if endian == Endian::Big {
+        bytes.reverse();
+    }
+    Ok(bytes)
+}
+
+/// 解析 `VALUE:WIDTH:ENDIAN` 形式的整数模式，如 `305419896:32:le`、`0x1234:u16:be`、`-1:64:le`
+///
+/// WIDTH 为 16、32、64（可带 `u` 或 `i` 前缀），ENDIAN 为 `le` 或 `be`，VALUE 为十进制或 `0x` 开头的十六进制。
+/// 结果为按字节搜索的十六进制模式。
+pub fn parse_int_pattern(spec: &str) -> Result<SearchPattern> {
+    let parts: Vec<&str> = spec.split(':').map(str::trim).collect();
+    let [value, width, endian] = parts[..] else {
+        anyhow::bail!("整数模式格式应为 值:宽度:字节序（如 305419896:32:le）: {}", spec);
+    };
+
+    let width = match width.trim_start_matches(['u', 'i', 'U', 'I']) {
+        "16" => IntWidth::U16,
+        "32" => IntWidth::U32,
+        "64" => IntWidth::U64,
+        _ => anyhow::bail!("无效的整数宽度 '{}'（可选 16、32、64）: {}", width, spec),
+    };
+    let endian = match endian.to_lowercase().as_str() {
+        "le" => Endian::Little,
+        "be" => Endian::Big,
+        _ => anyhow::bail!("无效的字节序 '{}'（可选 le、be）: {}", endian, spec),
+    };
+
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => digits.parse::<i128>(),
+    }
+    .with_context(|| format!("无效的整数 '{}': {}", value, spec))?;
+
+    let value = if negative { -magnitude } else { magnitude };
+    Ok(SearchPattern::Hex(int_to_bytes(value, width, endian)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_bytes(spec: &str) -> Vec<u8> {
+        match parse_int_pattern(spec).unwrap() {
+            SearchPattern::Hex(bytes) => bytes,
+            other => panic!("unexpected pattern {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_int_to_bytes() {
+        assert_eq!(int_to_bytes(0x12345678, IntWidth::U32, Endian::Little).unwrap(), [0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(int_to_bytes(0x12345678, IntWidth::U32, Endian::Big).unwrap(), [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(int_to_bytes(0xBEEF, IntWidth::U16, Endian::Little).unwrap(), [0xEF, 0xBE]);
+        assert_eq!(int_to_bytes(1, IntWidth::U64, Endian::Big).unwrap(), [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(int_to_bytes(-2, IntWidth::U16, Endian::Little).unwrap(), [0xFE, 0xFF]);
+        assert_eq!(int_to_bytes(u64::MAX as i128, IntWidth::U64, Endian::Little).unwrap(), [0xFF; 8]);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        assert!(int_to_bytes(0x1_0000, IntWidth::U16, Endian::Little).is_err());
+        assert!(int_to_bytes(-0x8001, IntWidth::U16, Endian::Little).is_err());
+        assert!(int_to_bytes(1 << 64, IntWidth::U64, Endian::Big).is_err());
+    }
+
+    #[test]
+    fn test_parse_int_pattern() {
+        assert_eq!(hex_bytes("305419896:32:le"), [0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(hex_bytes("0x1234:u16:BE"), [0x12, 0x34]);
+        assert_eq!(hex_bytes("-1:i32:le"), [0xFF; 4]);
+
+        assert!(parse_int_pattern("305419896:32").is_err());
+        assert!(parse_int_pattern("305419896:24:le").is_err());
+        assert!(parse_int_pattern("305419896:32:middle").is_err());
+        assert!(parse_int_pattern("abc:32:le").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use super::search::{matched_text, select_line, LineMatcher, SearchResult};
+
+/// 按路径搜索时与匹配器比较的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathTarget {
+    /// 只匹配文件名
+    Name,
+    /// 匹配完整路径
+    Path,
+}
+
+/// 用匹配器检查文件名或完整路径，不读取文件内容
+///
+/// 匹配时返回 `line_number` 为 0、行内容为完整路径的结果；按文件名匹配时，
+/// 匹配位置也换算到完整路径中，便于高亮。
+pub fn match_path(
+    path: &Path,
+    matcher: &LineMatcher,
+    target: PathTarget,
+    max_matches_per_line: usize,
+) -> Option<SearchResult> {
+    let line = path.to_string_lossy().to_string();
+    let offset = match target {
+        PathTarget::Path => 0,
+        PathTarget::Name => {
+            let name = path.file_name()?.to_string_lossy();
+            // 文件名总是路径的结尾部分
+            line.len() - name.len()
+        }
+    };
+
+    let (mut matches, matches_truncated) =
+        select_line(matcher, &line.as_bytes()[offset..], max_matches_per_line)?;
+    for (start, end) in &mut matches {
+        *start += offset;
+        *end += offset;
+    }
+
+    Some(SearchResult {
+        path: line.clone(),
+        line_number: 0,
+        matched_text: matched_text(&line, &matches),
+        line,
+        context_before: Vec::new(),
+        context_after: Vec::new(),
+        matches,
+        matches_truncated,
+        total_lines: 0,
+        line_offset: 0,
+        encoding: None,
+        is_binary: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SearchPattern;
+
+    fn matcher(pattern: &str) -> LineMatcher {
+        SearchPattern::Text(pattern.to_string()).get_line_matcher().unwrap()
+    }
+
+    #[test]
+    fn test_match_file_name_and_path() {
+        let path = Path::new("src/foo/bar.rs");
+
+        assert!(match_path(path, &matcher("foo"), PathTarget::Name, 1000).is_none());
+        let result = match_path(Path::new("src/foo.rs"), &matcher("foo"), PathTarget::Name, 1000).unwrap();
+        assert_eq!(result.line_number, 0);
+        assert_eq!(result.line, "src/foo.rs");
+        assert_eq!(result.matches, vec![(4, 7)]);
+        assert_eq!(result.matched_text, "foo");
+
+        let result = match_path(path, &matcher("foo"), PathTarget::Path, 1000).unwrap();
+        assert_eq!(result.matches, vec![(4, 7)]);
+    }
+}
@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use super::search::{MatcherOptions, SearchPattern};
+
+/// 替换模板中的分组引用
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GroupRef {
+    /// `$1`、`${1}`
+    Index(usize),
+    /// `$name`、`${name}`
+    Name(String),
+}
+
+/// 替换文本，创建时已按搜索模式的分组校验过模板中的所有分组引用
+///
+/// 模板语法与 `regex` crate 相同：`$1`、`${1}`、`$name`、`${name}`，`$$` 表示字面量 `$`。
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    regex: Regex,
+    template: String,
+}
+
+impl Replacement {
+    /// 创建替换，模板引用了不存在的分组时报错
+    ///
+    /// 不存在的分组在替换时会被当作空字符串，悄悄删掉文本，因此必须在处理任何文件之前发现。
+    pub fn new(pattern: &SearchPattern, options: &MatcherOptions, template: &str) -> Result<Self> {
+        let regex = RegexBuilder::new(&pattern.regex_pattern_with(options))
+            .unicode(!options.ascii_word_boundaries)
+            .case_insensitive(pattern.is_case_insensitive(options))
+            .build()
+            .context("无法创建替换用的正则表达式")?;
+
+        // captures_len 包含代表整个匹配的第 0 组
+        let group_count = regex.captures_len() - 1;
+        for group in group_references(template) {
+            match group {
+                GroupRef::Index(index) if index > group_count => anyhow::bail!(
+                    "替换文本引用了分组 ${}，但模式只有 {} 个分组",
+                    index,
+                    group_count
+                ),
+                GroupRef::Name(name) if !regex.capture_names().flatten().any(|n| n == name) => {
+                    // `$1a` 会被当作名为 `1a` 的分组，通常是想写 `${1}a`
+                    let hint = match name.find(|c: char| !c.is_ascii_digit()) {
+                        Some(split) if split > 0 => format!("（如果要引用分组 {} 后接文本，请写成 ${{{}}}{}）", &name[..split], &name[..split], &name[split..]),
+                        _ => String::new(),
+                    };
+                    anyhow::bail!("替换文本引用了不存在的命名分组 ${{{}}}{}", name, hint)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            regex,
+            template: template.to_string(),
+        })
+    }
+
+    /// 替换文本中的所有匹配，没有匹配时原样返回
+    pub fn replace_all<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.regex.replace_all(text, self.template.as_str())
+    }
+
+    /// 流式过滤：逐行读取 `input`，替换匹配后写入 `output`，返回被修改的行数
+    ///
+    /// 所有行都会输出，未修改的行逐字节原样输出。替换只作用于行内容，
+    /// 换行符（`\n` 或 `\r\n`）和最后一行是否有换行符都保持不变；不是有效 UTF-8 的行原样输出。
+    pub fn replace_stream(&self, input: impl BufRead, mut output: impl Write) -> Result<u64> {
+        let changed = self.replace_lines(input, &mut output).context("无法处理标准输入")?;
+        output.flush()?;
+        Ok(changed)
+    }
+
+    /// 计算文件替换后的完整内容，不修改文件；逐行处理的规则与 [`Replacement::replace_stream`] 相同
+    pub fn edit_file(&self, path: &Path) -> Result<FileEdit> {
+        let file = File::open(path).with_context(|| format!("无法打开文件: {}", path.display()))?;
+        let mut content = Vec::new();
+        let changed_lines = self.replace_lines(BufReader::new(file), &mut content)
+            .with_context(|| format!("无法读取文件: {}", path.display()))?;
+        Ok(FileEdit { content, changed_lines })
+    }
+
+    fn replace_lines(&self, mut input: impl BufRead, mut output: impl Write) -> io::Result<u64> {
+        let mut line = Vec::new();
+        let mut changed = 0;
+
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+
+            let content_len = line.strip_suffix(b"\r\n")
+                .or_else(|| line.strip_suffix(b"\n"))
+                .map_or(line.len(), |content| content.len());
+            let (content, terminator) = line.split_at(content_len);
+
+            match std::str::from_utf8(content).map(|text| self.replace_all(text)) {
+                Ok(Cow::Owned(replaced)) => {
+                    changed += 1;
+                    output.write_all(replaced.as_bytes())?;
+                    output.write_all(terminator)?;
+                }
+                _ => output.write_all(&line)?,
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// 一个文件替换后的内容
+#[derive(Debug)]
+pub struct FileEdit {
+    /// 替换后的完整内容
+    pub content: Vec<u8>,
+    /// 被修改的行数
+    pub changed_lines: u64,
+}
+
+impl FileEdit {
+    /// 将替换后的内容写回文件：先写入同一目录下的临时文件，再重命名覆盖原文件，
+    /// 中途失败时原文件保持不变。保留原文件的权限。
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let name = path.file_name()
+            .with_context(|| format!("无效的文件路径: {}", path.display()))?;
+        let temp_path = path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+
+        let result = (|| -> io::Result<()> {
+            let permissions = fs::metadata(path)?.permissions();
+            let mut file = File::options().write(true).create_new(true).open(&temp_path)?;
+            file.write_all(&self.content)?;
+            file.sync_all()?;
+            fs::set_permissions(&temp_path, permissions)?;
+            fs::rename(&temp_path, path)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result.with_context(|| format!("无法写回文件: {}", path.display()))
+    }
+}
+
+/// 按 `regex` crate 的规则提取模板中的分组引用
+fn group_references(template: &str) -> Vec<GroupRef> {
+    let bytes = template.as_bytes();
+    let mut groups = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'$' {
+            i += 1;
+            continue;
+        }
+        i += 1;
+
+        let name = match bytes.get(i) {
+            // `$$` 是字面量 `$`
+            Some(b'$') => {
+                i += 1;
+                continue;
+            }
+            Some(b'{') => match template[i + 1..].find('}') {
+                Some(len) => {
+                    let name = &template[i + 1..i + 1 + len];
+                    i += len + 2;
+                    name
+                }
+                // 没有闭合的 `${` 按字面量处理
+                None => continue,
+            },
+            _ => {
+                let len = template[i..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(template.len() - i);
+                let name = &template[i..i + len];
+                i += len;
+                name
+            }
+        };
+
+        if name.is_empty() {
+            continue;
+        }
+        groups.push(match name.parse() {
+            Ok(index) => GroupRef::Index(index),
+            Err(_) => GroupRef::Name(name.to_string()),
+        });
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(pattern: &str, template: &str) -> Result<Replacement> {
+        Replacement::new(&SearchPattern::Regex(pattern.to_string()), &MatcherOptions::default(), template)
+    }
+
+    #[test]
+    fn test_group_references() {
+        assert_eq!(
+            group_references("$1-${2}-$name-${other}$$3 $"),
+            vec![
+                GroupRef::Index(1),
+                GroupRef::Index(2),
+                GroupRef::Name("name".to_string()),
+                GroupRef::Name("other".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_valid_group_references() {
+        let r = replacement(r"(\w+)@(\w+)", "$2 at ${1}").unwrap();
+        assert_eq!(r.replace_all("me@host"), "host at me");
+
+        let r = replacement(r"(?P<key>\w+)=(?P<value>\w+)", "${value}=$key").unwrap();
+        assert_eq!(r.replace_all("a=1, b=2"), "1=a, 2=b");
+
+        // $0 是整个匹配，$$ 是字面量
+        let r = replacement(r"\d+", "$$$0").unwrap();
+        assert_eq!(r.replace_all("cost 5"), "cost $5");
+    }
+
+    #[test]
+    fn test_replace_stream_passes_other_lines_through() {
+        let r = replacement(r"foo(\d)", "bar$1").unwrap();
+        let input: &[u8] = b"foo1 and foo2\r\nunchanged  line \r\n\n\xff foo3\nlast foo4";
+        let mut output = Vec::new();
+
+        let changed = r.replace_stream(input, &mut output).unwrap();
+
+        assert_eq!(changed, 2);
+        assert_eq!(output, b"bar1 and bar2\r\nunchanged  line \r\n\n\xff foo3\nlast bar4");
+    }
+
+    #[test]
+    fn test_edit_file_in_place() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("app.conf");
+        fs::write(&path, "port=80\r\nhost=a\nport=443").unwrap();
+        let r = replacement(r"port=(\d+)", "port=8$1").unwrap();
+
+        let edit = r.edit_file(&path).unwrap();
+        assert_eq!(edit.changed_lines, 2);
+        // 计算替换不修改文件
+        assert_eq!(fs::read(&path).unwrap(), b"port=80\r\nhost=a\nport=443");
+
+        edit.write_to(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"port=880\r\nhost=a\nport=8443");
+        // 临时文件已被重命名，目录中只剩原文件
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_invalid_group_references() {
+        let err = replacement(r"(a)(b)", "$3").unwrap_err();
+        assert!(err.to_string().contains("只有 2 个分组"), "{}", err);
+
+        let err = replacement(r"(?P<key>\w+)", "${missing}").unwrap_err();
+        assert!(err.to_string().contains("${missing}"), "{}", err);
+
+        // `$1a` 实际引用名为 1a 的分组
+        let err = replacement(r"(a)", "$1a").unwrap_err();
+        assert!(err.to_string().contains("${1}a"), "{}", err);
+
+        // 文本模式没有任何分组
+        let text = SearchPattern::Text("a(b)".to_string());
+        assert!(Replacement::new(&text, &MatcherOptions::default(), "$1").is_err());
+        assert!(Replacement::new(&text, &MatcherOptions::default(), "[$0]").is_ok());
+    }
+}
@@ -1,8 +1,17 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use grep_matcher::Matcher;
-use grep_regex::RegexMatcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use memchr::memmem;
+use regex_syntax::ast::{self, Ast};
+use serde::{Deserialize, Serialize};
 
 /// 搜索模式类型
 #[derive(Debug, Clone)]
@@ -11,8 +20,67 @@ pub enum SearchPattern {
     Text(String),
     /// 十六进制值搜索
     Hex(Vec<u8>),
+    /// 带通配符的十六进制搜索，如 `48 ?? 6c 4?`：每个字节为 (值, 掩码)，掩码中为 1 的位必须与值相同
+    HexWildcard(Vec<(u8, u8)>),
     /// 正则表达式搜索
     Regex(String),
+    /// 多个模式（`-e`），匹配其中任意一个即可
+    Any(Vec<SearchPattern>),
+}
+
+/// 大小写匹配方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    /// 区分大小写
+    #[default]
+    Sensitive,
+    /// 不区分大小写
+    Insensitive,
+    /// 模式中没有大写字母时不区分大小写，否则区分
+    Smart,
+}
+
+/// 文件内容的解码方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Decoding {
+    /// 带 BOM 的 UTF-16 按 UTF-16 解码，其余按 UTF-8 解码，无效的字节序列替换为 U+FFFD
+    #[default]
+    Auto,
+    /// 只接受有效的 UTF-8（及带 BOM 的 UTF-16），否则返回 [`NotUtf8`] 错误
+    Strict,
+    /// 按指定编码（如 GBK、Latin-1）解码整个文件，文件以 BOM 开头时以 BOM 为准
+    Fixed(&'static encoding_rs::Encoding),
+}
+
+/// 有损解码的 UTF-8 文件在结果中显示的编码名
+pub const LOSSY_UTF8: &str = "UTF-8 (lossy)";
+
+impl FromStr for Decoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "utf8" | "utf-8" => Ok(Self::Strict),
+            label => encoding_rs::Encoding::for_label(label.as_bytes())
+                .map(Self::Fixed)
+                .with_context(|| format!("未知的编码: {}（可选 auto、utf8 或编码名，如 gbk、latin1、shift_jis）", s)),
+        }
+    }
+}
+
+/// 匹配器构建选项
+#[derive(Debug, Clone, Default)]
+pub struct MatcherOptions {
+    /// 使用 ASCII 单词边界：关闭正则的 Unicode 模式，`\b` 只把 ASCII 字母数字视为单词字符
+    pub ascii_word_boundaries: bool,
+    /// 大小写匹配方式，十六进制模式总是区分大小写
+    pub case_mode: CaseMode,
+    /// 整词匹配：匹配的两端必须是单词边界（`\b`），十六进制模式忽略此选项
+    pub whole_word: bool,
+    /// 反向匹配：选中不匹配的行（只影响 [`LineMatcher`]，见 [`select_line`]）
+    pub invert: bool,
 }
 
 impl SearchPattern {
@@ -24,8 +92,12 @@ impl SearchPattern {
                 .context("无效的正则表达式")?;
             Ok(SearchPattern::Regex(input.to_string()))
         } else if is_hex {
+            let digits = input.replace(' ', "");
+            if digits.contains('?') {
+                return parse_hex_wildcard(&digits).map(SearchPattern::HexWildcard);
+            }
             // 解析十六进制字符串
-            let hex_bytes = hex::decode(input.replace(' ', ""))
+            let hex_bytes = hex::decode(digits)
                 .context("无效的十六进制值")?;
             Ok(SearchPattern::Hex(hex_bytes))
         } else {
@@ -33,33 +105,328 @@ impl SearchPattern {
         }
     }
 
+    /// 从多个输入字符串创建搜索模式，每个都按同样的方式解释；只有一个时与 [`SearchPattern::from_input`] 相同
+    pub fn from_inputs(inputs: &[String], is_regex: bool, is_hex: bool) -> Result<Self> {
+        match inputs {
+            [input] => Self::from_input(input, is_regex, is_hex),
+            _ => inputs.iter()
+                .map(|input| Self::from_input(input, is_regex, is_hex).with_context(|| format!("无效的模式: {}", input)))
+                .collect::<Result<_>>()
+                .map(SearchPattern::Any),
+        }
+    }
+
+    /// 获取行匹配器，纯 ASCII 文本模式会启用字面量快速路径
+    pub fn get_line_matcher(&self) -> Result<LineMatcher> {
+        self.get_line_matcher_with(&MatcherOptions::default())
+    }
+
+    /// 按选项获取行匹配器
+    pub fn get_line_matcher_with(&self, options: &MatcherOptions) -> Result<LineMatcher> {
+        let regex = self.get_matcher_with(options)?;
+        match self {
+            SearchPattern::Text(text)
+                if !text.is_empty() && text.is_ascii() && !self.is_case_insensitive(options) && !options.whole_word =>
+            {
+                Ok(LineMatcher::with_ascii_literal(regex, text).inverted(options.invert))
+            }
+            _ => Ok(LineMatcher::new(regex).inverted(options.invert).raw_bytes(self.is_byte_pattern())),
+        }
+    }
+
+    /// 是否为按原始字节匹配的模式（十六进制、`--int`），这样的模式不按 UTF-8 字符解释
+    pub fn is_byte_pattern(&self) -> bool {
+        match self {
+            SearchPattern::Hex(_) | SearchPattern::HexWildcard(_) => true,
+            SearchPattern::Any(patterns) => patterns.iter().any(SearchPattern::is_byte_pattern),
+            SearchPattern::Text(_) | SearchPattern::Regex(_) => false,
+        }
+    }
+
     /// 获取匹配器
     pub fn get_matcher(&self) -> Result<RegexMatcher> {
+        self.get_matcher_with(&MatcherOptions::default())
+    }
+
+    /// 按选项获取匹配器
+    pub fn get_matcher_with(&self, options: &MatcherOptions) -> Result<RegexMatcher> {
+        let error_context = match self {
+            SearchPattern::Text(_) => "无法创建文本匹配器",
+            SearchPattern::Hex(_) | SearchPattern::HexWildcard(_) => "无法创建十六进制匹配器",
+            SearchPattern::Regex(_) => "无法创建正则表达式匹配器",
+            SearchPattern::Any(_) => "无法创建多模式匹配器",
+        };
+
+        RegexMatcherBuilder::new()
+            .unicode(!options.ascii_word_boundaries)
+            .case_insensitive(self.is_case_insensitive(options))
+            .build(&self.regex_pattern_with(options))
+            .context(error_context)
+    }
+
+    /// 按大小写模式计算实际是否忽略大小写
+    pub fn is_case_insensitive(&self, options: &MatcherOptions) -> bool {
+        match (self, options.case_mode) {
+            // 整个正则只有一个大小写设置，所有模式都可以忽略大小写时才忽略
+            (SearchPattern::Any(patterns), _) => patterns.iter().all(|pattern| pattern.is_case_insensitive(options)),
+            (SearchPattern::Hex(_) | SearchPattern::HexWildcard(_), _) => false,
+            (_, CaseMode::Sensitive) => false,
+            (_, CaseMode::Insensitive) => true,
+            (SearchPattern::Text(text), CaseMode::Smart) => !text.chars().any(char::is_uppercase),
+            (SearchPattern::Regex(pattern), CaseMode::Smart) => !regex_has_uppercase_literal(pattern),
+        }
+    }
+
+    /// 模式能否匹配空字符串（如 `a*`、`.?`），这样的模式会匹配每一行
+    ///
+    /// 只匹配空行的模式（如 `^$`）不算，因为它不会匹配有内容的行。
+    /// 整词匹配不影响结果：空匹配在每个单词边界处仍然成立。
+    pub fn matches_empty_string(&self, options: &MatcherOptions) -> Result<bool> {
+        let options = MatcherOptions { whole_word: false, ..options.clone() };
+        let matcher = self.get_matcher_with(&options)?;
+        Ok(matcher.is_match(b"")? && matcher.is_match(b"\x00")?)
+    }
+
+    /// 转换为最终编译的正则表达式字符串（转义、十六进制转换之后）
+    pub fn regex_pattern(&self) -> String {
         match self {
-            SearchPattern::Text(text) => {
-                // 转义正则表达式特殊字符
-                let escaped = regex::escape(text);
-                RegexMatcher::new(&escaped)
-                    .context("无法创建文本匹配器")
+            // 转义正则表达式特殊字符
+            SearchPattern::Text(text) => regex::escape(text),
+            // 将十六进制字节转换为正则表达式，关闭 Unicode 模式后 `\xNN` 才表示字节而不是码点 U+00NN
+            SearchPattern::Hex(bytes) => format!(
+                "(?-u:{})",
+                bytes.iter().map(|b| format!(r"\x{:02x}", b)).collect::<String>()
+            ),
+            SearchPattern::HexWildcard(bytes) => format!(
+                "(?s-u:{})",
+                bytes.iter().map(|&(value, mask)| hex_wildcard_regex(value, mask)).collect::<String>()
+            ),
+            SearchPattern::Regex(pattern) => pattern.clone(),
+            SearchPattern::Any(patterns) => alternation(patterns.iter().map(SearchPattern::regex_pattern)),
+        }
+    }
+
+    /// 按选项转换为最终编译的正则表达式字符串，整词匹配时在整个模式两侧加上 `\b`
+    pub fn regex_pattern_with(&self, options: &MatcherOptions) -> String {
+        match self {
+            SearchPattern::Text(_) | SearchPattern::Regex(_) if options.whole_word => {
+                // 非捕获分组保证 `a|b` 这样的模式整体受边界约束
+                format!(r"\b(?:{})\b", self.regex_pattern())
             }
-            SearchPattern::Hex(bytes) => {
-                // 将十六进制字节转换为正则表达式
-                let pattern = bytes.iter()
-                    .map(|b| format!(r"\x{:02x}", b))
-                    .collect::<String>();
-                RegexMatcher::new(&pattern)
-                    .context("无法创建十六进制匹配器")
+            SearchPattern::Any(patterns) => alternation(patterns.iter().map(|pattern| pattern.regex_pattern_with(options))),
+            _ => self.regex_pattern(),
+        }
+    }
+}
+
+/// 把多个正则组合为 `(?:p1)|(?:p2)`，分组保证每个模式内部的 `|` 和锚点不影响其他模式
+fn alternation(patterns: impl Iterator<Item = String>) -> String {
+    patterns.map(|pattern| format!("(?:{})", pattern)).collect::<Vec<_>>().join("|")
+}
+
+/// 解析带通配符的十六进制字符串（已去掉空格）
+///
+/// 每两个字符为一个字节：`??` 匹配任意字节，`4?` 只要求高 4 位为 4，`?4` 只要求低 4 位为 4。
+fn parse_hex_wildcard(digits: &str) -> Result<Vec<(u8, u8)>> {
+    if !digits.is_ascii() || digits.len() % 2 != 0 {
+        anyhow::bail!("无效的十六进制值: {}（每个字节需要两位，通配符为 ?? 或 4?、?4）", digits);
+    }
+
+    let nibble = |c: u8| -> Result<(u8, u8)> {
+        match c {
+            b'?' => Ok((0, 0)),
+            _ => (c as char).to_digit(16)
+                .map(|digit| (digit as u8, 0xf))
+                .with_context(|| format!("无效的十六进制值: {}", digits)),
+        }
+    };
+
+    digits.as_bytes().chunks(2).map(|pair| {
+        let (high, high_mask) = nibble(pair[0])?;
+        let (low, low_mask) = nibble(pair[1])?;
+        Ok(((high << 4) | low, (high_mask << 4) | low_mask))
+    }).collect()
+}
+
+/// 一个带掩码的字节对应的正则表达式：固定字节为 `\xNN`，`??` 为任意字节，半字节通配为字符类
+///
+/// 结果需要放在 `(?s-u:...)` 中使用，`.` 才匹配包括换行在内的任意单个字节。
+fn hex_wildcard_regex(value: u8, mask: u8) -> String {
+    match mask {
+        0xff => format!(r"\x{:02x}", value),
+        0x00 => ".".to_string(),
+        _ => {
+            let class: String = (0..=u8::MAX)
+                .filter(|byte| byte & mask == value & mask)
+                .map(|byte| format!(r"\x{:02x}", byte))
+                .collect();
+            format!("[{}]", class)
+        }
+    }
+}
+
+/// 正则表达式的字面量中是否含有大写字母
+///
+/// 只检查字面量和字符类中的字符，`\W`、`\S`、`\p{Lu}`、`(?U)`、`(?P<Name>...)` 等元语法中的大写字母不算。
+fn regex_has_uppercase_literal(pattern: &str) -> bool {
+    fn ast_has_uppercase(ast: &Ast) -> bool {
+        match ast {
+            Ast::Literal(literal) => literal.c.is_uppercase(),
+            Ast::ClassBracketed(class) => class_set_has_uppercase(&class.kind),
+            Ast::Repetition(repetition) => ast_has_uppercase(&repetition.ast),
+            Ast::Group(group) => ast_has_uppercase(&group.ast),
+            Ast::Alternation(alternation) => alternation.asts.iter().any(ast_has_uppercase),
+            Ast::Concat(concat) => concat.asts.iter().any(ast_has_uppercase),
+            _ => false,
+        }
+    }
+
+    fn class_set_has_uppercase(set: &ast::ClassSet) -> bool {
+        match set {
+            ast::ClassSet::Item(item) => class_item_has_uppercase(item),
+            ast::ClassSet::BinaryOp(op) => class_set_has_uppercase(&op.lhs) || class_set_has_uppercase(&op.rhs),
+        }
+    }
+
+    fn class_item_has_uppercase(item: &ast::ClassSetItem) -> bool {
+        match item {
+            ast::ClassSetItem::Literal(literal) => literal.c.is_uppercase(),
+            ast::ClassSetItem::Range(range) => range.start.c.is_uppercase() || range.end.c.is_uppercase(),
+            ast::ClassSetItem::Bracketed(class) => class_set_has_uppercase(&class.kind),
+            ast::ClassSetItem::Union(union) => union.items.iter().any(class_item_has_uppercase),
+            _ => false,
+        }
+    }
+
+    // 无法解析的模式在构建匹配器时会报错，这里按没有大写字母处理
+    ast::parse::Parser::new()
+        .parse(pattern)
+        .is_ok_and(|ast| ast_has_uppercase(&ast))
+}
+
+/// 行匹配器
+///
+/// 纯 ASCII 字面量使用 memchr 的 memmem 直接按字节查找，比正则匹配快得多；
+/// 其余模式回退到正则匹配器。ASCII 字节在 UTF-8 中不会出现在多字节字符内部，
+/// 因此快速路径对任意 UTF-8 文件都是正确的。
+///
+/// 可以附加一个排除匹配器：同时匹配排除模式的行视为不匹配。行是否“匹配”由
+/// `主模式 && !排除模式` 共同决定，反向匹配应对这个组合结果取反，
+/// 即选中不匹配主模式或命中排除模式的行。
+#[derive(Debug, Clone)]
+pub struct LineMatcher {
+    regex: RegexMatcher,
+    literal: Option<memmem::Finder<'static>>,
+    exclude: Option<RegexMatcher>,
+    invert: bool,
+    raw_bytes: bool,
+}
+
+impl LineMatcher {
+    /// 仅使用正则匹配器
+    pub fn new(regex: RegexMatcher) -> Self {
+        Self { regex, literal: None, exclude: None, invert: false, raw_bytes: false }
+    }
+
+    /// 使用 ASCII 字面量快速路径，`regex` 必须与 `literal` 的匹配结果一致
+    pub fn with_ascii_literal(regex: RegexMatcher, literal: &str) -> Self {
+        Self {
+            regex,
+            literal: Some(memmem::Finder::new(literal.as_bytes()).into_owned()),
+            exclude: None,
+            invert: false,
+            raw_bytes: false,
+        }
+    }
+
+    /// 反向匹配：选中不匹配的行
+    pub fn inverted(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// 是否为反向匹配
+    pub fn is_inverted(&self) -> bool {
+        self.invert
+    }
+
+    /// 按原始字节匹配：不是有效 UTF-8 的行也用原始字节匹配，而不是替换为 U+FFFD 之后的文本
+    pub fn raw_bytes(mut self, raw_bytes: bool) -> Self {
+        self.raw_bytes = raw_bytes;
+        self
+    }
+
+    /// 是否按原始字节匹配
+    pub fn matches_raw_bytes(&self) -> bool {
+        self.raw_bytes
+    }
+
+    /// 附加排除匹配器：匹配主模式的行如果也匹配 `exclude`，则丢弃该行
+    pub fn with_exclude(mut self, exclude: RegexMatcher) -> Self {
+        self.exclude = Some(exclude);
+        self
+    }
+
+    /// 该行是否被排除模式命中（没有排除模式时总是 false）
+    pub fn is_excluded(&self, line: &[u8]) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(line).unwrap_or(false))
+    }
+
+    /// 是否启用了字面量快速路径
+    pub fn is_literal(&self) -> bool {
+        self.literal.is_some()
+    }
+
+    /// 获取底层正则匹配器
+    pub fn regex(&self) -> &RegexMatcher {
+        &self.regex
+    }
+
+    /// 快速判断整段内容中是否可能存在匹配（无快速路径或反向匹配时总是返回 true）
+    pub fn may_match(&self, haystack: &[u8]) -> bool {
+        if self.invert {
+            return true;
+        }
+        match &self.literal {
+            Some(finder) => finder.find(haystack).is_some(),
+            None => true,
+        }
+    }
+
+    /// 依次回调每个匹配的字节范围，回调返回 false 时停止
+    pub fn find_iter<F>(&self, line: &[u8], mut matched: F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        match &self.literal {
+            Some(finder) => {
+                let len = finder.needle().len();
+                for start in finder.find_iter(line) {
+                    if !matched(start, start + len) {
+                        break;
+                    }
+                }
             }
-            SearchPattern::Regex(pattern) => {
-                RegexMatcher::new(pattern)
-                    .context("无法创建正则表达式匹配器")
+            None => {
+                let _ = self.regex.find_iter(line, |m| matched(m.start(), m.end()));
             }
         }
     }
 }
 
+impl From<RegexMatcher> for LineMatcher {
+    fn from(regex: RegexMatcher) -> Self {
+        Self::new(regex)
+    }
+}
+
 /// 搜索结果
-#[derive(Debug)]
+///
+/// 排序按 `(路径, 行号, 列)` 进行，列为该行第一个匹配的起始字节偏移；
+/// 三者都相同时再依次比较其余字段，使排序是与 `Eq` 一致的全序。
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchResult {
     pub path: String,
     pub line_number: u64,
@@ -67,51 +434,732 @@ pub struct SearchResult {
     pub matched_text: String,
     pub context_before: Vec<String>,
     pub context_after: Vec<String>,
+    /// 该行所有匹配的字节范围（行内偏移）
+    pub matches: Vec<(usize, usize)>,
+    /// 单行匹配数超过上限，后续匹配未被收集
+    pub matches_truncated: bool,
+    /// 所在文件的总行数
+    pub total_lines: u64,
+    /// 匹配行的第一个字节在文件中的偏移（文件不是有效的 UTF-8 时为解码后文本中的偏移，与行内的匹配位置一致）
+    pub line_offset: u64,
+    /// 文件不是有效的 UTF-8 时解码所用的编码（如 `UTF-16LE`、`GBK`、[`LOSSY_UTF8`]），UTF-8 文件为 None
+    pub encoding: Option<&'static str>,
+    /// 文件包含 NUL 字节，按二进制文件处理
+    pub is_binary: bool,
+}
+
+impl SearchResult {
+    /// 该行的匹配次数（超过单行匹配上限时为上限值）
+    pub fn matches_on_line(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// 第一个匹配在行内的起始字节偏移，没有匹配范围时为 0
+    pub fn column(&self) -> usize {
+        self.match_start().unwrap_or(0)
+    }
+
+    /// 第一个匹配在行内的起始字节偏移（按 UTF-8 字节计算），反向匹配选中的行没有匹配范围，为 None
+    pub fn match_start(&self) -> Option<usize> {
+        self.matches.first().map(|&(start, _)| start)
+    }
+
+    /// 第一个匹配在行内的结束字节偏移（不包含），没有匹配范围时为 None
+    pub fn match_end(&self) -> Option<usize> {
+        self.matches.first().map(|&(_, end)| end)
+    }
+
+    /// 第一个匹配在文件中的字节偏移
+    ///
+    /// 行偏移和列都按解码后的文本计算，UTF-16、指定编码和有损解码的文件得到的是解码后文本中的偏移，
+    /// 与原始文件中的字节位置不同。
+    pub fn byte_offset(&self) -> u64 {
+        self.line_offset + self.column() as u64
+    }
+
+    /// 匹配行在文件中的相对位置（百分比，`line_number / total_lines`），总行数未知时为 None
+    pub fn position_percent(&self) -> Option<u64> {
+        (self.total_lines > 0).then(|| (self.line_number * 100 / self.total_lines).min(100))
+    }
+}
+
+impl Ord for SearchResult {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.path, self.line_number, self.column())
+            .cmp(&(&other.path, other.line_number, other.column()))
+            .then_with(|| {
+                (&self.line, &self.matched_text, &self.matches, &self.context_before, &self.context_after).cmp(&(
+                    &other.line,
+                    &other.matched_text,
+                    &other.matches,
+                    &other.context_before,
+                    &other.context_after,
+                ))
+            })
+            .then_with(|| {
+                (self.matches_truncated, self.total_lines, self.line_offset, self.encoding, self.is_binary).cmp(&(
+                    other.matches_truncated,
+                    other.total_lines,
+                    other.line_offset,
+                    other.encoding,
+                    other.is_binary,
+                ))
+            })
+    }
 }
 
-/// 在单个文件中搜索
-pub fn search_in_file(path: &Path, matcher: &RegexMatcher, context_lines: usize) -> Result<Vec<SearchResult>> {
-    // 读取文件内容
-    let content = std::fs::read_to_string(path)
+impl PartialOrd for SearchResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 单个文件的搜索输出
+#[derive(Debug, Default)]
+pub struct FileSearch {
+    pub results: Vec<SearchResult>,
+    /// 扫描的行数
+    pub lines_scanned: u64,
+}
+
+/// 匹配行前后的上下文行数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextLines {
+    /// 匹配行之前的行数
+    pub before: usize,
+    /// 匹配行之后的行数
+    pub after: usize,
+}
+
+impl ContextLines {
+    /// 前后各 `lines` 行
+    pub fn symmetric(lines: usize) -> Self {
+        Self { before: lines, after: lines }
+    }
+
+    /// 前后都没有上下文
+    pub fn is_empty(&self) -> bool {
+        self.before == 0 && self.after == 0
+    }
+}
+
+/// 在单个文件中搜索，`max_count` 不为 None 时最多返回这么多个匹配，之后（收满后文）即停止读取
+pub fn search_in_file(
+    path: &Path,
+    matcher: &LineMatcher,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+) -> Result<Vec<SearchResult>> {
+    search_file_in_range(
+        path,
+        matcher,
+        &LineRange::default(),
+        Decoding::Auto,
+        context,
+        max_matches_per_line,
+        max_count,
+        false,
+    )
+    .map(|search| search.results)
+}
+
+/// 在单个文件中搜索，同时返回扫描的行数
+pub fn search_file_counted(
+    path: &Path,
+    matcher: &LineMatcher,
+    context: ContextLines,
+    max_matches_per_line: usize,
+) -> Result<FileSearch> {
+    search_file_in_range(path, matcher, &LineRange::default(), Decoding::Auto, context, max_matches_per_line, None, false)
+}
+
+/// 要搜索的行号范围（从 1 开始，两端都包含），未指定的一端不限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+impl LineRange {
+    /// 解析 `100:200`、`100:` 或 `:200` 形式的范围
+    pub fn parse(input: &str) -> Result<Self> {
+        let (start, end) = input.split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("行号范围格式应为 起始:结束（如 100:200、100: 或 :200）: {}", input))?;
+
+        let parse_bound = |bound: &str| -> Result<Option<u64>> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            match bound.parse::<u64>() {
+                Ok(0) => anyhow::bail!("行号从 1 开始: {}", input),
+                Ok(n) => Ok(Some(n)),
+                Err(_) => anyhow::bail!("无效的行号 '{}': {}", bound, input),
+            }
+        };
+
+        let range = Self {
+            start: parse_bound(start)?,
+            end: parse_bound(end)?,
+        };
+        if let (Some(start), Some(end)) = (range.start, range.end) {
+            if start > end {
+                anyhow::bail!("行号范围的起始大于结束: {}", input);
+            }
+        }
+        Ok(range)
+    }
+
+    /// 行号（从 1 开始）是否在范围内
+    pub fn contains(&self, line_number: u64) -> bool {
+        self.start.is_none_or(|start| line_number >= start) && self.end.is_none_or(|end| line_number <= end)
+    }
+}
+
+/// 在单个文件中搜索，只匹配 `range` 内的行；上下文行不受范围限制
+///
+/// UTF-8 文件逐行流式读取，内存占用只与上下文行数和匹配数有关，与文件大小无关；
+/// 带 BOM 的 UTF-16 文件和指定了编码的文件需要整体解码，仍然一次读入。
+///
+/// `max_count` 不为 None 时最多返回这么多个匹配（按行计），最后一个匹配的后文收满后不再读取文件的其余部分；
+/// 行号范围有结束行时，读到结束行且后文收满后同样停止。此时结果的总行数和扫描行数只计到停止读取的位置，
+/// 需要显示总行数时（如 `--position`、`--file-header`）将 `count_all_lines` 设为 true，
+/// 停止匹配后仍数完剩余的行（不做匹配），结果的总行数为整个文件的行数。
+#[allow(clippy::too_many_arguments)]
+pub fn search_file_in_range(
+    path: &Path,
+    matcher: &LineMatcher,
+    range: &LineRange,
+    decoding: Decoding,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+    count_all_lines: bool,
+) -> Result<FileSearch> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let head = reader.fill_buf()
         .with_context(|| format!("无法读取文件: {}", path.display()))?;
 
-    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-    let mut results = Vec::new();
-
-    // 查找匹配行
-    for (line_idx, line) in lines.iter().enumerate() {
-        if let Ok(Some(m)) = matcher.find(line.as_bytes()) {
-            let matched_text = String::from_utf8_lossy(&line.as_bytes()[m.start()..m.end()]).to_string();
-            
-            // 获取上下文行
-            let context_before = get_context_lines(&lines, line_idx, context_lines, true);
-            let context_after = get_context_lines(&lines, line_idx, context_lines, false);
-            
-            results.push(SearchResult {
-                path: path.to_string_lossy().to_string(),
-                line_number: (line_idx + 1) as u64, // 转换为1基索引
-                line: line.clone(),
-                matched_text,
-                context_before,
-                context_after,
-            });
+    if matches!(decoding, Decoding::Fixed(_)) || matches!(head.get(..2), Some([0xFF, 0xFE] | [0xFE, 0xFF])) {
+        let (content, encoding) = read_text_file(path, decoding)?;
+        return Ok(search_text(
+            &path.to_string_lossy(),
+            &content,
+            encoding,
+            matcher,
+            range,
+            context,
+            max_matches_per_line,
+            max_count,
+        ));
+    }
+
+    search_lines(
+        &path.to_string_lossy(),
+        reader,
+        decoding == Decoding::Strict,
+        matcher,
+        range,
+        context,
+        max_matches_per_line,
+        max_count,
+        count_all_lines,
+    )
+}
+
+/// 搜索标准输入时结果中显示的路径
+pub const STDIN_PATH: &str = "<stdin>";
+
+/// 从输入流（如标准输入）中逐行搜索，`name` 为结果中显示的路径
+///
+/// 与文件一样流式读取，前后文照常收集；不是有效 UTF-8 的行按替换后的文本匹配，不会报错。
+/// `max_count` 和 `count_all_lines` 与 [`search_file_in_range`] 相同。
+pub fn search_reader<R: BufRead>(
+    name: &str,
+    reader: R,
+    matcher: &LineMatcher,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+    count_all_lines: bool,
+) -> Result<FileSearch> {
+    search_lines(
+        name,
+        reader,
+        false,
+        matcher,
+        &LineRange::default(),
+        context,
+        max_matches_per_line,
+        max_count,
+        count_all_lines,
+    )
+}
+
+/// 在已读入的文本中搜索，`path` 为结果中显示的路径
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn search_text(
+    path: &str,
+    content: &str,
+    encoding: Option<&'static str>,
+    matcher: &LineMatcher,
+    range: &LineRange,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+) -> FileSearch {
+    // 字面量快速路径：整个文件都不包含时无需逐行处理，行数直接按换行符统计
+    if !matcher.may_match(content.as_bytes()) {
+        return FileSearch {
+            results: Vec::new(),
+            lines_scanned: count_lines(content.as_bytes()),
+        };
+    }
+
+    // 文本已在内存中，数完剩余的行代价很小，总是给出完整的总行数
+    let mut search = search_lines(
+        path,
+        content.as_bytes(),
+        true,
+        matcher,
+        range,
+        context,
+        max_matches_per_line,
+        max_count,
+        true,
+    )
+    .expect("内存中的文本总是有效的 UTF-8");
+    for result in &mut search.results {
+        result.encoding = encoding;
+    }
+    search
+}
+
+/// 逐行读取并搜索
+///
+/// 只保留最近 `context.before` 行作为前文，后文在读到之后补入尚未收满的结果，
+/// 因此不需要持有整个文件。行按 [`str::lines`] 的规则切分：行尾的 `\n` 或 `\r\n` 不属于行内容。
+/// 遇到不是有效 UTF-8 的行时，`strict` 为 true 则返回 [`NotUtf8`] 错误，否则将无效的字节序列替换为 U+FFFD，
+/// 匹配和匹配位置都针对替换后的文本，该文件的结果的编码标记为 [`LOSSY_UTF8`]。
+/// 按原始字节匹配的匹配器（见 [`LineMatcher::raw_bytes`]）匹配替换前的字节，匹配位置再换算到替换后的文本上。
+/// 已有 `max_count` 个匹配时不再匹配之后的行，最后一个匹配的后文收满后即停止读取；读过 `range` 的结束行后同样如此。
+/// 提前停止时，`count_all_lines` 为 true 则继续数完剩余的行，结果的总行数为全部行数。
+#[allow(clippy::too_many_arguments)]
+fn search_lines<R: BufRead>(
+    path: &str,
+    mut reader: R,
+    strict: bool,
+    matcher: &LineMatcher,
+    range: &LineRange,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+    count_all_lines: bool,
+) -> Result<FileSearch> {
+    let mut results: Vec<SearchResult> = Vec::new();
+    // 最近的若干行，用作之后匹配的前文
+    let mut recent: VecDeque<String> = VecDeque::with_capacity(context.before);
+    // 后文尚未收满的结果的下标
+    let mut awaiting_after: VecDeque<usize> = VecDeque::new();
+    let mut is_binary = false;
+    let mut lossy = false;
+    let mut line_number = 0u64;
+    let mut line_offset = 0u64;
+    let mut buf = Vec::new();
+    // 是否因匹配数达到上限或读过行号范围而提前停止读取
+    let mut stopped_early = false;
+
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf)
+            .with_context(|| format!("无法读取文件: {}", path))?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let mut raw = buf.as_slice();
+        if let Some(stripped) = raw.strip_suffix(b"\n") {
+            raw = stripped.strip_suffix(b"\r").unwrap_or(stripped);
+        }
+        is_binary |= memchr::memchr(0, raw).is_some();
+        let line: Cow<str> = match std::str::from_utf8(raw) {
+            Ok(line) => Cow::Borrowed(line),
+            Err(err) if strict => {
+                return Err(NotUtf8 {
+                    name: path.to_string(),
+                    source: Some(err),
+                }
+                .into())
+            }
+            Err(_) => {
+                lossy = true;
+                String::from_utf8_lossy(raw)
+            }
+        };
+        let line = line.as_ref();
+        // 偏移按解码后的文本累计，有效的 UTF-8 行与原始字节一致，有损解码的行与匹配位置一致
+        let current_offset = line_offset;
+        line_offset += (line.len() + read - raw.len()) as u64;
+
+        if context.after > 0 {
+            for &index in &awaiting_after {
+                results[index].context_after.push(line.to_string());
+            }
+            while awaiting_after.front().is_some_and(|&index| results[index].context_after.len() == context.after) {
+                awaiting_after.pop_front();
+            }
         }
+
+        // 匹配数达到上限后，之后的行只用来补全后文
+        let found_enough = max_count.is_some_and(|max| results.len() >= max);
+        if range.contains(line_number) && !found_enough {
+            let haystack = if matcher.matches_raw_bytes() { raw } else { line.as_bytes() };
+            if let Some((matches, matches_truncated)) = select_line(matcher, haystack, max_matches_per_line) {
+                results.push(SearchResult {
+                    path: path.to_string(),
+                    line_number,
+                    line: line.to_string(),
+                    matched_text: matched_text(line, &matches),
+                    context_before: recent.iter().cloned().collect(),
+                    context_after: Vec::new(),
+                    matches,
+                    matches_truncated,
+                    // 读完整个文件后才知道
+                    total_lines: 0,
+                    line_offset: current_offset,
+                    encoding: None,
+                    is_binary: false,
+                });
+                if context.after > 0 {
+                    awaiting_after.push_back(results.len() - 1);
+                }
+            }
+        }
+
+        if context.before > 0 {
+            if recent.len() == context.before {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+
+        // 匹配数达到上限或已读过行号范围的末尾，且没有结果在等待后文时，不再读取
+        let past_range = range.end.is_some_and(|end| line_number >= end);
+        if (past_range || max_count.is_some_and(|max| results.len() >= max)) && awaiting_after.is_empty() {
+            stopped_early = true;
+            break;
+        }
+    }
+
+    let mut total_lines = line_number;
+    if stopped_early && count_all_lines {
+        total_lines += count_remaining_lines(&mut reader)
+            .with_context(|| format!("无法读取文件: {}", path))?;
     }
 
-    Ok(results)
+    for result in &mut results {
+        result.total_lines = total_lines;
+        result.is_binary = is_binary;
+        if lossy {
+            result.encoding = Some(LOSSY_UTF8);
+        }
+    }
+    merge_overlapping_context(&mut results);
+
+    Ok(FileSearch {
+        results,
+        lines_scanned: line_number,
+    })
+}
+
+/// 读取文本文件，返回内容和非 UTF-8 时使用的编码
+pub(crate) fn read_text_file(path: &Path, decoding: Decoding) -> Result<(String, Option<&'static str>)> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?;
+    decode_text(bytes, &path.display().to_string(), decoding)
+}
+
+/// 按解码方式将字节解码为文本，返回内容和非 UTF-8 时使用的编码，`name` 用于错误信息
+///
+/// 带 BOM 的 UTF-16 总是按 UTF-16 解码。
+pub(crate) fn decode_text(bytes: Vec<u8>, name: &str, decoding: Decoding) -> Result<(String, Option<&'static str>)> {
+    if let Decoding::Fixed(encoding) = decoding {
+        let (content, used, _) = encoding.decode(&bytes);
+        let label = (used != encoding_rs::UTF_8).then(|| used.name());
+        return Ok((content.into_owned(), label));
+    }
+
+    let utf16 = match bytes.get(..2) {
+        Some([0xFF, 0xFE]) => Some(("UTF-16LE", u16::from_le_bytes as fn([u8; 2]) -> u16)),
+        Some([0xFE, 0xFF]) => Some(("UTF-16BE", u16::from_be_bytes as fn([u8; 2]) -> u16)),
+        _ => None,
+    };
+
+    match utf16 {
+        Some((encoding, to_unit)) => {
+            let units: Vec<u16> = bytes[2..].chunks_exact(2).map(|pair| to_unit([pair[0], pair[1]])).collect();
+            let content = if decoding == Decoding::Strict {
+                String::from_utf16(&units).with_context(|| format!("无法按 {} 解码文件: {}", encoding, name))?
+            } else {
+                String::from_utf16_lossy(&units)
+            };
+            Ok((content, Some(encoding)))
+        }
+        None => match String::from_utf8(bytes) {
+            Ok(content) => Ok((content, None)),
+            Err(err) if decoding == Decoding::Strict => Err(NotUtf8 {
+                name: name.to_string(),
+                source: Some(err.utf8_error()),
+            }
+            .into()),
+            Err(err) => Ok((String::from_utf8_lossy(err.as_bytes()).into_owned(), Some(LOSSY_UTF8))),
+        },
+    }
+}
+
+/// 文件不是有效的 UTF-8
+#[derive(Debug, thiserror::Error)]
+#[error("无法读取文件: {name}（不是有效的 UTF-8）")]
+pub struct NotUtf8 {
+    pub name: String,
+    /// 无效字节序列的位置，带 BOM 的 UTF-16 文件为 None
+    #[source]
+    pub source: Option<std::str::Utf8Error>,
+}
+
+/// 文件是否以 UTF-16 BOM 开头（这样的文件会被解码后搜索）
+pub fn has_utf16_bom(path: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    let mut bom = [0u8; 2];
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?;
+    let read = file.read(&mut bom)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?;
+    Ok(read == 2 && matches!(bom, [0xFF, 0xFE] | [0xFE, 0xFF]))
+}
+
+/// 按与 [`str::lines`] 相同的规则统计行数：最后一行没有换行符时也算一行
+pub(crate) fn count_lines(content: &[u8]) -> u64 {
+    let newlines = memchr::memchr_iter(b'\n', content).count() as u64;
+    match content.last() {
+        Some(&b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+/// 统计读取器中剩余的行数，只查找换行符，不做匹配；规则与 [`count_lines`] 相同
+fn count_remaining_lines<R: BufRead>(reader: &mut R) -> std::io::Result<u64> {
+    let mut newlines = 0u64;
+    let mut last_byte = None;
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(buf) => buf,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        newlines += memchr::memchr_iter(b'\n', buf).count() as u64;
+        last_byte = buf.last().copied();
+        let len = buf.len();
+        reader.consume(len);
+    }
+
+    Ok(match last_byte {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    })
+}
+
+/// 按文件行数过滤，未指定的一端不限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCountFilter {
+    pub min_lines: Option<u64>,
+    pub max_lines: Option<u64>,
+}
+
+impl LineCountFilter {
+    /// 是否设置了任何限制
+    pub fn is_active(&self) -> bool {
+        self.min_lines.is_some() || self.max_lines.is_some()
+    }
+
+    /// 统计文件行数并判断是否在范围内
+    ///
+    /// 需要额外读取一遍文件，但不会把整个文件读入内存；超过 `max_lines` 时立即停止读取。
+    pub fn matches_file(&self, path: &Path) -> Result<bool> {
+        use std::io::Read;
+
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("无法读取文件: {}", path.display()))?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut newlines = 0u64;
+        let mut last_byte = None;
+
+        loop {
+            let n = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err).with_context(|| format!("无法读取文件: {}", path.display())),
+            };
+            newlines += memchr::memchr_iter(b'\n', &buffer[..n]).count() as u64;
+            last_byte = Some(buffer[n - 1]);
+
+            // 行数不少于换行符数，已经超过上限就不必读完
+            if self.max_lines.is_some_and(|max| newlines > max) {
+                return Ok(false);
+            }
+        }
+
+        // 与 count_lines 规则一致：最后一行没有换行符时也算一行
+        let lines = match last_byte {
+            Some(b'\n') | None => newlines,
+            Some(_) => newlines + 1,
+        };
+        Ok(self.min_lines.is_none_or(|min| lines >= min) && self.max_lines.is_none_or(|max| lines <= max))
+    }
+}
+
+/// 单个文件搜索超时
+#[derive(Debug, thiserror::Error)]
+#[error("搜索文件超时（超过 {0:?}）")]
+pub struct SearchTimeout(pub Duration);
+
+/// 在辅助线程中执行任务，超过 `timeout` 仍未完成则返回 [`SearchTimeout`] 错误
+///
+/// 注意：标准库无法中断阻塞中的读取，超时后辅助线程会在后台继续运行直到读取返回，
+/// 只是其结果被丢弃。在网络挂载上卡死的读取因此仍会占用一个线程和一个文件句柄。
+pub fn run_with_timeout<T, F>(timeout: Duration, task: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::sync_channel(1);
+    std::thread::spawn(move || {
+        let _ = tx.send(task());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(SearchTimeout(timeout).into()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("搜索线程意外退出"),
+    }
+}
+
+/// 查找一行中的所有匹配，最多收集 `limit` 个，返回 (匹配范围, 是否截断)
+///
+/// 被排除模式命中的行返回空结果。排除模式只在主模式匹配后才检查，大多数行不需要额外匹配。
+/// 按原始字节匹配时匹配可能从字符中间开始或结束，返回的范围会扩展到完整的字符，
+/// 并换算为 [`String::from_utf8_lossy`] 替换后的文本中的偏移。
+pub(crate) fn find_line_matches(matcher: &LineMatcher, line: &[u8], limit: usize) -> (Vec<(usize, usize)>, bool) {
+    let mut matches = Vec::new();
+    let mut truncated = false;
+
+    matcher.find_iter(line, |start, end| {
+        if matches.len() >= limit {
+            truncated = true;
+            return false;
+        }
+        matches.push((start, end));
+        true
+    });
+
+    if !matches.is_empty() && matcher.is_excluded(line) {
+        return (Vec::new(), false);
+    }
+
+    if matcher.matches_raw_bytes() {
+        for (start, end) in &mut matches {
+            *start = decoded_offset(line, *start, false);
+            *end = decoded_offset(line, *end, true);
+        }
+    }
+
+    (matches, truncated)
+}
+
+/// 把原始字节中的偏移换算为 [`String::from_utf8_lossy`] 替换后的文本中的偏移
+///
+/// 偏移落在多字节字符或无效字节序列（替换为一个 U+FFFD）内部时，`round_up` 为 false 对齐到其起点，否则对齐到其终点。
+fn decoded_offset(raw: &[u8], offset: usize, round_up: bool) -> usize {
+    let mut raw_pos = 0;
+    let mut decoded_pos = 0;
+    for chunk in raw.utf8_chunks() {
+        let valid = chunk.valid();
+        if offset <= raw_pos + valid.len() {
+            let mut index = offset - raw_pos;
+            while !valid.is_char_boundary(index) {
+                if round_up { index += 1 } else { index -= 1 }
+            }
+            return decoded_pos + index;
+        }
+        raw_pos += valid.len();
+        decoded_pos += valid.len();
+
+        if offset < raw_pos + chunk.invalid().len() {
+            return decoded_pos + if round_up { char::REPLACEMENT_CHARACTER.len_utf8() } else { 0 };
+        }
+        raw_pos += chunk.invalid().len();
+        decoded_pos += char::REPLACEMENT_CHARACTER.len_utf8();
+    }
+    decoded_pos
+}
+
+/// 判断一行是否被选中，选中时返回该行的匹配范围和是否被截断
+///
+/// 正常匹配时选中有匹配的行；反向匹配时选中没有匹配的行，这些行没有匹配范围（不高亮）。
+pub(crate) fn select_line(matcher: &LineMatcher, line: &[u8], limit: usize) -> Option<(Vec<(usize, usize)>, bool)> {
+    let (matches, truncated) = find_line_matches(matcher, line, limit);
+    match (matcher.is_inverted(), matches.is_empty()) {
+        (false, false) => Some((matches, truncated)),
+        (true, true) => Some((Vec::new(), false)),
+        _ => None,
+    }
+}
+
+/// 第一个匹配的文本，没有匹配范围（反向匹配选中的行）时为空
+pub(crate) fn matched_text(line: &str, matches: &[(usize, usize)]) -> String {
+    matches.first()
+        .map(|&(start, end)| String::from_utf8_lossy(&line.as_bytes()[start..end]).to_string())
+        .unwrap_or_default()
+}
+
+/// 合并相邻匹配的上下文，使重叠的上下文行只出现一次
+///
+/// 上一个结果的后文截止到当前匹配行之前，当前结果的前文从上一个结果最后输出的行之后开始，
+/// 这样同一文件中距离较近的匹配会形成一个连续的块。`results` 必须按行号升序排列。
+pub(crate) fn merge_overlapping_context(results: &mut [SearchResult]) {
+    for i in 1..results.len() {
+        let (head, tail) = results.split_at_mut(i);
+        let prev = &mut head[i - 1];
+        let next = &mut tail[0];
+
+        let lines_between = (next.line_number - prev.line_number - 1) as usize;
+        prev.context_after.truncate(lines_between);
+
+        let last_printed = prev.line_number + prev.context_after.len() as u64;
+        let max_before = (next.line_number - last_printed - 1) as usize;
+        if next.context_before.len() > max_before {
+            let excess = next.context_before.len() - max_before;
+            next.context_before.drain(..excess);
+        }
+    }
 }
 
 /// 获取上下文行
-fn get_context_lines(lines: &[String], line_idx: usize, context_lines: usize, before: bool) -> Vec<String> {
+///
+/// 前文取 `context.before` 行，后文取 `context.after` 行；靠近文件开头或末尾、行数不足时，只返回实际存在的行。
+pub(crate) fn get_context_lines(lines: &[String], line_idx: usize, context: ContextLines, before: bool) -> Vec<String> {
     if before {
-        let start = if line_idx >= context_lines {
-            line_idx - context_lines
-        } else {
-            0
-        };
+        let start = line_idx.saturating_sub(context.before);
         lines[start..line_idx].to_vec()
     } else {
-        let end = std::cmp::min(line_idx + 1 + context_lines, lines.len());
+        let end = std::cmp::min(line_idx + 1 + context.after, lines.len());
         lines[line_idx + 1..end].to_vec()
     }
 }
@@ -156,4 +1204,802 @@ mod tests {
         let test_line = "this is a test line";
         assert!(matcher.find(test_line.as_bytes()).unwrap().is_some());
     }
+
+    #[test]
+    fn test_match_limit_per_line() {
+        let matcher = SearchPattern::Text("a".to_string()).get_line_matcher().unwrap();
+        let line = "a".repeat(5000);
+
+        let (matches, truncated) = find_line_matches(&matcher, line.as_bytes(), 100);
+        assert_eq!(matches.len(), 100);
+        assert!(truncated);
+        assert_eq!(matches[99], (99, 100));
+
+        let (matches, truncated) = find_line_matches(&matcher, b"banana", 100);
+        assert_eq!(matches, vec![(1, 2), (3, 4), (5, 6)]);
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// 每次读取前等待放行的输入，模拟卡住的文件读取；放行端被丢弃后不再等待
+        struct GatedReader {
+            inner: std::io::Cursor<&'static [u8]>,
+            gate: mpsc::Receiver<()>,
+            reads: Arc<AtomicUsize>,
+        }
+
+        impl std::io::Read for GatedReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let _ = self.gate.recv();
+                self.reads.fetch_add(1, Ordering::SeqCst);
+                self.inner.read(buf)
+            }
+        }
+
+        let (release, gate) = mpsc::channel();
+        let reads = Arc::new(AtomicUsize::new(0));
+        let reader = GatedReader {
+            inner: std::io::Cursor::new(b"needle\n"),
+            gate,
+            reads: Arc::clone(&reads),
+        };
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+        let (done_tx, done_rx) = mpsc::channel();
+        let slow = run_with_timeout(Duration::from_millis(20), move || {
+            let search = search_reader("slow", BufReader::new(reader), &matcher, ContextLines::default(), 100, None, false);
+            let _ = done_tx.send(search.as_ref().map(|search| search.results.len()).unwrap_or(0));
+            search
+        });
+        assert!(slow.unwrap_err().downcast_ref::<SearchTimeout>().is_some());
+        // 超时返回时读取仍被阻塞，一次也没有完成
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        // 放行后脱离的搜索线程照常读完
+        drop(release);
+        assert_eq!(done_rx.recv_timeout(Duration::from_secs(5)).unwrap(), 1);
+        assert!(reads.load(Ordering::SeqCst) >= 1);
+
+        let fast = run_with_timeout(Duration::from_secs(5), || Ok(42));
+        assert_eq!(fast.unwrap(), 42);
+    }
+
+    #[test]
+    fn test_ascii_literal_fast_path() {
+        let literal = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+        assert!(literal.is_literal());
+        assert!(!SearchPattern::Text("针".to_string()).get_line_matcher().unwrap().is_literal());
+        assert!(!SearchPattern::Regex("need.e".to_string()).get_line_matcher().unwrap().is_literal());
+
+        // 快速路径与正则路径的结果必须一致
+        let fallback = LineMatcher::new(literal.regex().clone());
+        let line = "针needle，needle!".as_bytes();
+        let (fast, _) = find_line_matches(&literal, line, 100);
+        let (slow, _) = find_line_matches(&fallback, line, 100);
+        assert_eq!(fast, slow);
+        assert_eq!(fast, vec![(3, 9), (12, 18)]);
+
+        assert!(literal.may_match(b"haystack with needle"));
+        assert!(!literal.may_match(b"haystack"));
+    }
+
+    #[test]
+    fn test_streaming_large_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("large.log");
+        let total = 200_000u64;
+        let mut content = String::new();
+        for i in 1..=total {
+            if i == 1 || i == 100_000 || i == total {
+                content.push_str(&format!("needle {}\r\n", i));
+            } else {
+                content.push_str(&format!("line {}\n", i));
+            }
+        }
+        std::fs::write(&file_path, &content).unwrap();
+
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+        let search = search_file_counted(&file_path, &matcher, ContextLines::symmetric(2), 100).unwrap();
+        assert_eq!(search.lines_scanned, total);
+
+        let results = search.results;
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [1, 100_000, total]);
+        assert!(results.iter().all(|r| r.total_lines == total));
+
+        assert!(results[0].context_before.is_empty());
+        assert_eq!(results[0].context_after, ["line 2", "line 3"]);
+        assert_eq!(results[1].line, "needle 100000");
+        assert_eq!(results[1].context_before, ["line 99998", "line 99999"]);
+        assert_eq!(results[1].context_after, ["line 100001", "line 100002"]);
+        assert_eq!(results[2].context_before, ["line 199998", "line 199999"]);
+        assert!(results[2].context_after.is_empty());
+
+        // 行偏移指向文件中该行的起始字节
+        let offset = results[1].line_offset as usize;
+        assert!(content[offset..].starts_with("needle 100000\r\n"));
+    }
+
+    #[test]
+    fn test_close_matches_share_context() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("close.txt");
+        let content = (1..=20)
+            .map(|i| if i == 10 || i == 13 { format!("match {}", i) } else { format!("line {}", i) })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(&file_path, content).unwrap();
+
+        let matcher = SearchPattern::Text("match".to_string()).get_line_matcher().unwrap();
+        let results = search_in_file(&file_path, &matcher, ContextLines::symmetric(5), 100, None).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].total_lines, 20);
+
+        assert_eq!(results[0].line_number, 10);
+        assert_eq!(results[0].context_before, vec!["line 5", "line 6", "line 7", "line 8", "line 9"]);
+        assert_eq!(results[0].context_after, vec!["line 11", "line 12"]);
+
+        assert_eq!(results[1].line_number, 13);
+        assert!(results[1].context_before.is_empty());
+        assert_eq!(results[1].context_after, vec!["line 14", "line 15", "line 16", "line 17", "line 18"]);
+    }
+
+    #[test]
+    fn test_ascii_vs_unicode_word_boundaries() {
+        let pattern = SearchPattern::from_input(r"\bcaf\b", true, false).unwrap();
+        let line = "un café noir".as_bytes();
+
+        // Unicode 模式下 é 是单词字符，caf 后面没有单词边界
+        let unicode = pattern.get_matcher().unwrap();
+        assert!(unicode.find(line).unwrap().is_none());
+
+        // ASCII 模式下 é 不是单词字符，caf 后面是单词边界
+        let options = MatcherOptions { ascii_word_boundaries: true, ..MatcherOptions::default() };
+        let ascii = pattern.get_matcher_with(&options).unwrap();
+        assert!(ascii.find(line).unwrap().is_some());
+
+        // 非 ASCII 文本在 ASCII 模式下仍可搜索
+        let text = SearchPattern::Text("café".to_string()).get_matcher_with(&options).unwrap();
+        assert!(text.find(line).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_search_pattern_hex_wildcards() {
+        let pattern = SearchPattern::from_input("48 ?? 6c 6?", false, true).unwrap();
+        match &pattern {
+            SearchPattern::HexWildcard(bytes) => {
+                assert_eq!(bytes, &vec![(0x48, 0xff), (0x00, 0x00), (0x6c, 0xff), (0x60, 0xf0)]);
+            }
+            other => panic!("Expected HexWildcard pattern, got {:?}", other),
+        }
+        assert_eq!(
+            pattern.regex_pattern(),
+            r"(?s-u:\x48.\x6c[\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f])"
+        );
+
+        let matcher = pattern.get_matcher().unwrap();
+        assert!(matcher.is_match(b"Hello").unwrap());
+        assert!(matcher.is_match(b"HXlo").unwrap());
+        assert!(!matcher.is_match(b"Hel").unwrap());
+        assert!(!matcher.is_match(b"HelL").unwrap());
+
+        // 低半字节固定
+        let matcher = SearchPattern::from_input("?1", false, true).unwrap().get_matcher().unwrap();
+        assert!(matcher.is_match(b"a").unwrap());
+        assert!(matcher.is_match(b"1").unwrap());
+        assert!(!matcher.is_match(b"b").unwrap());
+
+        // `??` 是单个字节，不是单个字符
+        let matcher = SearchPattern::from_input("62 ?? 63", false, true).unwrap().get_matcher().unwrap();
+        assert!(matcher.is_match(b"b\xe9c").unwrap());
+        assert!(!matcher.is_match("béc".as_bytes()).unwrap());
+
+        // 全部是字面量时仍为普通十六进制模式
+        assert!(matches!(SearchPattern::from_input("48 65", false, true).unwrap(), SearchPattern::Hex(_)));
+        assert!(SearchPattern::from_input("48 ?", false, true).is_err());
+        assert!(SearchPattern::from_input("4g ??", false, true).is_err());
+    }
+
+    #[test]
+    fn test_regex_pattern() {
+        assert_eq!(SearchPattern::Text("a.b(c)".to_string()).regex_pattern(), r"a\.b\(c\)");
+        assert_eq!(SearchPattern::Hex(vec![0x41, 0xff]).regex_pattern(), r"(?-u:\x41\xff)");
+        assert_eq!(SearchPattern::Regex(r"\bfoo\b".to_string()).regex_pattern(), r"\bfoo\b");
+    }
+
+    #[test]
+    fn test_any_of_several_patterns() {
+        let line_matches = |pattern: &SearchPattern, line: &str| {
+            let matcher = pattern.get_line_matcher().unwrap();
+            select_line(&matcher, line.as_bytes(), 1000).map(|(matches, _)| matched_text(line, &matches))
+        };
+
+        let texts = SearchPattern::from_inputs(&["cat".to_string(), "dog".to_string()], false, false).unwrap();
+        assert_eq!(line_matches(&texts, "a dog"), Some("dog".to_string()));
+        assert_eq!(line_matches(&texts, "dog and cat"), Some("dog".to_string()));
+        assert_eq!(line_matches(&texts, "bird"), None);
+
+        // 文本模式转义后与正则组合，各自的 `|` 和锚点互不影响
+        let mixed = SearchPattern::Any(vec![
+            SearchPattern::Regex(r"^\d+$|x".to_string()),
+            SearchPattern::Text("a.b".to_string()),
+        ]);
+        assert_eq!(mixed.regex_pattern(), r"(?:^\d+$|x)|(?:a\.b)");
+        assert_eq!(line_matches(&mixed, "see a.b"), Some("a.b".to_string()));
+        assert_eq!(line_matches(&mixed, "axb 12"), Some("x".to_string()));
+        assert_eq!(line_matches(&mixed, "ab 12"), None);
+
+        // 只有一个输入时与 from_input 相同
+        assert!(matches!(SearchPattern::from_inputs(&["cat".to_string()], false, false).unwrap(), SearchPattern::Text(_)));
+        assert!(SearchPattern::from_inputs(&["ok".to_string(), "(".to_string()], true, false).is_err());
+    }
+
+    #[test]
+    fn test_zero_width_matches_report_every_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("lines.txt");
+        std::fs::write(&file_path, "first\n\nthird\n").unwrap();
+
+        let matcher = SearchPattern::from_input("^", true, false).unwrap().get_line_matcher().unwrap();
+        let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        for result in &results {
+            assert_eq!(result.matched_text, "");
+            assert_eq!(result.matches, vec![(0, 0)]);
+        }
+        assert_eq!(results[2].line, "third");
+
+        let matcher = SearchPattern::from_input("$", true, false).unwrap().get_line_matcher().unwrap();
+        let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results[0].matches, vec![(5, 5)]);
+    }
+
+    #[test]
+    fn test_every_match_on_line_is_reported() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("repeat.txt");
+        std::fs::write(&file_path, "needle, needle and NEEDLE needle\n").unwrap();
+
+        for pattern in [SearchPattern::Text("needle".to_string()), SearchPattern::Regex("ne+dle".to_string())] {
+            let matcher = pattern.get_line_matcher().unwrap();
+            let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].matches, vec![(0, 6), (8, 14), (26, 32)]);
+            assert_eq!(results[0].matched_text, "needle");
+        }
+    }
+
+    #[test]
+    fn test_match_offsets_after_multibyte_text() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("utf8.txt");
+        std::fs::write(&file_path, "ascii needle
+前缀 café needle
+").unwrap();
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+
+        let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!((results[0].match_start(), results[0].match_end()), (Some(6), Some(12)));
+        // “前缀 café ” 占 6 + 1 + 5 + 1 = 13 个字节
+        assert_eq!((results[1].match_start(), results[1].match_end()), (Some(13), Some(19)));
+        assert_eq!(&results[1].line[13..19], "needle");
+        assert_eq!(results[1].byte_offset(), 13 + 13);
+    }
+
+    #[test]
+    fn test_max_count_stops_reading() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("many.txt");
+        let content: String = (1..=100).map(|i| format!("line {} needle\n", i)).collect();
+        std::fs::write(&file_path, content).unwrap();
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+
+        let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, Some(1)).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 1);
+
+        let search = search_file_in_range(&file_path, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::default(), 1000, Some(7), false)
+            .unwrap();
+        assert_eq!(search.results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(search.lines_scanned, 7);
+
+        // 后文收满后才停止读取，后文中的匹配行不再作为结果
+        let search = search_file_in_range(&file_path, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::symmetric(2), 1000, Some(1), false)
+            .unwrap();
+        assert_eq!(search.results.len(), 1);
+        assert_eq!(search.results[0].context_after, vec!["line 2 needle", "line 3 needle"]);
+        assert_eq!(search.lines_scanned, 3);
+        assert_eq!(search.results[0].total_lines, 3);
+
+        // 需要总行数时数完剩余的行，扫描行数不变
+        let search = search_file_in_range(&file_path, &matcher, &LineRange::default(), Decoding::Auto, ContextLines::default(), 1000, Some(1), true)
+            .unwrap();
+        assert_eq!(search.results[0].total_lines, 100);
+        assert_eq!(search.lines_scanned, 1);
+
+        assert!(search_in_file(&file_path, &matcher, ContextLines::default(), 1000, Some(0)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invert_match_selects_complementary_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("mixed.txt");
+        std::fs::write(&file_path, "needle 1\nhay 2\nneedle 3\nhay 4\nhay 5\nneedle 6\n").unwrap();
+        let pattern = SearchPattern::Text("needle".to_string());
+        let line_numbers = |invert: bool| {
+            let options = MatcherOptions { invert, ..MatcherOptions::default() };
+            let matcher = pattern.get_line_matcher_with(&options).unwrap();
+            search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None)
+                .unwrap()
+                .iter()
+                .map(|r| r.line_number)
+                .collect::<Vec<_>>()
+        };
+
+        let selected = line_numbers(false);
+        let inverted = line_numbers(true);
+        assert_eq!(selected, [1, 3, 6]);
+        assert_eq!(inverted, [2, 4, 5]);
+
+        // 反向选中的行没有匹配范围，上下文照常收集
+        let options = MatcherOptions { invert: true, ..MatcherOptions::default() };
+        let matcher = pattern.get_line_matcher_with(&options).unwrap();
+        let results = search_in_file(&file_path, &matcher, ContextLines::symmetric(1), 1000, None).unwrap();
+        assert_eq!(results[0].matched_text, "");
+        assert!(results[0].matches.is_empty());
+        assert_eq!(results[0].context_before, vec!["needle 1"]);
+        assert_eq!(results[2].context_after, vec!["needle 6"]);
+
+        // 排除模式命中的行也算作不匹配
+        let matcher = pattern.get_line_matcher_with(&options).unwrap()
+            .with_exclude(SearchPattern::Text("3".to_string()).get_matcher().unwrap());
+        let results = search_in_file(&file_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_case_modes() {
+        let line = b"Hello World";
+        let matches = |pattern: SearchPattern, case_mode: CaseMode| {
+            let options = MatcherOptions { case_mode, ..MatcherOptions::default() };
+            let matcher = pattern.get_line_matcher_with(&options).unwrap();
+            let mut found = false;
+            matcher.find_iter(line, |_, _| {
+                found = true;
+                false
+            });
+            found
+        };
+        let text = |s: &str| SearchPattern::Text(s.to_string());
+
+        assert!(!matches(text("hello"), CaseMode::Sensitive));
+        assert!(matches(text("HELLO"), CaseMode::Insensitive));
+        // 智能模式：全小写时忽略大小写，含大写时区分
+        assert!(matches(text("hello"), CaseMode::Smart));
+        assert!(!matches(text("HELLO"), CaseMode::Smart));
+        assert!(matches(text("Hello"), CaseMode::Smart));
+
+        // 十六进制模式总是按字节精确匹配
+        assert!(!matches(SearchPattern::Hex(b"hello".to_vec()), CaseMode::Insensitive));
+
+        let options = MatcherOptions { case_mode: CaseMode::Insensitive, ..MatcherOptions::default() };
+        let matcher = text("hello").get_matcher_with(&options).unwrap();
+        assert!(matcher.is_match(b"HELLO").unwrap());
+    }
+
+    #[test]
+    fn test_whole_word() {
+        let options = MatcherOptions { whole_word: true, ..MatcherOptions::default() };
+        let is_match = |pattern: SearchPattern, line: &str| {
+            let matcher = pattern.get_line_matcher_with(&options).unwrap();
+            let mut found = false;
+            matcher.find_iter(line.as_bytes(), |_, _| {
+                found = true;
+                false
+            });
+            found
+        };
+
+        let text = SearchPattern::Text("cat".to_string());
+        assert!(is_match(text.clone(), "a cat sat"));
+        assert!(!is_match(text.clone(), "category"));
+        assert!(!is_match(SearchPattern::Text("a.b".to_string()), "axb"));
+
+        // 分组使边界作用于整个选择分支
+        let regex = SearchPattern::Regex("cat|dog".to_string());
+        assert_eq!(regex.regex_pattern_with(&options), r"\b(?:cat|dog)\b");
+        assert!(is_match(regex.clone(), "hotdog or cat"));
+        assert!(!is_match(regex, "hotdog"));
+
+        // 十六进制模式按字节匹配，忽略整词选项
+        let hex = SearchPattern::Hex(b"cat".to_vec());
+        assert_eq!(hex.regex_pattern_with(&options), hex.regex_pattern());
+        assert!(is_match(hex, "category"));
+    }
+
+    #[test]
+    fn test_smart_case_ignores_regex_meta_characters() {
+        let options = MatcherOptions { case_mode: CaseMode::Smart, ..MatcherOptions::default() };
+        let insensitive = |pattern: &str| SearchPattern::Regex(pattern.to_string()).is_case_insensitive(&options);
+
+        // 元字符、Unicode 类别、标志和分组名中的大写字母不算
+        assert!(insensitive(r"hello\W\S\D\B"));
+        assert!(insensitive(r"\p{Lu}+world"));
+        assert!(insensitive(r"(?U)a+"));
+        assert!(insensitive(r"(?P<Name>abc)"));
+        assert!(insensitive(r"\x{61}"));
+
+        // 字面量、转义的字面量和字符类中的大写字母算
+        assert!(!insensitive(r"Hello"));
+        assert!(!insensitive(r"a\x41"));
+        assert!(!insensitive(r"[A-Z]+"));
+        assert!(!insensitive(r"(foo|Bar)"));
+    }
+
+    #[test]
+    fn test_lines_scanned() {
+        assert_eq!(count_lines(b""), 0);
+        assert_eq!(count_lines(b"a\nb"), 2);
+        assert_eq!(count_lines(b"a\nb\n"), 2);
+        assert_eq!(count_lines(b"\n\n"), 2);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("lines.txt");
+        std::fs::write(&file_path, "one\ntwo\nthree").unwrap();
+
+        // 字面量快速路径跳过的文件与逐行搜索的文件按同样的规则计数
+        for pattern in ["missing", "two"] {
+            let matcher = SearchPattern::Text(pattern.to_string()).get_line_matcher().unwrap();
+            let search = search_file_counted(&file_path, &matcher, ContextLines::default(), 1000).unwrap();
+            assert_eq!(search.lines_scanned, 3);
+        }
+    }
+
+    #[test]
+    fn test_line_range() {
+        assert_eq!(LineRange::parse("100:200").unwrap(), LineRange { start: Some(100), end: Some(200) });
+        assert_eq!(LineRange::parse("100:").unwrap(), LineRange { start: Some(100), end: None });
+        assert_eq!(LineRange::parse(":200").unwrap(), LineRange { start: None, end: Some(200) });
+        assert!(LineRange::parse("100").is_err());
+        assert!(LineRange::parse("0:5").is_err());
+        assert!(LineRange::parse("5:3").is_err());
+        assert!(LineRange::parse("a:3").is_err());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("range.txt");
+        std::fs::write(&file_path, "hit\nhit\nhit\nhit\nhit\n").unwrap();
+        let matcher = SearchPattern::Text("hit".to_string()).get_line_matcher().unwrap();
+        let line_numbers = |range: &str| -> Vec<u64> {
+            let range = LineRange::parse(range).unwrap();
+            search_file_in_range(&file_path, &matcher, &range, Decoding::Auto, ContextLines::symmetric(1), 1000, None, false).unwrap()
+                .results.iter().map(|r| r.line_number).collect()
+        };
+
+        // 两端都包含，行号从 1 开始
+        assert_eq!(line_numbers("2:4"), vec![2, 3, 4]);
+        assert_eq!(line_numbers("4:"), vec![4, 5]);
+        assert_eq!(line_numbers(":1"), vec![1]);
+        assert_eq!(line_numbers("9:"), Vec::<u64>::new());
+
+        // 上下文行可以超出范围
+        let range = LineRange::parse("3:3").unwrap();
+        let search = search_file_in_range(&file_path, &matcher, &range, Decoding::Auto, ContextLines::symmetric(1), 1000, None, false).unwrap();
+        assert_eq!(search.results[0].context_before, vec!["hit"]);
+        assert_eq!(search.results[0].context_after, vec!["hit"]);
+        // 结束行的后文收满后不再读取
+        assert_eq!(search.lines_scanned, 4);
+        let range = LineRange::parse(":2").unwrap();
+        let search = search_file_in_range(&file_path, &matcher, &range, Decoding::Auto, ContextLines::default(), 1000, None, false).unwrap();
+        assert_eq!(search.lines_scanned, 2);
+        // 需要总行数时数完剩余的行
+        let search = search_file_in_range(&file_path, &matcher, &range, Decoding::Auto, ContextLines::default(), 1000, None, true).unwrap();
+        assert_eq!(search.lines_scanned, 2);
+        assert!(search.results.iter().all(|r| r.total_lines == 5));
+    }
+
+    #[test]
+    fn test_asymmetric_context() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("lines.txt");
+        let content = "a\nb\nhit\nc\nd\ne\n";
+        std::fs::write(&file_path, content).unwrap();
+        let matcher = SearchPattern::Text("hit".to_string()).get_line_matcher().unwrap();
+        let context = ContextLines { before: 0, after: 2 };
+
+        // 逐行读取和整体读入两条路径结果相同
+        let streamed = search_in_file(&file_path, &matcher, context, 1000, None).unwrap();
+        let in_memory = search_text("lines.txt", content, None, &matcher, &LineRange::default(), context, 1000, None).results;
+        for results in [streamed, in_memory] {
+            assert!(results[0].context_before.is_empty());
+            assert_eq!(results[0].context_after, vec!["c", "d"]);
+        }
+
+        let lines: Vec<String> = content.lines().map(str::to_string).collect();
+        let context = ContextLines { before: 1, after: 0 };
+        assert_eq!(get_context_lines(&lines, 2, context, true), vec!["b"]);
+        assert!(get_context_lines(&lines, 2, context, false).is_empty());
+    }
+
+    #[test]
+    fn test_matches_empty_string() {
+        let options = MatcherOptions::default();
+        let regex = |pattern: &str| SearchPattern::Regex(pattern.to_string()).matches_empty_string(&options).unwrap();
+
+        assert!(regex("a*"));
+        assert!(regex(".?"));
+        assert!(regex("foo|"));
+        assert!(SearchPattern::Text(String::new()).matches_empty_string(&options).unwrap());
+
+        assert!(!regex("a+"));
+        assert!(!regex("^$"));
+        assert!(!regex(r"\bfoo"));
+        assert!(!SearchPattern::Text("a*".to_string()).matches_empty_string(&options).unwrap());
+    }
+
+    #[test]
+    fn test_zero_context_matches_same_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("context.txt");
+        std::fs::write(&file_path, "a\nhit one\nb\r\nhit two\r\nc").unwrap();
+        let matcher = SearchPattern::Text("hit".to_string()).get_line_matcher().unwrap();
+
+        let with_context = search_file_counted(&file_path, &matcher, ContextLines::symmetric(2), 1000).unwrap();
+        let without_context = search_file_counted(&file_path, &matcher, ContextLines::default(), 1000).unwrap();
+
+        assert_eq!(with_context.lines_scanned, without_context.lines_scanned);
+        assert_eq!(without_context.results.len(), 2);
+        for (a, b) in with_context.results.iter().zip(&without_context.results) {
+            assert_eq!(a.line_number, b.line_number);
+            assert_eq!(a.line, b.line);
+            assert_eq!(a.total_lines, b.total_lines);
+            assert!(b.context_before.is_empty() && b.context_after.is_empty());
+        }
+        assert_eq!(without_context.results[1].line, "hit two");
+    }
+
+    #[test]
+    fn test_line_count_filter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("lines.txt");
+        std::fs::write(&file_path, "1\n2\n3").unwrap();
+
+        let filter = |min_lines, max_lines| LineCountFilter { min_lines, max_lines }.matches_file(&file_path).unwrap();
+        assert!(filter(None, None));
+        assert!(filter(Some(3), Some(3)));
+        assert!(!filter(Some(4), None));
+        assert!(!filter(None, Some(2)));
+
+        // 超过上限时提前停止读取，大文件也能快速判断
+        let big_path = temp_dir.path().join("big.txt");
+        std::fs::write(&big_path, "x\n".repeat(500_000)).unwrap();
+        let filter = LineCountFilter { min_lines: None, max_lines: Some(10) };
+        assert!(!filter.matches_file(&big_path).unwrap());
+        assert!(!LineCountFilter::default().is_active());
+    }
+
+    #[test]
+    fn test_encoding_and_binary_flags() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let matcher = SearchPattern::Text("hello".to_string()).get_line_matcher().unwrap();
+
+        let utf16_path = temp_dir.path().join("utf16.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("first\nhello world\n".encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(&utf16_path, bytes).unwrap();
+        let results = search_in_file(&utf16_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 2);
+        assert_eq!(results[0].encoding, Some("UTF-16LE"));
+        assert!(!results[0].is_binary);
+
+        // 转码和有损解码的文件，偏移都按解码后的文本计算
+        let bytes: Vec<u8> = [0xFF, 0xFE].into_iter()
+            .chain("é
+ab hello
+".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        std::fs::write(&utf16_path, bytes).unwrap();
+        let results = search_in_file(&utf16_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results[0].byte_offset(), 3 + 3);
+
+        let lossy_path = temp_dir.path().join("lossy.txt");
+        std::fs::write(&lossy_path, b"\xC3\x28 x\r\nab hello\n").unwrap();
+        let results = search_in_file(&lossy_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results[0].encoding, Some(LOSSY_UTF8));
+        let decoded = String::from_utf8_lossy(b"\xC3\x28 x\r\nab hello\n");
+        assert_eq!(results[0].byte_offset(), decoded.find("hello").unwrap() as u64);
+
+        let binary_path = temp_dir.path().join("data.bin");
+        std::fs::write(&binary_path, b"\x00\x01hello\x00").unwrap();
+        let results = search_in_file(&binary_path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results[0].encoding, None);
+        assert!(results[0].is_binary);
+    }
+
+    #[test]
+    fn test_after_context_at_end_of_file() {
+        let lines: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
+        let after = |line_idx: usize| get_context_lines(&lines, line_idx, ContextLines::symmetric(5), false);
+
+        // 倒数第三、第二和最后一行：后文恰好是剩下的行
+        assert_eq!(after(7), vec!["line 9", "line 10"]);
+        assert_eq!(after(8), vec!["line 10"]);
+        assert!(after(9).is_empty());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let matcher = SearchPattern::Text("hit".to_string()).get_line_matcher().unwrap();
+        // 文件末尾有无换行符结果都一样
+        for ending in ["", "\n"] {
+            let file_path = temp_dir.path().join("eof.txt");
+            let mut content: Vec<String> = (1..=7).map(|i| format!("line {}", i)).collect();
+            content.extend(["hit 8".to_string(), "line 9".to_string(), "hit 10".to_string()]);
+            std::fs::write(&file_path, content.join("\n") + ending).unwrap();
+
+            for line_number in [8, 9, 10] {
+                let range = LineRange { start: Some(line_number), end: Some(line_number) };
+                let pattern = SearchPattern::Regex(format!("^(hit|line) {}$", line_number));
+                let matcher = pattern.get_line_matcher().unwrap();
+                let search = search_file_in_range(&file_path, &matcher, &range, Decoding::Auto, ContextLines::symmetric(5), 1000, None, false).unwrap();
+                let result = &search.results[0];
+
+                let expected_after: Vec<&str> = content[line_number as usize..].iter().map(|s| s.as_str()).collect();
+                assert_eq!(result.context_after, expected_after, "第 {} 行", line_number);
+                assert_eq!(result.context_before.len(), 5);
+            }
+
+            // 相邻的末尾匹配合并上下文后，最后一行只输出一次
+            let results = search_in_file(&file_path, &matcher, ContextLines::symmetric(5), 1000, None).unwrap();
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].context_after, vec!["line 9"]);
+            assert!(results[1].context_after.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_exclude_pattern_drops_matching_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("settings.py");
+        std::fs::write(&path, "password = 'x'\npassword_hash = 'y'\nuser = 'z'\n").unwrap();
+
+        let exclude = SearchPattern::Regex("password_hash".to_string()).get_matcher().unwrap();
+        let matcher = SearchPattern::Text("password".to_string())
+            .get_line_matcher()
+            .unwrap()
+            .with_exclude(exclude);
+
+        let results = search_in_file(&path, &matcher, ContextLines::default(), 1000, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 1);
+
+        // 只匹配排除模式、不匹配主模式的行本来就不是结果
+        assert!(matcher.is_excluded(b"password_hash"));
+        assert_eq!(find_line_matches(&matcher, b"user = 'z'", 10), (Vec::new(), false));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_typed_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("latin1.txt");
+        std::fs::write(&path, b"caf\xe9 needle\n").unwrap();
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+
+        let err = search_file_in_range(&path, &matcher, &LineRange::default(), Decoding::Strict, ContextLines::default(), 1000, None, false).unwrap_err();
+        let not_utf8 = err.downcast_ref::<NotUtf8>().unwrap();
+        assert_eq!(not_utf8.source.unwrap().valid_up_to(), 3);
+        assert!(!has_utf16_bom(&path).unwrap());
+
+        std::fs::write(&path, b"\xff\xfen\x00").unwrap();
+        assert!(has_utf16_bom(&path).unwrap());
+    }
+
+    #[test]
+    fn test_lossy_decoding_keeps_searching_around_invalid_bytes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mixed.txt");
+        std::fs::write(&path, b"needle one\ncaf\xe9 needle\nneedle three\n").unwrap();
+        let matcher = SearchPattern::Text("needle".to_string()).get_line_matcher().unwrap();
+
+        let results = search_in_file(&path, &matcher, ContextLines::symmetric(1), 1000, None).unwrap();
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(results[1].line, "caf\u{FFFD} needle");
+        // 匹配位置针对替换后的文本
+        assert_eq!(results[1].matches, vec![(7, 13)]);
+        assert!(results.iter().all(|r| r.encoding == Some(LOSSY_UTF8)));
+        assert_eq!(results[2].context_before, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_byte_patterns_match_raw_bytes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("mixed.bin");
+        std::fs::write(&path, b"id=\xe8\x03\x00\x00\ncaf\xc3\xa9\nb\xe9c \xe9\n").unwrap();
+        let search = |pattern: SearchPattern| {
+            let matcher = pattern.get_line_matcher().unwrap();
+            search_in_file(&path, &matcher, ContextLines::default(), 1000, None).unwrap()
+        };
+
+        // 1000 的 32 位小端编码 e8 03 00 00
+        let results = search(SearchPattern::Hex(vec![0xe8, 0x03, 0x00, 0x00]));
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [1]);
+        // 匹配位置换算到替换后的文本：e8 被替换为 U+FFFD（3 个字节）
+        assert_eq!(results[0].matches, vec![(3, 9)]);
+
+        // e9 是字节，不匹配 UTF-8 编码的 é（c3 a9）
+        let results = search(SearchPattern::from_input("e9", false, true).unwrap());
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [3]);
+        assert_eq!(results[0].matches, vec![(1, 4), (6, 9)]);
+
+        let results = search(SearchPattern::from_input("62 ?? 63", false, true).unwrap());
+        assert_eq!(results.iter().map(|r| r.line_number).collect::<Vec<_>>(), [3]);
+        assert_eq!(results[0].matched_text, "b\u{FFFD}c");
+
+        // 匹配多字节字符中间的字节时，范围扩展到整个字符
+        let results = search(SearchPattern::from_input("a9", false, true).unwrap());
+        assert_eq!(results[0].matched_text, "é");
+    }
+
+    #[test]
+    fn test_fixed_encoding() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("gbk.txt");
+        // “你好 needle” 的 GBK 编码
+        std::fs::write(&path, b"\xc4\xe3\xba\xc3 needle\n").unwrap();
+        let matcher = SearchPattern::Text("你好".to_string()).get_line_matcher().unwrap();
+
+        let decoding: Decoding = "gbk".parse().unwrap();
+        let search = search_file_in_range(&path, &matcher, &LineRange::default(), decoding, ContextLines::default(), 1000, None, false).unwrap();
+        assert_eq!(search.results[0].line, "你好 needle");
+        assert_eq!(search.results[0].encoding, Some("GBK"));
+
+        assert_eq!("utf-8".parse::<Decoding>().unwrap(), Decoding::Strict);
+        assert_eq!("AUTO".parse::<Decoding>().unwrap(), Decoding::Auto);
+        assert!("klingon".parse::<Decoding>().is_err());
+    }
+
+    #[test]
+    fn test_search_result_ordering() {
+        let result = |path: &str, line_number: u64, column: usize| SearchResult {
+            path: path.to_string(),
+            line_number,
+            line: "x".repeat(20),
+            matched_text: "x".to_string(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![(column, column + 1)],
+            matches_truncated: false,
+            total_lines: 100,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        };
+
+        let expected = vec![
+            result("a.txt", 2, 0),
+            result("a.txt", 2, 7),
+            result("a.txt", 10, 3),
+            result("b/c.txt", 1, 0),
+            result("b/c.txt", 1, 0),
+            result("b/d.txt", 1, 5),
+        ];
+        let mut shuffled = vec![
+            expected[4].clone(),
+            expected[2].clone(),
+            expected[5].clone(),
+            expected[0].clone(),
+            expected[3].clone(),
+            expected[1].clone(),
+        ];
+        shuffled.sort();
+        assert_eq!(shuffled, expected);
+
+        // 位置相同但内容不同的结果不相等，顺序仍然确定
+        let mut other = result("a.txt", 2, 0);
+        other.line = "y".repeat(20);
+        assert_ne!(other, expected[0]);
+        assert_eq!(other.cmp(&expected[0]), std::cmp::Ordering::Greater);
+        assert_eq!(expected[3].cmp(&expected[4]), std::cmp::Ordering::Equal);
+    }
 }
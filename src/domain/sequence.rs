@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::search::{
+    count_lines, find_line_matches, get_context_lines, merge_overlapping_context, read_text_file, ContextLines, Decoding,
+    FileSearch, LineMatcher, SearchResult,
+};
+
+/// 有序的两个模式：A 之后 `within` 行内出现 B
+///
+/// 窗口按行号计算，两端都包含：A 在第 `a` 行时，B 必须出现在第 `a + 1` 到 `a + within` 行之间，
+/// 与 A 同一行的 B 不算。窗口内出现新的 A 时以最近的 A 为准，
+/// 每个 A 只与它之后的第一个 B 配对，配对后需要新的 A 才能再次配对。
+#[derive(Debug, Clone)]
+pub struct SequenceMatcher {
+    pub first: LineMatcher,
+    pub second: LineMatcher,
+    pub within: u64,
+}
+
+/// 在单个文件中查找 A 后跟 B 的序列，每对匹配输出 A 行和 B 行两个结果
+///
+/// 最多保留 `max_count` 个结果，先截断再合并上下文，保留的最后一个结果的后文不受被丢弃的结果影响。
+pub fn search_sequence_file(
+    path: &Path,
+    sequence: &SequenceMatcher,
+    decoding: Decoding,
+    context: ContextLines,
+    max_matches_per_line: usize,
+    max_count: Option<usize>,
+) -> Result<FileSearch> {
+    let (content, encoding) = read_text_file(path, decoding)?;
+    let total_lines = count_lines(content.as_bytes());
+
+    // 两个模式都不可能出现时无需逐行处理
+    if !sequence.first.may_match(content.as_bytes()) || !sequence.second.may_match(content.as_bytes()) {
+        return Ok(FileSearch {
+            results: Vec::new(),
+            lines_scanned: total_lines,
+        });
+    }
+
+    let lines: Vec<String> = if !context.is_empty() {
+        content.lines().map(|s| s.to_string()).collect()
+    } else {
+        Vec::new()
+    };
+    let is_binary = memchr::memchr(0, content.as_bytes()).is_some();
+    let to_result = |line_idx: usize, line: &str, matches: Vec<(usize, usize)>, matches_truncated: bool| {
+        let (start, end) = matches[0];
+        let (context_before, context_after) = if !context.is_empty() {
+            (
+                get_context_lines(&lines, line_idx, context, true),
+                get_context_lines(&lines, line_idx, context, false),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        SearchResult {
+            path: path.to_string_lossy().to_string(),
+            line_number: (line_idx + 1) as u64,
+            line: line.to_string(),
+            matched_text: String::from_utf8_lossy(&line.as_bytes()[start..end]).to_string(),
+            context_before,
+            context_after,
+            matches,
+            matches_truncated,
+            total_lines,
+            line_offset: (line.as_ptr() as usize - content.as_ptr() as usize) as u64,
+            encoding,
+            is_binary,
+        }
+    };
+
+    let mut results = Vec::new();
+    // 最近一个尚未配对的 A
+    let mut pending: Option<SearchResult> = None;
+
+    for (line_idx, line) in content.lines().enumerate() {
+        let line_number = (line_idx + 1) as u64;
+        if pending.as_ref().is_some_and(|a| line_number - a.line_number > sequence.within) {
+            pending = None;
+        }
+
+        // 先用本行完成之前的 A，再把本行作为新的 A，因此同时匹配两者的行可以既是 B 又是下一个 A
+        if pending.is_some() {
+            let (matches, truncated) = find_line_matches(&sequence.second, line.as_bytes(), max_matches_per_line);
+            if !matches.is_empty() {
+                results.extend(pending.take());
+                results.push(to_result(line_idx, line, matches, truncated));
+            }
+        }
+
+        let (matches, truncated) = find_line_matches(&sequence.first, line.as_bytes(), max_matches_per_line);
+        if !matches.is_empty() {
+            pending = Some(to_result(line_idx, line, matches, truncated));
+        }
+    }
+
+    // 同一行既是上一对的 B 又是下一对的 A 时只保留一个结果
+    results.dedup_by_key(|result| result.line_number);
+    if let Some(max) = max_count {
+        results.truncate(max);
+    }
+    merge_overlapping_context(&mut results);
+
+    Ok(FileSearch {
+        results,
+        lines_scanned: total_lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::search::SearchPattern;
+
+    fn sequence(first: &str, second: &str, within: u64) -> SequenceMatcher {
+        SequenceMatcher {
+            first: SearchPattern::Text(first.to_string()).get_line_matcher().unwrap(),
+            second: SearchPattern::Text(second.to_string()).get_line_matcher().unwrap(),
+            within,
+        }
+    }
+
+    fn search(content: &str, sequence: &SequenceMatcher, context: ContextLines, max_count: Option<usize>) -> Vec<SearchResult> {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("app.log");
+        std::fs::write(&path, content).unwrap();
+        search_sequence_file(&path, sequence, Decoding::Auto, context, 1000, max_count)
+            .unwrap()
+            .results
+    }
+
+    fn matched_lines(content: &str, sequence: &SequenceMatcher) -> Vec<u64> {
+        search(content, sequence, ContextLines::default(), None)
+            .iter()
+            .map(|r| r.line_number)
+            .collect()
+    }
+
+    #[test]
+    fn test_second_pattern_inside_window() {
+        let content = "connect\nnoise\nnoise\ntimeout\n";
+        // B 在 A 之后 3 行，窗口包含边界
+        assert_eq!(matched_lines(content, &sequence("connect", "timeout", 3)), vec![1, 4]);
+        assert_eq!(matched_lines(content, &sequence("connect", "timeout", 10)), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_second_pattern_outside_window() {
+        let content = "connect\nnoise\nnoise\ntimeout\n";
+        assert!(matched_lines(content, &sequence("connect", "timeout", 2)).is_empty());
+        // B 在 A 之前不算
+        assert!(matched_lines("timeout\nconnect\n", &sequence("connect", "timeout", 5)).is_empty());
+        // 同一行不算
+        assert!(matched_lines("connect timeout\n", &sequence("connect", "timeout", 5)).is_empty());
+    }
+
+    #[test]
+    fn test_latest_first_pattern_wins_and_pairs_once() {
+        let content = "connect 1\nconnect 2\ntimeout\ntimeout\n";
+        // 第 2 行的 A 取代第 1 行，配对后第 4 行的 B 没有可配对的 A
+        assert_eq!(matched_lines(content, &sequence("connect", "timeout", 5)), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_line_can_close_one_pair_and_open_the_next() {
+        let content = "step\nstep\nstep\n";
+        assert_eq!(matched_lines(content, &sequence("step", "step", 1)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_max_count_keeps_context_of_last_result() {
+        let content = "connect
+timeout
+noise
+";
+        let context = ContextLines { before: 0, after: 2 };
+        let results = search(content, &sequence("connect", "timeout", 1), context, Some(1));
+        // 被截断的 B 行不会挤掉 A 行的后文
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].context_after, vec!["timeout", "noise"]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use git2::{Blame, Repository};
+
+/// 一行的 git blame 信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub author: String,
+    /// 提交的短哈希，工作区中尚未提交的行为 None
+    pub commit: Option<String>,
+}
+
+impl BlameLine {
+    /// 格式化为 `作者 提交`，未提交的行显示为 `未提交`
+    pub fn format(&self) -> String {
+        match &self.commit {
+            Some(commit) => format!("{} {}", self.author, commit),
+            None => "未提交".to_string(),
+        }
+    }
+}
+
+/// 按文件缓存的 git blame
+///
+/// 每个文件只 blame 一次（针对工作区中的当前内容，因此行号与搜索结果一致），之后的匹配直接查表。
+/// 搜索结果按文件成批到达，所以只保留最近一个文件的 blame，内存占用与文件数无关。
+/// 不在 git 仓库中或未被跟踪的文件没有 blame 信息。
+#[derive(Default)]
+pub struct BlameCache {
+    repositories: Vec<Repository>,
+    /// 已确认不在任何仓库中的目录
+    outside_repository: HashSet<PathBuf>,
+    /// 最近一个文件的路径及其每一行的 blame
+    current: Option<(String, Option<Vec<BlameLine>>)>,
+}
+
+impl BlameCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取文件中某一行（从 1 开始）的 blame 信息
+    pub fn line(&mut self, path: &str, line_number: u64) -> Option<&BlameLine> {
+        if self.current.as_ref().is_none_or(|(current, _)| current != path) {
+            let lines = self.blame_file(Path::new(path));
+            self.current = Some((path.to_string(), lines));
+        }
+
+        let (_, lines) = self.current.as_ref()?;
+        lines.as_ref()?.get(line_number.checked_sub(1)? as usize)
+    }
+
+    /// blame 整个文件，失败时返回 None
+    fn blame_file(&mut self, path: &Path) -> Option<Vec<BlameLine>> {
+        let path = path.canonicalize().ok()?;
+        let repository = self.repository_for(&path)?;
+        let relative = path.strip_prefix(repository.workdir()?.canonicalize().ok()?).ok()?;
+
+        let content = std::fs::read(&path).ok()?;
+        let committed = repository.blame_file(relative, None).ok()?;
+        // 在已提交的 blame 上叠加工作区内容，修改过的行视为未提交
+        let blame = committed.blame_buffer(&content).ok()?;
+
+        let line_count = content.split(|&b| b == b'\n').count();
+        Some((1..=line_count).map(|line| blame_line(&blame, line)).collect())
+    }
+
+    /// 查找包含该路径的仓库，打开过的仓库会被复用
+    fn repository_for(&mut self, path: &Path) -> Option<&Repository> {
+        let dir = path.parent()?;
+        let existing = self.repositories.iter().position(|repository| {
+            repository.workdir()
+                .and_then(|workdir| workdir.canonicalize().ok())
+                .is_some_and(|workdir| dir.starts_with(workdir))
+        });
+        if let Some(index) = existing {
+            return self.repositories.get(index);
+        }
+        if self.outside_repository.contains(dir) {
+            return None;
+        }
+
+        match Repository::discover(dir) {
+            Ok(repository) if repository.workdir().is_some() => {
+                self.repositories.push(repository);
+                self.repositories.last()
+            }
+            _ => {
+                self.outside_repository.insert(dir.to_path_buf());
+                None
+            }
+        }
+    }
+}
+
+fn blame_line(blame: &Blame, line: usize) -> BlameLine {
+    let Some(hunk) = blame.get_line(line) else {
+        return BlameLine { author: String::new(), commit: None };
+    };
+
+    // 未提交的行没有签名，不能访问 final_signature
+    let commit_id = hunk.final_commit_id();
+    if commit_id.is_zero() {
+        return BlameLine { author: String::new(), commit: None };
+    }
+    let author = hunk.final_signature().name().unwrap_or_default().to_string();
+    BlameLine {
+        author,
+        commit: Some(commit_id.to_string()[..8].to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    fn commit_all(repository: &Repository, author: &str, message: &str) {
+        let mut index = repository.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now(author, &format!("{}@example.com", author)).unwrap();
+        let parent = repository.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repository.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap();
+    }
+
+    #[test]
+    fn test_blame_committed_and_uncommitted_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repository = Repository::init(temp_dir.path()).unwrap();
+        let file = temp_dir.path().join("main.rs");
+
+        std::fs::write(&file, "fn a() {}\n").unwrap();
+        commit_all(&repository, "alice", "first");
+        std::fs::write(&file, "fn a() {}\nfn b() {}\n").unwrap();
+        commit_all(&repository, "bob", "second");
+        // 工作区中追加一行，不提交
+        std::fs::write(&file, "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+
+        let mut cache = BlameCache::new();
+        let path = file.to_string_lossy().to_string();
+        assert_eq!(cache.line(&path, 1).unwrap().author, "alice");
+        let second = cache.line(&path, 2).unwrap().clone();
+        assert_eq!(second.author, "bob");
+        assert_eq!(second.commit.as_ref().unwrap().len(), 8);
+        assert_eq!(cache.line(&path, 3).unwrap().format(), "未提交");
+    }
+
+    #[test]
+    fn test_no_blame_outside_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "hello\n").unwrap();
+
+        let mut cache = BlameCache::new();
+        assert!(cache.line(&file.to_string_lossy(), 1).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -7,19 +7,64 @@ use std::collections::HashMap;
 use anyhow::Result;
 use chrono::Local;
 
+use super::logging::create_log_file;
+
 /// 错误类型分类
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ErrorType {
     /// 文件读取错误
     FileRead,
+    /// 文件读取超时
+    Timeout,
+    /// 目录遍历错误
+    Traversal,
+    /// 没有访问权限
+    PermissionDenied,
+    /// 无法读取文件元数据（大小、修改时间等）
+    MetadataRead,
+    /// 内容不是有效的 UTF-8
+    InvalidUtf8,
 }
 
 impl ErrorType {
     pub fn as_str(&self) -> &'static str {
         match self {
             ErrorType::FileRead => "文件读取",
+            ErrorType::Timeout => "读取超时",
+            ErrorType::Traversal => "目录遍历",
+            ErrorType::PermissionDenied => "权限不足",
+            ErrorType::MetadataRead => "元数据读取",
+            ErrorType::InvalidUtf8 => "无效 UTF-8",
+        }
+    }
+
+    /// 按 IO 错误的种类分类，无法细分时返回 `fallback`
+    ///
+    /// `InvalidData` 也用于 UTF-8 以外的数据错误（如 gzip 格式错误），只有内部错误是 UTF-8 解码错误时才算无效 UTF-8。
+    pub fn from_io_error(err: &std::io::Error, fallback: ErrorType) -> ErrorType {
+        match err.kind() {
+            std::io::ErrorKind::PermissionDenied => ErrorType::PermissionDenied,
+            _ if err.get_ref().is_some_and(|inner| is_utf8_error(inner)) => ErrorType::InvalidUtf8,
+            _ => fallback,
         }
     }
+
+    /// 按错误链中最先出现的 IO 或 UTF-8 解码错误分类，无法细分时返回 `fallback`
+    pub fn classify(err: &anyhow::Error, fallback: ErrorType) -> ErrorType {
+        for cause in err.chain() {
+            if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+                return Self::from_io_error(io_err, fallback);
+            }
+            if is_utf8_error(cause) {
+                return ErrorType::InvalidUtf8;
+            }
+        }
+        fallback
+    }
+}
+
+fn is_utf8_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.is::<std::str::Utf8Error>() || err.is::<std::string::FromUtf8Error>()
 }
 
 
@@ -35,6 +80,11 @@ pub struct ErrorLogger {
 impl ErrorLogger {
     /// 创建新的错误日志记录器
     pub fn new(enabled: bool) -> Result<Self> {
+        Self::with_bom(enabled, true)
+    }
+
+    /// 创建新的错误日志记录器，`write_bom` 控制日志文件开头是否写入 UTF-8 BOM
+    pub fn with_bom(enabled: bool, write_bom: bool) -> Result<Self> {
         if !enabled {
             return Ok(Self {
                 error_file: Arc::new(Mutex::new(None)),
@@ -52,16 +102,9 @@ impl ErrorLogger {
         let error_path = PathBuf::from(format!("error_{}.log", timestamp));
         
         // 创建错误日志文件
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .append(true)
-            .open(&error_path)?;
-            
-        // 写入UTF-8 BOM以确保文件被正确识别为UTF-8
+        let file = create_log_file(&error_path, write_bom)?;
         let mut file_clone = file.try_clone()?;
-        file_clone.write_all(&[0xEF, 0xBB, 0xBF])?; // UTF-8 BOM
-            
+
         // 写入错误日志头部信息
         writeln!(file_clone, "# FindEverything 错误日志")?;
         writeln!(file_clone, "# 开始时间: {}", now.format("%Y-%m-%d %H:%M:%S"))?;
@@ -179,20 +222,26 @@ impl ErrorLogger {
 
     /// 打印错误摘要到控制台
     pub fn print_error_summary(&self) {
+        let _ = self.write_error_summary(&mut std::io::stdout().lock());
+    }
+
+    /// 将错误摘要写入指定输出，没有错误时不输出任何内容
+    pub fn write_error_summary(&self, w: &mut impl Write) -> Result<()> {
         if !self.has_errors() {
-            return;
+            return Ok(());
         }
 
-        println!("\n⚠️  搜索过程中发现错误:");
-        println!("----------------------------");
+        writeln!(w, "\n⚠️  搜索过程中发现错误:")?;
+        writeln!(w, "----------------------------")?;
         
         let summary = self.get_error_summary();
         for (error_type, count) in &summary {
-            println!("  {}: {} 次", error_type.as_str(), count);
+            writeln!(w, "  {}: {} 次", error_type.as_str(), count)?;
         }
         
-        println!("  总计: {} 个错误", self.get_total_errors());
-        println!("  详细错误信息请查看: {}", self.error_path.display());
+        writeln!(w, "  总计: {} 个错误", self.get_total_errors())?;
+        writeln!(w, "  详细错误信息请查看: {}", self.error_path.display())?;
+        Ok(())
     }
 }
 
@@ -228,5 +277,33 @@ mod tests {
     #[test]
     fn test_error_types() {
         assert_eq!(ErrorType::FileRead.as_str(), "文件读取");
+        assert_eq!(ErrorType::Timeout.as_str(), "读取超时");
+        assert_eq!(ErrorType::Traversal.as_str(), "目录遍历");
+        assert_eq!(ErrorType::PermissionDenied.as_str(), "权限不足");
+        assert_eq!(ErrorType::MetadataRead.as_str(), "元数据读取");
+        assert_eq!(ErrorType::InvalidUtf8.as_str(), "无效 UTF-8");
+    }
+
+    #[test]
+    fn test_error_classification() {
+        use std::io::{Error, ErrorKind};
+
+        let denied = anyhow::Error::new(Error::from(ErrorKind::PermissionDenied)).context("无法读取文件: a.txt");
+        assert_eq!(ErrorType::classify(&denied, ErrorType::FileRead), ErrorType::PermissionDenied);
+
+        let not_found = anyhow::Error::new(Error::from(ErrorKind::NotFound));
+        assert_eq!(ErrorType::classify(&not_found, ErrorType::FileRead), ErrorType::FileRead);
+        assert_eq!(ErrorType::classify(&not_found, ErrorType::MetadataRead), ErrorType::MetadataRead);
+
+        let utf8 = anyhow::Error::new(String::from_utf8(vec![0xFF]).unwrap_err()).context("解码失败");
+        assert_eq!(ErrorType::classify(&utf8, ErrorType::FileRead), ErrorType::InvalidUtf8);
+
+        // 其他数据格式错误保持调用方给出的类型
+        let corrupt = anyhow::Error::new(Error::new(ErrorKind::InvalidData, "corrupt deflate stream"));
+        assert_eq!(ErrorType::classify(&corrupt, ErrorType::FileRead), ErrorType::FileRead);
+        let wrapped = Error::new(ErrorKind::InvalidData, String::from_utf8(vec![0xFF]).unwrap_err());
+        assert_eq!(ErrorType::from_io_error(&wrapped, ErrorType::FileRead), ErrorType::InvalidUtf8);
+
+        assert_eq!(ErrorType::classify(&anyhow::anyhow!("其他错误"), ErrorType::FileRead), ErrorType::FileRead);
     }
 }
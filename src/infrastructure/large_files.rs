@@ -0,0 +1,160 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+/// 限制同时读取的大文件数量
+///
+/// 搜索时整个文件会被读入内存，多个线程同时读取几百 MB 的文件会使内存占用成倍增长。
+/// 超过阈值的文件需要先取得许可才能读取，小文件不受限制，因此峰值内存与线程数无关。
+pub struct LargeFileLimiter {
+    threshold: u64,
+    max_concurrent: usize,
+    state: Mutex<LimiterState>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    active: usize,
+    peak: usize,
+}
+
+/// 大文件读取许可，离开作用域时归还
+pub struct LargeFilePermit<'a> {
+    limiter: &'a LargeFileLimiter,
+}
+
+/// 持有限制器所有权的大文件读取许可，可以移动到其他线程，离开作用域时归还
+pub struct OwnedLargeFilePermit {
+    limiter: Arc<LargeFileLimiter>,
+}
+
+impl LargeFileLimiter {
+    /// 大小超过 `threshold` 字节的文件最多同时读取 `max_concurrent` 个
+    pub fn new(threshold: u64, max_concurrent: usize) -> Self {
+        Self {
+            threshold,
+            max_concurrent: max_concurrent.max(1),
+            state: Mutex::new(LimiterState::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// 文件大小超过阈值时等待并取得许可，小文件直接返回 None
+    pub fn acquire(&self, file_size: u64) -> Option<LargeFilePermit<'_>> {
+        if file_size <= self.threshold {
+            return None;
+        }
+
+        self.wait_for_slot();
+        Some(LargeFilePermit { limiter: self })
+    }
+
+    /// 与 [`LargeFileLimiter::acquire`] 相同，但许可持有限制器的所有权，
+    /// 可以移动到读取文件的线程中，直到读取真正结束才归还
+    pub fn acquire_owned(self: &Arc<Self>, file_size: u64) -> Option<OwnedLargeFilePermit> {
+        if file_size <= self.threshold {
+            return None;
+        }
+
+        self.wait_for_slot();
+        Some(OwnedLargeFilePermit { limiter: Arc::clone(self) })
+    }
+
+    fn wait_for_slot(&self) {
+        let mut state = self.state.lock().unwrap();
+        while state.active >= self.max_concurrent {
+            state = self.released.wait(state).unwrap();
+        }
+        state.active += 1;
+        state.peak = state.peak.max(state.active);
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        self.released.notify_one();
+    }
+
+    /// 同时读取的大文件数的峰值
+    pub fn peak(&self) -> usize {
+        self.state.lock().unwrap().peak
+    }
+}
+
+impl Drop for LargeFilePermit<'_> {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+impl Drop for OwnedLargeFilePermit {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_small_files_need_no_permit() {
+        let limiter = LargeFileLimiter::new(1024, 1);
+        let _large = limiter.acquire(4096).unwrap();
+        // 大文件许可被占用时小文件不受影响
+        assert!(limiter.acquire(1024).is_none());
+        assert_eq!(limiter.peak(), 1);
+    }
+
+    #[test]
+    fn test_limits_concurrent_large_reads() {
+        let limiter = Arc::new(LargeFileLimiter::new(0, 2));
+        let reading = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+
+        let workers: Vec<_> = (0..6)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                let reading = Arc::clone(&reading);
+                let max_seen = Arc::clone(&max_seen);
+                std::thread::spawn(move || {
+                    let _permit = limiter.acquire(1).unwrap();
+                    let now = reading.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_seen.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    reading.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert!(max_seen.load(Ordering::SeqCst) <= 2);
+        assert!(limiter.peak() <= 2);
+    }
+
+    #[test]
+    fn test_owned_permit_is_released_where_it_is_dropped() {
+        let limiter = Arc::new(LargeFileLimiter::new(0, 1));
+        let permit = limiter.acquire_owned(1).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        // 许可随读取线程一起移动，等待方直到该线程结束才能取得许可
+        let reader = std::thread::spawn(move || {
+            let _permit = permit;
+            rx.recv().unwrap();
+        });
+        let limiter_clone = Arc::clone(&limiter);
+        let waiter = std::thread::spawn(move || limiter_clone.acquire_owned(1).is_some());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+
+        tx.send(()).unwrap();
+        reader.join().unwrap();
+        assert!(waiter.join().unwrap());
+        assert_eq!(limiter.peak(), 1);
+    }
+}
@@ -14,6 +14,22 @@ pub trait LoggerTrait: Send + Sync {
     fn finalize(&self, total_files: u64, matched_files: u64, total_matches: u64, duration: std::time::Duration) -> Result<()>;
 }
 
+/// 创建日志文件（追加模式），`write_bom` 为 true 时写入 UTF-8 BOM
+///
+/// BOM 用于让 Windows 记事本等工具正确识别 UTF-8 编码，但部分日志收集工具会将其视为多余字节
+pub(crate) fn create_log_file(path: &Path, write_bom: bool) -> Result<File> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    if write_bom {
+        file.write_all(&[0xEF, 0xBB, 0xBF])?; // UTF-8 BOM
+    }
+
+    Ok(file)
+}
+
 /// 调试日志记录器（用于系统状态和调试信息）
 pub struct Logger {
     log_file: Arc<Mutex<Option<File>>>,
@@ -23,6 +39,11 @@ pub struct Logger {
 impl Logger {
     /// 创建新的日志记录器
     pub fn new(enabled: bool) -> Result<Self> {
+        Self::with_bom(enabled, true)
+    }
+
+    /// 创建新的日志记录器，`write_bom` 控制日志文件开头是否写入 UTF-8 BOM
+    pub fn with_bom(enabled: bool, write_bom: bool) -> Result<Self> {
         if !enabled {
             return Ok(Self {
                 log_file: Arc::new(Mutex::new(None)),
@@ -38,16 +59,9 @@ impl Logger {
         let log_path = PathBuf::from(format!("debug_{}.log", timestamp));
         
         // 创建日志文件
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .append(true)
-            .open(&log_path)?;
-            
-        // 写入UTF-8 BOM以确保文件被正确识别为UTF-8
+        let file = create_log_file(&log_path, write_bom)?;
         let mut file_clone = file.try_clone()?;
-        file_clone.write_all(&[0xEF, 0xBB, 0xBF])?; // UTF-8 BOM
-            
+
         // 写入调试日志头部信息
         writeln!(file_clone, "# FindEverything 调试日志")?;
         writeln!(file_clone, "# 开始时间: {}", now.format("%Y-%m-%d %H:%M:%S"))?;
@@ -146,6 +160,20 @@ mod tests {
         assert!(logger.is_enabled());
     }
 
+    #[test]
+    fn test_create_log_file_bom() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let with_bom = temp_dir.path().join("with_bom.log");
+        drop(create_log_file(&with_bom, true).unwrap());
+        assert_eq!(std::fs::read(&with_bom).unwrap(), vec![0xEF, 0xBB, 0xBF]);
+
+        let without_bom = temp_dir.path().join("without_bom.log");
+        let mut file = create_log_file(&without_bom, false).unwrap();
+        writeln!(file, "# log").unwrap();
+        assert_eq!(std::fs::read(&without_bom).unwrap(), b"# log\n");
+    }
+
     #[test]
     fn test_logger_trait() {
         let logger = Logger::new(true).unwrap();
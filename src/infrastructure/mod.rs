@@ -1,7 +1,11 @@
 pub mod logging;
 pub mod error_logging;
 pub mod monitoring;
+pub mod large_files;
+pub mod blame;
 
 pub use logging::{Logger, LoggerTrait};
 pub use error_logging::{ErrorLogger, ErrorType};
 pub use monitoring::{CpuMonitor, MonitoringTrait};
+pub use large_files::{LargeFileLimiter, OwnedLargeFilePermit};
+pub use blame::BlameCache;
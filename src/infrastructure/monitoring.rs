@@ -7,7 +7,7 @@ use anyhow::Result;
 use sysinfo::System;
 
 use crate::application::Config;
-use crate::infrastructure::{LoggerTrait, Logger};
+use crate::infrastructure::LoggerTrait;
 
 /// 监控trait
 pub trait MonitoringTrait: Send + Sync {
@@ -15,34 +15,52 @@ pub trait MonitoringTrait: Send + Sync {
     fn stop(&self);
     fn apply_throttle(&self);
     fn get_cpu_usage(&self) -> f32;
+    /// 最近一次采样的系统已用内存（MB）
+    fn get_memory_usage(&self) -> u64;
     fn should_throttle(&self) -> bool;
     fn get_status(&self) -> MonitorStatus;
 }
 
-/// CPU监控器
+/// CPU监控器，同时监控系统内存，任一项超过阈值时限流
 pub struct CpuMonitor {
     cpu_threshold: f32,
+    /// 内存阈值（MB），0 表示不按内存限流
+    memory_threshold_mb: u64,
     search_delay_ms: u64,
     current_cpu_usage: Arc<AtomicU64>, // 存储CPU使用率 * 100
+    current_memory_mb: Arc<AtomicU64>,
     should_throttle: Arc<AtomicBool>,
     is_running: Arc<AtomicBool>,
-    logger: Arc<Logger>,
+    /// 能否读取到有效的 CPU 统计（容器等环境中可能读不到）
+    stats_available: Arc<AtomicBool>,
+    logger: Arc<dyn LoggerTrait>,
 }
 
 impl CpuMonitor {
     /// 创建新的CPU监控器
-    pub fn new(config: &Config, logger: Arc<Logger>) -> Self {
+    pub fn new(config: &Config, logger: Arc<dyn LoggerTrait>) -> Self {
         Self {
             cpu_threshold: config.performance.cpu_threshold,
+            memory_threshold_mb: config.performance.memory_threshold_mb,
             search_delay_ms: config.performance.search_delay_ms,
             current_cpu_usage: Arc::new(AtomicU64::new(0)),
+            current_memory_mb: Arc::new(AtomicU64::new(0)),
             should_throttle: Arc::new(AtomicBool::new(false)),
             is_running: Arc::new(AtomicBool::new(false)),
+            stats_available: Arc::new(AtomicBool::new(true)),
             logger,
         }
     }
 }
 
+/// 计算平均 CPU 使用率，CPU 列表为空或读数不合理（NaN、负数、超过 100%）时返回 None
+fn average_cpu_usage(usages: &[f32]) -> Option<f32> {
+    if usages.is_empty() || usages.iter().any(|usage| !(0.0..=100.0).contains(usage)) {
+        return None;
+    }
+    Some(usages.iter().sum::<f32>() / usages.len() as f32)
+}
+
 impl MonitoringTrait for CpuMonitor {
     fn start(&self) -> Result<()> {
         if self.is_running.load(Ordering::Relaxed) {
@@ -52,36 +70,59 @@ impl MonitoringTrait for CpuMonitor {
         self.is_running.store(true, Ordering::Relaxed);
         
         let cpu_threshold = self.cpu_threshold;
+        let memory_threshold_mb = self.memory_threshold_mb;
         let current_cpu_usage = Arc::clone(&self.current_cpu_usage);
+        let current_memory_mb = Arc::clone(&self.current_memory_mb);
         let should_throttle = Arc::clone(&self.should_throttle);
         let is_running = Arc::clone(&self.is_running);
+        let stats_available = Arc::clone(&self.stats_available);
         let logger = Arc::clone(&self.logger);
 
         thread::spawn(move || {
             let mut system = System::new_all();
             let mut last_log_time = Instant::now();
+            let mut warned_unavailable = false;
             
             while is_running.load(Ordering::Relaxed) {
                 system.refresh_cpu();
+                system.refresh_memory();
+
+                let memory_mb = system.used_memory() / (1024 * 1024);
+                current_memory_mb.store(memory_mb, Ordering::Relaxed);
+                let memory_exceeded = memory_threshold_mb > 0 && memory_mb > memory_threshold_mb;
                 
                 // 计算平均CPU使用率
-                let cpu_usage = system.cpus().iter()
-                    .map(|cpu| cpu.cpu_usage())
-                    .sum::<f32>() / system.cpus().len() as f32;
+                let usages: Vec<f32> = system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+                let Some(cpu_usage) = average_cpu_usage(&usages) else {
+                    // 读不到有效的 CPU 统计时只按内存限流，只在第一次记录警告
+                    should_throttle.store(memory_exceeded, Ordering::Relaxed);
+                    current_cpu_usage.store(0, Ordering::Relaxed);
+                    stats_available.store(false, Ordering::Relaxed);
+                    if !warned_unavailable && logger.is_enabled() {
+                        warned_unavailable = true;
+                        let _ = logger.log_message(&format!(
+                            "警告: 无法读取有效的 CPU 统计（CPU 数: {}），已停用 CPU 限流",
+                            usages.len()
+                        ));
+                    }
+                    thread::sleep(Duration::from_secs(1));
+                    continue;
+                };
+                stats_available.store(true, Ordering::Relaxed);
                 
                 // 存储CPU使用率（乘以100以便用整数存储）
                 current_cpu_usage.store((cpu_usage * 100.0) as u64, Ordering::Relaxed);
                 
                 // 检查是否需要限流
-                let needs_throttle = cpu_usage > cpu_threshold;
+                let needs_throttle = cpu_usage > cpu_threshold || memory_exceeded;
                 should_throttle.store(needs_throttle, Ordering::Relaxed);
                 
-                // 每5秒记录一次CPU使用率
+                // 每5秒记录一次CPU和内存使用情况
                 if logger.is_enabled() && last_log_time.elapsed() >= Duration::from_secs(5) {
                     let status = if needs_throttle { "限流中" } else { "正常" };
                     let _ = logger.log_message(&format!(
-                        "CPU使用率: {:.1}% (阈值: {:.1}%) - {}",
-                        cpu_usage, cpu_threshold, status
+                        "CPU使用率: {:.1}% (阈值: {:.1}%), 内存: {} MB (阈值: {}) - {}",
+                        cpu_usage, cpu_threshold, memory_mb, format_memory_threshold(memory_threshold_mb), status
                     ));
                     last_log_time = Instant::now();
                 }
@@ -92,8 +133,8 @@ impl MonitoringTrait for CpuMonitor {
 
         if self.logger.is_enabled() {
             self.logger.log_message(&format!(
-                "CPU监控已启动 - 阈值: {:.1}%, 延迟: {}ms",
-                self.cpu_threshold, self.search_delay_ms
+                "CPU监控已启动 - 阈值: {:.1}%, 内存阈值: {}, 延迟: {}ms",
+                self.cpu_threshold, format_memory_threshold(self.memory_threshold_mb), self.search_delay_ms
             ))?;
         }
 
@@ -112,6 +153,10 @@ impl MonitoringTrait for CpuMonitor {
         self.current_cpu_usage.load(Ordering::Relaxed) as f32 / 100.0
     }
 
+    fn get_memory_usage(&self) -> u64 {
+        self.current_memory_mb.load(Ordering::Relaxed)
+    }
+
     fn should_throttle(&self) -> bool {
         self.should_throttle.load(Ordering::Relaxed)
     }
@@ -126,8 +171,11 @@ impl MonitoringTrait for CpuMonitor {
         MonitorStatus {
             cpu_usage: self.get_cpu_usage(),
             cpu_threshold: self.cpu_threshold,
+            memory_usage_mb: self.get_memory_usage(),
+            memory_threshold_mb: self.memory_threshold_mb,
             is_throttling: self.should_throttle(),
             is_running: self.is_running.load(Ordering::Relaxed),
+            stats_available: self.stats_available.load(Ordering::Relaxed),
         }
     }
 }
@@ -138,22 +186,48 @@ impl Drop for CpuMonitor {
     }
 }
 
+/// 内存阈值的显示文本
+fn format_memory_threshold(threshold_mb: u64) -> String {
+    if threshold_mb == 0 {
+        "不限".to_string()
+    } else {
+        format!("{} MB", threshold_mb)
+    }
+}
+
 /// 监控状态信息
 #[derive(Debug, Clone)]
 pub struct MonitorStatus {
     pub cpu_usage: f32,
     pub cpu_threshold: f32,
+    /// 系统已用内存（MB）
+    pub memory_usage_mb: u64,
+    /// 内存阈值（MB），0 表示不按内存限流
+    pub memory_threshold_mb: u64,
     pub is_throttling: bool,
     pub is_running: bool,
+    /// 能否读取到有效的 CPU 统计
+    pub stats_available: bool,
 }
 
 impl MonitorStatus {
     /// 格式化状态信息
     pub fn format(&self) -> String {
+        let cpu = if self.stats_available {
+            format!("CPU: {:.1}%/{:.1}%", self.cpu_usage, self.cpu_threshold)
+        } else {
+            "CPU: 统计不可用".to_string()
+        };
+        let memory = if self.memory_threshold_mb == 0 {
+            format!("内存: {} MB", self.memory_usage_mb)
+        } else {
+            format!("内存: {} MB/{} MB", self.memory_usage_mb, self.memory_threshold_mb)
+        };
+
         format!(
-            "CPU: {:.1}%/{:.1}% {}{}",
-            self.cpu_usage,
-            self.cpu_threshold,
+            "{}, {} {}{}",
+            cpu,
+            memory,
             if self.is_throttling { "(限流)" } else { "(正常)" },
             if self.is_running { "" } else { " [已停止]" }
         )
@@ -191,4 +265,90 @@ mod tests {
         assert!(formatted.contains("CPU:"));
         assert!(formatted.contains("(正常)"));
     }
+
+    /// 记录所有日志消息的测试用日志记录器
+    #[derive(Default)]
+    struct RecordingLogger {
+        messages: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl LoggerTrait for RecordingLogger {
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn log_message(&self, message: &str) -> Result<()> {
+            self.messages.lock().unwrap().push(message.to_string());
+            Ok(())
+        }
+
+        fn log_file(&self, _path: &std::path::Path, _size: u64, _status: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn finalize(&self, _total_files: u64, _matched_files: u64, _total_matches: u64, _duration: Duration) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_monitor_with_custom_logger() {
+        let mut config = Config::default();
+        config.performance.search_delay_ms = 20;
+        let logger = Arc::new(RecordingLogger::default());
+        let monitor = CpuMonitor::new(&config, Arc::clone(&logger) as Arc<dyn LoggerTrait>);
+
+        monitor.start().unwrap();
+        // 限流时每个文件前等待 search_delay_ms
+        monitor.should_throttle.store(true, Ordering::Relaxed);
+        let started = Instant::now();
+        monitor.apply_throttle();
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        monitor.stop();
+
+        let messages = logger.messages.lock().unwrap().clone();
+        assert!(messages[0].starts_with("CPU监控已启动 - 阈值: 80.0%"), "{:?}", messages);
+        assert!(messages.iter().any(|message| message == "CPU监控已停止"), "{:?}", messages);
+    }
+
+    #[test]
+    fn test_status_format_includes_memory() {
+        let mut status = MonitorStatus {
+            cpu_usage: 12.5,
+            cpu_threshold: 80.0,
+            memory_usage_mb: 512,
+            memory_threshold_mb: 0,
+            is_throttling: false,
+            is_running: true,
+            stats_available: true,
+        };
+        assert_eq!(status.format(), "CPU: 12.5%/80.0%, 内存: 512 MB (正常)");
+
+        status.memory_threshold_mb = 256;
+        status.is_throttling = true;
+        assert_eq!(status.format(), "CPU: 12.5%/80.0%, 内存: 512 MB/256 MB (限流)");
+
+        status.stats_available = false;
+        status.is_running = false;
+        assert_eq!(status.format(), "CPU: 统计不可用, 内存: 512 MB/256 MB (限流) [已停止]");
+    }
+
+    #[test]
+    fn test_unavailable_cpu_stats() {
+        // 容器中可能读不到任何 CPU
+        assert_eq!(average_cpu_usage(&[]), None);
+        assert_eq!(average_cpu_usage(&[10.0, f32::NAN]), None);
+        assert_eq!(average_cpu_usage(&[250.0]), None);
+        assert_eq!(average_cpu_usage(&[-1.0]), None);
+        assert_eq!(average_cpu_usage(&[20.0, 40.0]), Some(30.0));
+
+        let config = Config::default();
+        let logger = Arc::new(Logger::new(false).unwrap());
+        let monitor = CpuMonitor::new(&config, logger);
+        monitor.stats_available.store(false, Ordering::Relaxed);
+
+        let status = monitor.get_status();
+        assert!(!status.is_throttling);
+        assert!(status.format().contains("统计不可用"));
+    }
 }
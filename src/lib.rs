@@ -3,9 +3,11 @@ pub mod domain;
 pub mod application;
 pub mod infrastructure;
 pub mod presentation;
+pub mod util;
 
 // 重新导出主要类型
 pub use domain::{SearchPattern, SearchResult, FileFilter};
 pub use application::Config;
 pub use infrastructure::{Logger, ErrorLogger, ErrorType, CpuMonitor};
 pub use presentation::{SearchSummary, print_search_result};
+pub use util::{parse_size, format_file_size, format_duration};
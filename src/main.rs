@@ -1,42 +1,137 @@
 use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use crossbeam_channel::bounded;
 
-// 使用新的模块结构
-mod domain;
-mod application;
-mod infrastructure;
-mod presentation;
+// 使用库中的模块结构
+use find_everything::{domain, application, infrastructure, presentation};
+use find_everything::parse_size;
 
-use application::Config;
+use application::{Config, ScanState};
 use infrastructure::{Logger, ErrorLogger, ErrorType, CpuMonitor, LoggerTrait, MonitoringTrait};
-use presentation::{SearchSummary, print_search_result};
-use domain::{SearchPattern, SearchResult, FileFilter};
+use presentation::{SearchSummary, GroupedPrinter, OutputTemplate, SummaryFormat};
+use presentation::ResultSink;
+use domain::{SearchPattern, SearchResult, ContextLines, FileFilter, MatcherOptions, WalkOptions, CaseMode, LineRange, LineCountFilter};
+use domain::file_walker::ScanAborted;
 
 /// 查找文件内容的命令行工具
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, disable_help_flag = true)]
 struct Args {
-    /// 要搜索的内容
-    #[clap(required = true)]
-    pattern: String,
+    /// 显示帮助信息（-h 用于 --no-filename）
+    #[clap(long, action = clap::ArgAction::Help)]
+    help: Option<bool>,
+
+    /// 要搜索的内容（使用 -e、--sequence 或 --int 时省略，此位置的参数作为搜索路径）
+    #[clap(required_unless_present_any = ["patterns", "sequence", "int"])]
+    pattern: Option<String>,
     
-    /// 要搜索的目录路径
+    /// 要搜索的目录路径，为 - 时搜索标准输入的内容
     #[clap()]
     path: Option<PathBuf>,
     
+    /// 要搜索的内容，可以多次指定，匹配其中任意一个的行都会被选中（如 -e foo -e bar）；
+    /// 每个模式都按 -r、-x 解释
+    #[clap(short = 'e', long = "regexp", value_name = "PATTERN", conflicts_with_all = ["sequence", "int"])]
+    patterns: Vec<String>,
+
     /// 使用正则表达式搜索
     #[clap(short, long)]
     regex: bool,
     
-    /// 将搜索内容解析为十六进制值
+    /// 将搜索内容解析为十六进制值，忽略空格；`??` 匹配任意字节，`4?`、`?4` 只固定高或低 4 位
     #[clap(short = 'x', long)]
     hex: bool,
+
+    /// 按字节搜索指定宽度和字节序的整数，格式为 值:宽度:字节序，如 305419896:32:le（即 78 56 34 12）。
+    /// 宽度为 16、32、64，字节序为 le、be，值可以是十进制、0x 开头的十六进制或负数（按补码）
+    #[clap(long, value_name = "VALUE:WIDTH:ENDIAN", conflicts_with_all = ["regex", "hex", "sequence", "replace"])]
+    int: Option<String>,
+
+    /// 忽略大小写（覆盖配置中的 case_mode，十六进制和 --int 模式总是按字节精确匹配）
+    #[clap(short = 'i', long, conflicts_with = "smart_case")]
+    ignore_case: bool,
+
+    /// 整词匹配：匹配的两端必须是单词边界，如 cat 不再匹配 category（不能与 --hex、--int 同时使用）
+    #[clap(short = 'w', long, conflicts_with_all = ["hex", "int"])]
+    word: bool,
+
+    /// 反向匹配：输出不匹配的行（与 --exclude-pattern 同时使用时，命中排除模式的行也会输出）
+    #[clap(short = 'v', long, conflicts_with_all = ["sequence", "json_path", "replace"])]
+    invert_match: bool,
+
+    /// 智能大小写：模式全为小写时忽略大小写，含大写字母时区分（覆盖配置中的 case_mode）
+    #[clap(short = 'S', long)]
+    smart_case: bool,
+
+    /// 使用 ASCII 单词边界（默认 Unicode：带重音的字母和中文等也视为单词字符）
+    #[clap(long)]
+    ascii_word_boundaries: bool,
+
+    /// 丢弃同时匹配此正则表达式的行，如搜索 password 但跳过包含 password_hash 的行。
+    /// 排除在主模式匹配之后逐行检查，大小写规则与主模式相同
+    #[clap(long, value_name = "REGEX")]
+    exclude_pattern: Option<String>,
+
+    /// 查找模式 A 之后 --within 行内出现模式 B 的位置，输出配对的两行（模式按 -r/-x 解析）
+    #[clap(long, num_args = 2, value_names = ["A", "B"], conflicts_with_all = ["follow_file", "json_path", "lines"])]
+    sequence: Option<Vec<String>>,
+
+    /// --sequence 的窗口：A 在第 N 行时 B 必须出现在第 N+1 到 N+LINES 行之间（两端都包含）
+    #[clap(long, value_name = "LINES", default_value_t = 10, requires = "sequence")]
+    within: u64,
+
+    /// 把匹配替换为此文本，支持 $1、${name} 等分组引用。指定搜索路径时预览每个匹配行替换前后的内容
+    /// （加 --in-place 才修改文件）；不指定搜索路径时为流过滤模式：从标准输入读取，
+    /// 替换后将所有行写到标准输出（类似 sed），未匹配的行原样输出（不能与 --hex、--int 同时使用）
+    #[clap(long, value_name = "TEXT", conflicts_with_all = [
+        "sequence", "exclude_pattern", "follow_file", "json", "stdin_paths", "stdin_paths0",
+        "files_from", "locations", "output_template", "blame", "hex",
+    ])]
+    replace: Option<String>,
+
+    /// 与 --replace 一起使用：将替换写回匹配的文件（先写入同目录下的临时文件，再重命名覆盖原文件）。
+    /// 文件中的每个匹配都会被替换，因此不能与只选中部分行或按其他方式解读内容的选项同时使用
+    #[clap(long, requires = "replace", conflicts_with_all = [
+        "max_count", "lines", "json_path", "sequence", "search_archives", "encoding",
+    ])]
+    in_place: bool,
+
+    /// 搜索前将最终编译的正则表达式输出到标准错误
+    #[clap(long)]
+    debug_matcher: bool,
     
+    /// 只搜索文件名（不含目录）匹配此正则表达式的文件的内容，如 '\.rs$'
+    #[clap(long, value_name = "REGEX")]
+    name_filter: Option<String>,
+
+    /// 搜索文件名而不是文件内容：模式与文件名（不含目录）匹配的文件被列出，不读取文件
+    #[clap(long = "name", conflicts_with_all = [
+        "match_path", "sequence", "json_path", "replace", "follow_file", "block_context", "lines", "search_archives",
+    ])]
+    match_name: bool,
+
+    /// 搜索完整路径而不是文件内容：模式与文件路径匹配的文件被列出，不读取文件
+    #[clap(long = "path", conflicts_with_all = [
+        "sequence", "json_path", "replace", "follow_file", "block_context", "lines", "search_archives",
+    ])]
+    match_path: bool,
+
+    /// 只搜索完全有效的 UTF-8 文件：含无效字节序列的文件（以及 UTF-16 文件）被跳过并计数，而不是有损解码后搜索
+    #[clap(long)]
+    utf8_only: bool,
+
+    /// 文件编码：auto（默认，带 BOM 的 UTF-16 按 UTF-16 解码，其余按 UTF-8 解码，无效字节替换为 U+FFFD）、
+    /// utf8（不是有效 UTF-8 的文件记为错误）或编码名，如 gbk、latin1、shift_jis（整个文件按该编码解码）
+    #[clap(long, value_name = "ENCODING", default_value = "auto", conflicts_with = "utf8_only")]
+    encoding: domain::search::Decoding,
+
     /// 最小文件大小 (例如 "1K", "1M", "1G")
     #[clap(long)]
     min_size: Option<String>,
@@ -45,14 +140,46 @@ struct Args {
     #[clap(long)]
     max_size: Option<String>,
     
+    /// 只搜索至少有 N 行的文件（需要先统计每个文件的行数，会额外读取一遍文件）
+    #[clap(long, value_name = "N")]
+    min_lines: Option<u64>,
+
+    /// 只搜索最多 N 行的文件（统计行数时超过 N 行立即停止读取）
+    #[clap(long, value_name = "N")]
+    max_lines: Option<u64>,
+
+    /// 最多同时读取 N 个大文件，限制峰值内存，小文件仍完全并行（0 表示不限制，默认使用配置）
+    #[clap(long, value_name = "N")]
+    max_concurrent_large_files: Option<usize>,
+
+    /// 超过此大小的文件视为大文件 (例如 "64M"，默认使用配置中的 large_file_threshold_mb)
+    #[clap(long, value_name = "SIZE")]
+    large_file_threshold: Option<String>,
+
+    /// 逐个成员搜索 .tar.gz / .tgz 包的内容（直接从解压流读取，不解压到磁盘），结果显示为 `压缩包:成员:行号`
+    #[clap(long, conflicts_with_all = ["json_path", "sequence", "follow_file"])]
+    search_archives: bool,
+
+    /// 压缩包中超过此大小的成员不读取 (例如 "64M")，跳过的成员在标准错误中提示
+    #[clap(long, value_name = "SIZE", requires = "search_archives")]
+    max_archive_member_size: Option<String>,
+
     /// 不使用并行处理 (默认使用所有可用CPU)
     #[clap(long)]
     no_parallel: bool,
+
+    /// 并行搜索的线程数（至少为 1），默认使用所有可用CPU
+    #[clap(long, value_name = "N", conflicts_with = "no_parallel")]
+    threads: Option<usize>,
     
     /// 启用详细日志记录，日志文件将保存到程序同级目录下
     #[clap(long)]
     log: bool,
 
+    /// 日志文件开头不写入 UTF-8 BOM（便于日志收集工具处理）
+    #[clap(long)]
+    log_no_bom: bool,
+
     /// 排除指定目录（用逗号分隔）
     #[clap(long)]
     exclude_dir: Option<String>,
@@ -60,56 +187,698 @@ struct Args {
     /// 排除文件路径列表文件
     #[clap(long)]
     exclude_file: Option<PathBuf>,
+
+    /// 只搜索这些扩展名的文件（用逗号分隔，不区分大小写，如 rs,toml），没有扩展名的文件被跳过
+    #[clap(long, value_name = "EXTS", value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// 只搜索文件名或路径匹配此 glob 模式的文件（如 '*.rs'，可重复指定，匹配任意一个即可）
+    #[clap(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// 跳过文件名或路径匹配此 glob 模式的文件（如 '*.min.js'、'target/**'，可重复指定）
+    #[clap(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// 跳过路径（以 / 分隔）匹配此正则表达式的文件（如 '.*/generated/.*'，可重复指定）
+    #[clap(long, value_name = "REGEX")]
+    exclude_regex: Vec<String>,
+
+    /// 最大遍历深度：0 只搜索目录下直接的文件，1 再多进入一层子目录，默认不限制
+    #[clap(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// 额外的忽略文件名（默认为配置中的 custom_ignore_file，即 .findignore），按 gitignore 语法解析，
+    /// 不受 respect_gitignore 影响；传空字符串时不使用
+    #[clap(long, value_name = "NAME")]
+    custom_ignore_file: Option<String>,
+
+    /// 跟随符号链接进入其指向的目录（形成循环的链接会被跳过并记录到日志）
+    #[clap(long)]
+    follow_links: bool,
+
+    /// 不跨越文件系统边界（不进入其他挂载点）
+    #[clap(long)]
+    one_file_system: bool,
+
+    /// 即使被 .gitignore 排除也要搜索的文件或目录（可重复指定）
+    #[clap(long, value_name = "PATH")]
+    unrestricted_path: Vec<PathBuf>,
+
+    /// 不输出警告信息
+    #[clap(long)]
+    no_messages: bool,
+
+    /// 列出被忽略规则（.gitignore / .ignore）排除的路径及具体规则，配置中关闭 respect_gitignore 时不报告
+    #[clap(long)]
+    show_excluded: bool,
+
+    /// 跟踪单个文件：搜索现有内容后持续匹配新追加的行（类似 tail -f | grep），按 Ctrl+C 退出
+    #[clap(long, conflicts_with_all = ["stdin_paths", "stdin_paths0", "files_from", "incremental"])]
+    follow_file: bool,
+
+    /// 只搜索 .json 文件中指定 JSONPath 下的值（如 '$.spec.image'，'$' 表示所有值），解析失败的文件记入错误日志
+    #[clap(long, value_name = "JSONPATH", conflicts_with = "follow_file")]
+    json_path: Option<String>,
+
+    /// 只匹配每个文件中指定行号范围内的行，如 100:200、100: 或 :200（从 1 开始，两端都包含，与输出的行号一致）
+    #[clap(long, value_name = "START:END", value_parser = LineRange::parse, conflicts_with_all = ["json_path", "follow_file"])]
+    lines: Option<LineRange>,
+
+    /// 最多输出 N 条结果，并在不同文件扩展名之间轮流抽取（会缓冲全部结果直到搜索结束）
+    #[clap(long, value_name = "N")]
+    diverse_sample: Option<usize>,
+
+    /// 以 JSON 输出：每行一个事件，依次为匹配结果、摘要（总会输出）
+    #[clap(long, conflicts_with_all = ["output_template", "follow_file", "file_header", "show_excluded"])]
+    json: bool,
+
+    /// 结果的输出格式: text（默认，带颜色的文本）、json（同 --json）或 ndjson（同 json，但每个匹配输出后立即刷新）
+    #[clap(long, value_name = "FORMAT", default_value = "text", conflicts_with = "json")]
+    format: presentation::OutputFormat,
+
+    /// 何时输出颜色: auto（默认，仅输出到终端时）、always 或 never。
+    /// 配置文件中 highlight_matches = false 时 auto 也不输出颜色
+    #[clap(long, value_name = "WHEN", default_value = "auto")]
+    color: presentation::ColorChoice,
+
+    /// JSON 模式下在摘要之后再输出结束标记 {"type":"done","matches":N}
+    #[clap(long)]
+    json_done: bool,
+
+    /// 每个匹配只输出一行 `路径:行号`（无颜色、无内容，同一行只输出一次），便于编辑器和 `less` 跳转
+    #[clap(long, conflicts_with_all = ["json", "output_template", "follow_file", "file_header", "show_excluded", "line_match_count"])]
+    locations: bool,
+
+    /// 只输出包含匹配的文件路径，每个文件一行（搜索结束后按路径排序输出）。
+    /// 每个文件找到第一个匹配后即停止读取该文件
+    #[clap(
+        short = 'l',
+        long,
+        conflicts_with_all = [
+            "json", "locations", "output_template", "follow_file", "file_header", "show_excluded",
+            "line_match_count", "position", "blame", "block_context", "replace", "diverse_sample",
+        ]
+    )]
+    files_with_matches: bool,
+
+    /// 每个文件只输出一行 `路径:匹配行数`（搜索结束后按路径排序输出），摘要仍统计全部匹配
+    #[clap(
+        short = 'c',
+        long,
+        conflicts_with_all = [
+            "json", "locations", "files_with_matches", "output_template", "follow_file", "file_header",
+            "line_match_count", "position", "blame", "block_context", "replace", "diverse_sample",
+        ]
+    )]
+    count: bool,
+
+    /// 每个文件最多输出 N 个匹配行，达到后不再读取该文件的其余部分（不是全局上限；压缩包按整个压缩包计）
+    #[clap(short = 'm', long, value_name = "N")]
+    max_count: Option<usize>,
+
+    /// 搜索摘要的格式: plain（默认）、table（对齐的表格）或 json（单个 JSON 对象）
+    #[clap(long, value_name = "FORMAT", default_value = "plain", conflicts_with_all = ["json", "locations"])]
+    summary_format: SummaryFormat,
+
+    /// 搜索结束后按顺序输出结果: path（路径和行号）或 count（匹配最多的文件在前）。
+    /// 需要在内存中缓冲全部结果，结果很多时占用内存与结果数成正比
+    #[clap(long, value_name = "ORDER")]
+    sort: Option<presentation::SortOrder>,
+
+    /// 跳过前 N 条匹配结果，从第 N+1 条开始输出（摘要仍统计全部匹配）。
+    /// 并行遍历的顺序不固定，用于分页时请配合 --sort 使结果顺序稳定
+    #[clap(long, value_name = "N")]
+    skip: Option<u64>,
+
+    /// 省略与已输出结果的行内容和匹配文本都相同的结果（只保留第一次出现的文件），用于过滤生成或复制的代码；
+    /// 只有行内容相同但匹配文本不同的结果仍会输出。省略的数量显示在摘要中
+    #[clap(long)]
+    dedup_results: bool,
+
+    /// 在摘要中按文件扩展名统计匹配数和匹配文件数，按匹配数从多到少排列
+    #[clap(long)]
+    stats: bool,
+
+    /// --stats 最多单独列出的扩展名数，其余汇总为“其他”
+    #[clap(long, value_name = "N", default_value_t = 10, requires = "stats")]
+    stats_top: usize,
+
+    /// 每秒最多输出 N 条结果，便于交互式阅读（只控制输出速度，与 CPU 限流无关；
+    /// 搜索很快时结果通道会被填满，搜索线程随之等待）
+    #[clap(long, value_name = "N")]
+    max_matches_per_second: Option<u32>,
+
+    /// 不读取也不创建配置文件，只使用内置默认值和命令行参数
+    #[clap(long)]
+    no_config: bool,
+
+    /// 任何文件无法读取或搜索时立即中止，输出出错的路径和原因并以非零状态退出（用于 CI 检查）
+    #[clap(long)]
+    fail_fast: bool,
+
+    /// 匹配总数超过 N 时以状态码 2 退出，并在标准错误输出
+    /// `policy violation: M matches exceed allowed N`（用于 CI 检查，只写 --max-allowed 时 N 为 0）
+    #[clap(long, value_name = "N", num_args = 0..=1, default_missing_value = "0")]
+    max_allowed: Option<u64>,
+
+    /// 错误数达到 N 时中止搜索，0 表示不限制（默认使用配置文件中的 max_errors）
+    #[clap(long, value_name = "N")]
+    max_errors: Option<usize>,
+
+    /// 单个文件的最长搜索时间，超时则放弃该文件 (例如 "500ms", "30s", "2m")
+    #[clap(long, value_parser = parse_duration)]
+    max_runtime_per_file: Option<std::time::Duration>,
+
+    /// 匹配行前后各显示 N 行上下文，仅本次运行覆盖配置中的 context_lines
+    #[clap(short = 'C', long, value_name = "N")]
+    context: Option<usize>,
+
+    /// 匹配行之前显示 N 行上下文，优先于 -C 和配置中的 context_lines
+    #[clap(short = 'B', long, value_name = "N")]
+    before_context: Option<usize>,
+
+    /// 匹配行之后显示 N 行上下文，优先于 -C 和配置中的 context_lines
+    #[clap(short = 'A', long, value_name = "N")]
+    after_context: Option<usize>,
+
+    /// 不显示上下文行，无论配置中的 context_lines 是多少
+    #[clap(long, conflicts_with_all = ["context", "before_context", "after_context"])]
+    no_context: bool,
+
+    /// 代码文件（C、Rust、Java、JavaScript 等花括号语言）中以匹配所在的 `{ ... }` 代码块作为上下文，
+    /// 代码块最多 N 行（只写 --block-context 时为 50），超过上限或找不到配对的花括号时使用普通上下文
+    #[clap(long, value_name = "N", num_args = 0..=1, default_missing_value = "50",
+        conflicts_with_all = ["sequence", "json_path"])]
+    block_context: Option<usize>,
+
+    /// 有上下文行时，两组结果之间的分隔符
+    #[clap(long, default_value = "--")]
+    group_separator: String,
+
+    /// 在每个文件的结果之前输出 `路径 (N 行, M 处匹配)` 头部（需要按文件缓冲结果）
+    #[clap(long)]
+    file_header: bool,
+
+    /// 只搜索在这段时间内修改过的文件（如 2h、3d），可与 --modified-before 组合为时间窗口
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    modified_within: Option<std::time::Duration>,
+
+    /// 只搜索在这段时间之前修改的文件（如 30d 表示 30 天前或更早修改的文件）
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    modified_before: Option<std::time::Duration>,
+
+    /// 增量扫描：只搜索上次扫描之后修改过的文件，并在完成后记录本次扫描时间
+    #[clap(long)]
+    incremental: bool,
+
+    /// 增量扫描状态文件路径（默认与配置文件同目录的 scan_state.toml）
+    #[clap(long)]
+    state_file: Option<PathBuf>,
+
+    /// 在每个匹配行之后显示该行的 git blame 作者和提交（每个文件 blame 一次；历史很长的仓库中明显变慢）。
+    /// 不在 git 仓库中的文件不显示，工作区中修改过的行显示为“未提交”
+    #[clap(long, conflicts_with_all = ["json", "locations", "output_template", "follow_file"])]
+    blame: bool,
+
+    /// 另外将全部匹配的位置（路径、行号、字节偏移、列）写入索引文件，按路径、行号、列排序，
+    /// 可以直接二分查找（格式见 README）。搜索结束后一次性原子地写入
+    #[clap(long, value_name = "PATH", conflicts_with = "follow_file")]
+    index: Option<PathBuf>,
+
+    /// 在结果头部显示该行的匹配次数，如 `path:12(3)`，不显示路径时为 `12(3)`
+    #[clap(long)]
+    line_match_count: bool,
+
+    /// 在结果头部显示第一个匹配的列（从 1 开始，按字节计算），如 `path:12:5`，不显示路径时为 `12:5`
+    #[clap(long, conflicts_with_all = ["json", "locations", "output_template"])]
+    column: bool,
+
+    /// 在结果头部显示匹配行在文件中的相对位置，如 `path:870 (87%)`，不显示路径时为 `870 (87%)`
+    /// （需要文件的总行数，流式读取时顺带统计；-m 或 --lines 提前停止匹配时会数完剩余的行）
+    #[clap(long, conflicts_with_all = ["json", "locations", "output_template"])]
+    position: bool,
+
+    /// 自定义每个结果的输出格式，可用占位符: {path} {line} {col} {match} {text} {before} {after}
+    #[clap(long)]
+    output_template: Option<String>,
+
+    /// 输出的换行符: lf（默认）、crlf，或 auto（输出到文件时使用平台默认换行符，输出到管道时为 lf）
+    #[clap(long, value_name = "STYLE", default_value = "lf")]
+    line_ending: presentation::LineEnding,
+
+    /// 不输出文件路径（搜索单个文件时的默认行为）
+    #[clap(short = 'h', long, conflicts_with = "with_filename")]
+    no_filename: bool,
+
+    /// 总是输出文件路径
+    #[clap(short = 'H', long)]
+    with_filename: bool,
+
+    /// 从标准输入逐行读取要搜索的文件路径（边读边搜索）
+    #[clap(long, conflicts_with = "stdin_paths0")]
+    stdin_paths: bool,
+
+    /// 同 --stdin-paths，但路径以 NUL 字符分隔（配合 find -print0 使用）
+    #[clap(long)]
+    stdin_paths0: bool,
+
+    /// 从文件逐行读取要搜索的文件路径（为 - 时从标准输入读取），不遍历目录；不存在的路径记入错误日志
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["stdin_paths", "stdin_paths0", "incremental"])]
+    files_from: Option<PathBuf>,
 }
 
-/// 解析文件大小字符串为字节数
-fn parse_size(size_str: &str) -> Result<u64> {
-    let size_str = size_str.trim().to_lowercase();
-    
-    let multiplier = if size_str.ends_with('k') {
-        1024
-    } else if size_str.ends_with('m') {
-        1024 * 1024
-    } else if size_str.ends_with('g') {
-        1024 * 1024 * 1024
+/// 解析时间长度字符串 (支持 ms/s/m/h/d 后缀，无后缀时按秒计算)
+fn parse_duration(duration_str: &str) -> Result<std::time::Duration> {
+    let duration_str = duration_str.trim().to_lowercase();
+
+    let (numeric_part, unit_secs) = if let Some(n) = duration_str.strip_suffix("ms") {
+        (n, 0.001)
+    } else if let Some(n) = duration_str.strip_suffix('s') {
+        (n, 1.0)
+    } else if let Some(n) = duration_str.strip_suffix('m') {
+        (n, 60.0)
+    } else if let Some(n) = duration_str.strip_suffix('h') {
+        (n, 3600.0)
+    } else if let Some(n) = duration_str.strip_suffix('d') {
+        (n, 86400.0)
     } else {
-        1
+        (duration_str.as_str(), 1.0)
     };
-    
-    let numeric_part = size_str
-        .trim_end_matches(|c: char| c.is_alphabetic())
+
+    let value = numeric_part
+        .trim()
         .parse::<f64>()
-        .context("无效的大小值")?;
-    
-    Ok((numeric_part * multiplier as f64) as u64)
+        .context("无效的时间长度")?;
+    if !value.is_finite() || value < 0.0 {
+        anyhow::bail!("无效的时间长度: {}", duration_str);
+    }
+
+    Ok(std::time::Duration::from_secs_f64(value * unit_secs))
+}
+
+/// 匹配数超过 --max-allowed 时的退出状态码（一般错误为 1）
+const POLICY_VIOLATION_EXIT_CODE: i32 = 2;
+
+/// 检查匹配总数是否超过允许的上限，超过时返回固定格式的提示，便于脚本解析
+fn match_policy_violation(total_matches: u64, max_allowed: Option<u64>) -> Option<String> {
+    let allowed = max_allowed?;
+    (total_matches > allowed).then(|| format!("policy violation: {} matches exceed allowed {}", total_matches, allowed))
+}
+
+/// 将 `--format json`/`--format ndjson` 统一为 `--json`，并检查与 JSON 输出冲突的参数
+///
+/// clap 只能对 `--json` 本身声明冲突，`--format` 的取值需要在解析后检查。
+fn apply_output_format(args: &mut Args) -> Result<()> {
+    let format_name = match args.format {
+        presentation::OutputFormat::Text => None,
+        presentation::OutputFormat::Json => Some("json"),
+        presentation::OutputFormat::Ndjson => Some("ndjson"),
+    };
+    if let Some(format_name) = format_name {
+        let conflicts = [
+            ("--output-template", args.output_template.is_some()),
+            ("--follow-file", args.follow_file),
+            ("--file-header", args.file_header),
+            ("--show-excluded", args.show_excluded),
+            ("--locations", args.locations),
+            ("--files-with-matches", args.files_with_matches),
+            ("--count", args.count),
+            ("--position", args.position),
+            ("--column", args.column),
+            ("--blame", args.blame),
+            ("--replace", args.replace.is_some()),
+            // 显式的 plain 与默认值无法区分，只检查其他格式
+            ("--summary-format", args.summary_format != SummaryFormat::Plain),
+        ];
+        if let Some((flag, _)) = conflicts.iter().find(|(_, present)| *present) {
+            anyhow::bail!("--format {} 不能与 {} 同时使用", format_name, flag);
+        }
+        args.json = true;
+    }
+    if args.json_done && !args.json {
+        anyhow::bail!("--json-done 需要 --json 或 --format json/ndjson");
+    }
+    Ok(())
+}
+
+/// 检查搜索路径存在、是目录或普通文件并且可以读取，否则返回说明原因的错误
+fn check_search_path(path: &std::path::Path) -> Result<()> {
+    use std::io::ErrorKind;
+
+    let describe = |err: std::io::Error| match err.kind() {
+        ErrorKind::NotFound => anyhow::anyhow!("搜索路径不存在: {}", path.display()),
+        ErrorKind::PermissionDenied => anyhow::anyhow!("没有权限访问搜索路径: {}", path.display()),
+        _ => anyhow::Error::new(err).context(format!("无法访问搜索路径: {}", path.display())),
+    };
+
+    let metadata = std::fs::metadata(path).map_err(describe)?;
+    if metadata.is_dir() {
+        std::fs::read_dir(path).map_err(describe)?;
+    } else if metadata.is_file() {
+        std::fs::File::open(path).map_err(describe)?;
+    } else {
+        anyhow::bail!("搜索路径不是目录或普通文件: {}", path.display());
+    }
+    Ok(())
+}
+
+/// 决定是否输出文件路径：显式参数优先，否则只有搜索单个明确指定的文件时才省略
+fn should_show_filename(with_filename: bool, no_filename: bool, single_file: bool) -> bool {
+    if with_filename {
+        true
+    } else if no_filename {
+        false
+    } else {
+        !single_file
+    }
+}
+
+/// 展开 `@文件` 形式的参数：从文件中读取附加参数，每行一个，忽略空行和 `#` 注释
+///
+/// 文件不存在时按普通参数处理，以免影响以 `@` 开头的搜索内容
+fn expand_args_files<I>(args: I) -> Result<Vec<OsString>>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut expanded = Vec::new();
+
+    for arg in args {
+        let args_file = arg
+            .to_str()
+            .and_then(|s| s.strip_prefix('@'))
+            .map(PathBuf::from)
+            .filter(|path| path.is_file());
+
+        match args_file {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("无法读取参数文件: {}", path.display()))?;
+
+                expanded.extend(
+                    content.lines()
+                        .map(|line| line.trim())
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(OsString::from)
+                );
+            }
+            None => expanded.push(arg),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// 以 root 身份搜索系统目录时输出一次性警告（不阻止搜索）
+#[cfg(unix)]
+fn warn_if_root_over_system_path(search_path: &std::path::Path, one_file_system: bool) {
+    // SAFETY: geteuid 没有任何前置条件，且总是成功
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if let Some(warning) = root_over_system_path_warning(search_path, is_root, one_file_system) {
+        eprintln!("{}", warning);
+    }
+}
+
+/// 以 root 身份、未限制在同一文件系统内搜索 `/`、`/mnt`、`/media` 时的警告，其余情况为 None
+#[cfg(unix)]
+fn root_over_system_path_warning(search_path: &std::path::Path, is_root: bool, one_file_system: bool) -> Option<String> {
+    if !is_root || one_file_system {
+        return None;
+    }
+
+    let resolved = search_path
+        .canonicalize()
+        .unwrap_or_else(|_| search_path.to_path_buf());
+    let system_paths = ["/", "/mnt", "/media"];
+    system_paths.iter().any(|p| resolved == std::path::Path::new(p)).then(|| format!(
+        "警告: 正在以 root 身份搜索 {}，可能会进入 /proc、/sys 以及远程挂载点，建议使用 --one-file-system",
+        resolved.display()
+    ))
+}
+
+/// 跟踪模式的轮询间隔
+const FOLLOW_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// 跟踪单个文件，持续输出新追加内容中的匹配，直到进程被终止
+fn follow_file(
+    path: &std::path::Path,
+    follower: &mut domain::follow::FileFollower,
+    output_template: Option<&OutputTemplate>,
+    display: &application::config::DisplayConfig,
+    color: bool,
+    no_messages: bool,
+    line_ending: presentation::LineEnding,
+) -> Result<()> {
+    if !path.is_file() {
+        anyhow::bail!("--follow-file 需要指定单个文件: {}", path.display());
+    }
+
+    let mut stdout = presentation::LineEndingWriter::new(std::io::stdout(), line_ending);
+    loop {
+        let poll = follower.poll()?;
+        if poll.rotated && !no_messages {
+            eprintln!("文件已被截断或替换，从头重新读取: {}", path.display());
+        }
+
+        for result in &poll.results {
+            match output_template {
+                Some(template) => writeln!(stdout, "{}", template.render(result))?,
+                None => presentation::write_search_result(
+                    &mut stdout,
+                    result,
+                    display.max_line_length,
+                    color,
+                )?,
+            }
+        }
+        stdout.flush()?;
+
+        std::thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+/// 输出被忽略规则排除的路径
+fn print_excluded_entries(w: &mut impl Write, entries: &[domain::ignore_report::ExcludedEntry]) -> Result<()> {
+    writeln!(w, "\n被忽略规则排除的路径: {} 个", entries.len())?;
+    writeln!(w, "----------------------------")?;
+
+    for entry in entries {
+        let kind = if entry.is_dir { "目录" } else { "文件" };
+        match &entry.rule {
+            Some(rule) => writeln!(w, "  [{}] {}\n      规则: {}", kind, entry.path.display(), rule.format())?,
+            None => writeln!(w, "  [{}] {}\n      规则: 无法确定（可能来自全局 gitignore）", kind, entry.path.display())?,
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse_from(expand_args_files(std::env::args_os())?);
+    apply_output_format(&mut args)?;
+
+    // -e、--sequence 和 --int 自带模式，唯一的位置参数是搜索路径
+    if !args.patterns.is_empty() || args.sequence.is_some() || args.int.is_some() {
+        if let Some(path) = args.pattern.take() {
+            if args.path.is_some() {
+                anyhow::bail!("使用 -e、--sequence 或 --int 时不需要搜索内容，只需指定搜索路径");
+            }
+            args.path = Some(PathBuf::from(path));
+        }
+    }
+    if !args.patterns.is_empty() {
+        args.pattern = Some(args.patterns.join(" | "));
+    }
+    if let Some(sequence) = &args.sequence {
+        args.pattern = Some(format!("{} → {}（{} 行内）", sequence[0], sequence[1], args.within));
+    }
+    if let Some(int) = &args.int {
+        args.pattern = Some(format!("整数 {}", int));
+    }
+    // 搜索路径为 - 时搜索标准输入的内容，不遍历目录
+    let search_stdin = args.path.as_deref() == Some(std::path::Path::new("-"));
+    // 从标准输入或 --files-from 列表读取要搜索的文件，不遍历目录
+    let path_list_mode = args.stdin_paths || args.stdin_paths0 || args.files_from.is_some();
+    if search_stdin {
+        // 标准输入只按行流式搜索，这些选项依赖文件或整体读取，不会生效
+        let unsupported = [
+            ("--stdin-paths、--stdin-paths0 或 --files-from", path_list_mode),
+            ("--incremental", args.incremental),
+            ("--follow-file", args.follow_file),
+            ("--lines", args.lines.is_some()),
+            ("--json-path", args.json_path.is_some()),
+            ("--sequence", args.sequence.is_some()),
+            ("--block-context", args.block_context.is_some()),
+            ("--encoding", args.encoding != domain::search::Decoding::Auto),
+        ];
+        if let Some((option, _)) = unsupported.iter().find(|(_, used)| *used) {
+            anyhow::bail!("搜索标准输入的内容（路径为 -）时不能使用 {}", option);
+        }
+    }
+    // 不指定搜索路径的 --replace 从标准输入读取
+    let replace_stdin = args.replace.is_some() && (args.path.is_none() || search_stdin);
+    if replace_stdin && args.in_place {
+        anyhow::bail!("--in-place 需要指定要修改的文件或目录");
+    }
+    let pattern_inputs = match &args.sequence {
+        Some(sequence) => vec![sequence[0].clone()],
+        None if !args.patterns.is_empty() => args.patterns.clone(),
+        None => vec![args.pattern.clone().unwrap_or_default()],
+    };
+    let pattern_input = pattern_inputs.join(" | ");
+    let pattern_display = args.pattern.clone().unwrap_or_default();
 
     // 加载配置文件
+    // --no-config 时只使用内置默认值，不读取也不创建配置文件；FE_ 开头的环境变量总是覆盖配置
     let config_path = Config::default_config_path()?;
-    let config = Config::load_or_create(&config_path)?;
+    let mut config = if args.no_config {
+        Config::default()
+    } else {
+        Config::load_or_create(&config_path)?
+    };
+    config.apply_env_overrides()?;
     config.validate()?;
 
+    // --color always/never 优先于配置文件，auto 时配置文件可以关闭颜色
+    let color = match args.color {
+        presentation::ColorChoice::Auto if !config.display.highlight_matches => false,
+        choice => choice.for_stdout(),
+    };
+
     // 确定搜索路径（命令行参数优先于配置文件）
     let search_path = args.path.unwrap_or_else(|| {
         PathBuf::from(&config.search.default_search_path)
     });
 
+    // 从标准输入读取内容或路径时不使用搜索路径
+    if !replace_stdin && !search_stdin && !path_list_mode {
+        check_search_path(&search_path)?;
+    }
+
+    #[cfg(unix)]
+    if !args.no_messages {
+        warn_if_root_over_system_path(&search_path, args.one_file_system);
+    }
+
     // 初始化日志记录器
-    let logger = Arc::new(Logger::new(args.log)?);
+    let write_bom = !args.log_no_bom;
+    let logger = Arc::new(Logger::with_bom(args.log, write_bom)?);
 
     // 初始化错误日志记录器
-    let error_logger = Arc::new(ErrorLogger::new(true)?); // 总是启用错误日志
+    let error_logger = Arc::new(ErrorLogger::with_bom(true, write_bom)?); // 总是启用错误日志
 
     // 初始化CPU监控器
-    let cpu_monitor = Arc::new(CpuMonitor::new(&config, Arc::clone(&logger)));
+    let cpu_monitor = Arc::new(CpuMonitor::new(&config, Arc::clone(&logger) as Arc<dyn LoggerTrait>));
     cpu_monitor.start()?;
 
     // 解析搜索模式
-    let pattern = SearchPattern::from_input(&args.pattern, args.regex, args.hex)?;
-    let matcher = pattern.get_matcher()?;
+    let pattern = match &args.int {
+        Some(int) => domain::numeric::parse_int_pattern(int)?,
+        None => SearchPattern::from_inputs(&pattern_inputs, args.regex, args.hex)?,
+    };
+    let matcher_options = MatcherOptions {
+        ascii_word_boundaries: args.ascii_word_boundaries,
+        case_mode: if args.ignore_case {
+            CaseMode::Insensitive
+        } else if args.smart_case {
+            CaseMode::Smart
+        } else {
+            config.search.case_mode
+        },
+        whole_word: args.word,
+        invert: args.invert_match,
+    };
+    // 排除模式按原样匹配，不受整词选项影响
+    let exclude_options = MatcherOptions { whole_word: false, ..matcher_options.clone() };
+    let mut matcher = pattern.get_line_matcher_with(&matcher_options)?;
+    let exclude_pattern = args.exclude_pattern.as_deref()
+        .map(|exclude| SearchPattern::from_input(exclude, true, false).context("无效的 --exclude-pattern"))
+        .transpose()?;
+    if let Some(exclude) = &exclude_pattern {
+        matcher = matcher.with_exclude(exclude.get_matcher_with(&exclude_options)?);
+    }
+    let sequence_second = args.sequence.as_ref()
+        .map(|patterns| SearchPattern::from_input(&patterns[1], args.regex, args.hex))
+        .transpose()?;
+    let sequence = match &sequence_second {
+        Some(second_pattern) => {
+            let mut second = second_pattern.get_line_matcher_with(&matcher_options)?;
+            if let Some(exclude) = &exclude_pattern {
+                second = second.with_exclude(exclude.get_matcher_with(&exclude_options)?);
+            }
+            Some(domain::sequence::SequenceMatcher {
+                first: matcher.clone(),
+                second,
+                within: args.within,
+            })
+        }
+        None => None,
+    };
+    if args.debug_matcher {
+        eprintln!(
+            "[匹配器] 正则: {} (Unicode: {}, 忽略大小写: {}, 字面量快速路径: {})",
+            pattern.regex_pattern_with(&matcher_options),
+            if matcher_options.ascii_word_boundaries { "关" } else { "开" },
+            if pattern.is_case_insensitive(&matcher_options) { "是" } else { "否" },
+            if matcher.is_literal() { "是" } else { "否" },
+        );
+        // 多个 -e 模式组合为一个正则，逐个列出以便核对每个模式的转义
+        if let SearchPattern::Any(patterns) = &pattern {
+            for (index, each) in patterns.iter().enumerate() {
+                eprintln!("[匹配器] 模式 {}: {}", index + 1, each.regex_pattern_with(&matcher_options));
+            }
+        }
+        if let Some(second) = &sequence_second {
+            eprintln!("[匹配器] 序列第二行: {}", second.regex_pattern_with(&matcher_options));
+        }
+        if let Some(exclude) = &exclude_pattern {
+            eprintln!("[匹配器] 排除: {}", exclude.regex_pattern());
+        }
+    }
+
+    // 能匹配空字符串的模式会匹配每一行，通常不是用户的本意，但仍按原样搜索
+    if !args.no_messages && pattern.matches_empty_string(&matcher_options)? {
+        eprintln!(
+            "警告: 搜索模式 '{}' 可以匹配空字符串，每一行都会被匹配；如非有意，请使用更具体的模式（如用 + 代替 *）",
+            pattern_input
+        );
+    }
+
+    // 解析输出模板（未知占位符在搜索开始前报错）
+    let output_template = args.output_template.as_deref()
+        .map(OutputTemplate::parse)
+        .transpose()?;
+
+    let line_ending = args.line_ending;
+
+    let json_path = args.json_path.as_deref()
+        .map(domain::json_search::JsonPath::parse)
+        .transpose()?;
+
+    let replacement = args.replace.as_deref()
+        .map(|template| domain::replace::Replacement::new(&pattern, &matcher_options, template))
+        .transpose()?;
+    if let (Some(replacement), true) = (&replacement, replace_stdin) {
+        replacement.replace_stream(std::io::stdin().lock(), std::io::stdout().lock())?;
+        return Ok(());
+    }
+    let replacement_in_place = replacement.clone().filter(|_| args.in_place);
+
+    if args.follow_file {
+        let mut follower = domain::follow::FileFollower::new(
+            &search_path,
+            matcher.clone(),
+            config.search.max_matches_per_line,
+        );
+        return follow_file(
+            &search_path,
+            &mut follower,
+            output_template.as_ref(),
+            &config.display,
+            color,
+            args.no_messages,
+            line_ending,
+        );
+    }
     
     // 解析排除目录
     let mut excluded_dirs = config.exclude.default_dirs.clone();
@@ -136,73 +905,310 @@ fn main() -> Result<()> {
         }
     }
 
+    // 加载增量扫描状态，记录本次扫描开始时间
+    let scan_started_at = std::time::SystemTime::now();
+    let state_path = args.state_file.clone()
+        .unwrap_or_else(|| ScanState::default_state_path(&config_path));
+    let scan_state = if args.incremental {
+        Some(ScanState::load(&state_path)?)
+    } else {
+        None
+    };
+    let modified_after = scan_state.as_ref()
+        .and_then(|state| state.last_scan_time(&search_path));
+
+    // 按修改时间过滤的范围，--modified-within 与增量扫描同时使用时取较晚的时间
+    let within_cutoff = args.modified_within.and_then(|within| scan_started_at.checked_sub(within));
+    let before_cutoff = args.modified_before
+        .map(|before| scan_started_at.checked_sub(before).unwrap_or(std::time::UNIX_EPOCH));
+
     // 创建文件过滤器
     let filter = FileFilter::new(
         args.min_size.as_deref().map(parse_size).transpose()?,
         args.max_size.as_deref().map(parse_size).transpose()?,
         excluded_dirs,
         excluded_paths,
-    );
+    )
+    .with_modified_after(modified_after.max(within_cutoff))
+    .with_modified_before(before_cutoff)
+    .with_globs(args.include.clone(), args.exclude.clone())?
+    .with_exclude_regexes(&args.exclude_regex)?
+    .with_extensions(&args.ext);
     
+    let excluded_filter = filter.clone();
+
+    // 上下文行数：--no-context 强制为 0，--context 覆盖配置，-A/-B 再分别覆盖前后的行数
+    // -l 和 -c 不输出匹配行，不需要上下文
+    let context = if args.no_context || args.files_with_matches || args.count || args.replace.is_some() {
+        ContextLines::default()
+    } else {
+        let symmetric = args.context.unwrap_or(config.search.context_lines);
+        ContextLines {
+            before: args.before_context.unwrap_or(symmetric),
+            after: args.after_context.unwrap_or(symmetric),
+        }
+    };
+
+    if args.threads == Some(0) {
+        anyhow::bail!("--threads 至少为 1");
+    }
+    let walk_options = WalkOptions {
+        parallel: !args.no_parallel,
+        threads: args.threads,
+        respect_gitignore: config.search.respect_gitignore,
+        one_file_system: args.one_file_system,
+        unrestricted_paths: args.unrestricted_path.clone(),
+        max_depth: args.max_depth,
+        follow_links: args.follow_links,
+        custom_ignore_filename: args.custom_ignore_file.clone()
+            .or_else(|| config.search.custom_ignore_file.clone())
+            .filter(|name| !name.is_empty()),
+    };
+
     // 创建搜索摘要
-    let summary = Arc::new(Mutex::new(SearchSummary::new()));
+    let mut summary = SearchSummary::new();
+    if args.stats {
+        summary.extension_stats = Some(presentation::ExtensionStats::new(args.stats_top));
+    }
+    let summary = Arc::new(Mutex::new(summary));
     
     // 存储已匹配文件路径
     let matched_files = Arc::new(Mutex::new(HashSet::new()));
     
     // 创建结果通道
-    let (tx, rx) = bounded::<SearchResult>(100);
+    // 同一文件的结果作为一批发送，避免与其他文件的结果交错
+    let (tx, rx) = bounded::<Vec<SearchResult>>(100);
     
     // 创建处理线程
     let summary_clone = Arc::clone(&summary);
     let matched_files_clone = Arc::clone(&matched_files);
     let logger_clone = Arc::clone(&logger);
+    // 只有输出上下文行时才需要分隔各组结果
+    let group_separator = (!context.is_empty() || args.block_context.is_some()).then(|| args.group_separator.clone());
+    let file_header = args.file_header;
+    let line_match_count = args.line_match_count;
+    let show_position = args.position;
+    let show_filename = should_show_filename(
+        args.with_filename,
+        args.no_filename,
+        search_stdin || (!path_list_mode && search_path.is_file()),
+    );
     
+    let diverse_sample = args.diverse_sample;
+    let sort_order = args.sort;
+    let dedup_results = args.dedup_results;
+    let json_output = args.json;
+    let locations = args.locations;
+    let files_with_matches = args.files_with_matches;
+    let max_matches_per_second = args.max_matches_per_second;
+    let mut skip_remaining = args.skip.unwrap_or(0);
+    let index_path = args.index.clone();
+    let mut match_index = index_path.is_some().then(presentation::MatchIndex::new);
+
+    // 按输出格式选择结果的输出目标
+    let stdout_writer = || presentation::LineEndingWriter::new(std::io::stdout(), line_ending);
+    let mut sink: Box<dyn ResultSink + Send> = if json_output {
+        match args.format {
+            presentation::OutputFormat::Ndjson => Box::new(presentation::JsonSink::ndjson(stdout_writer())),
+            _ => Box::new(presentation::JsonSink::json(stdout_writer())),
+        }
+    } else if locations {
+        Box::new(presentation::LocationSink::new(stdout_writer()))
+    } else if files_with_matches {
+        Box::new(presentation::FileListSink::new(stdout_writer()))
+    } else if args.count {
+        Box::new(presentation::CountSink::new(stdout_writer()))
+    } else if let Some(template) = output_template {
+        Box::new(presentation::TemplateSink::new(stdout_writer(), template))
+    } else if let Some(replacement) = replacement {
+        let printer = GroupedPrinter::new(None)
+            .with_color(color)
+            .with_filename(show_filename);
+        Box::new(presentation::ReplaceSink::new(stdout_writer(), printer, replacement))
+    } else {
+        let printer = GroupedPrinter::new(group_separator)
+            .with_max_line_length(config.display.max_line_length)
+            .with_color(color)
+            .with_line_match_count(line_match_count)
+            .with_column(args.column)
+            .with_position(show_position)
+            .with_filename(show_filename);
+        let annotator = args.blame.then(|| {
+            let mut blame_cache = infrastructure::BlameCache::new();
+            Box::new(move |result: &SearchResult| {
+                blame_cache.line(&result.path, result.line_number).map(|blame| blame.format())
+            }) as presentation::Annotator
+        });
+        Box::new(
+            presentation::TextSink::new(stdout_writer(), printer)
+                .with_file_header(file_header)
+                .with_annotator(annotator),
+        )
+    };
+
     let handle = std::thread::spawn(move || -> Result<()> {
+        let mut pacer = max_matches_per_second.and_then(presentation::OutputPacer::new);
+
+        // 打印同一文件的一批结果
+        let mut print_file_results = |file_results: &[SearchResult]| -> Result<()> {
+            // --skip 跳过的结果不输出
+            let skipped = skip_remaining.min(file_results.len() as u64);
+            skip_remaining -= skipped;
+            let file_results = &file_results[skipped as usize..];
+            if file_results.is_empty() {
+                return Ok(());
+            }
+
+            sink.begin_file(file_results)?;
+            for result in file_results {
+                // 不输出的结果（如重复位置）不占用输出速率
+                if sink.skips(result) {
+                    continue;
+                }
+                if let Some(pacer) = &mut pacer {
+                    pacer.wait();
+                }
+                sink.write(result)?;
+            }
+            Ok(())
+        };
+
+        // 多样化抽样和排序需要先缓冲全部结果
+        let buffer_results = diverse_sample.is_some() || sort_order.is_some();
+        let mut buffered = Vec::new();
+
+        // 已输出结果的 (行内容, 匹配文本)，用于 --dedup-results
+        let mut seen_results: HashSet<(String, String)> = HashSet::new();
+
         // 从通道接收并处理结果
-        while let Ok(result) = rx.recv() {
-            // 打印结果
-            print_search_result(&result)?;
-            
-            // 更新统计信息
-            let mut summary = summary_clone.lock().unwrap();
-            let mut matched_paths = matched_files_clone.lock().unwrap();
-            
-            summary.total_matches += 1;
-            
-            // 记录匹配到的文件
-            if matched_paths.insert(result.path.clone()) {
-                summary.matched_files += 1;
-                
-                // 记录到日志
-                if logger_clone.is_enabled() {
-                    logger_clone.log_message(&format!("找到匹配: {}", result.path))?;
+        for mut file_results in rx.iter() {
+            if dedup_results {
+                let before = file_results.len();
+                file_results.retain(|result| seen_results.insert((result.line.clone(), result.matched_text.clone())));
+                let suppressed = (before - file_results.len()) as u64;
+                summary_clone.lock().unwrap().duplicates_suppressed += suppressed;
+                if file_results.is_empty() {
+                    continue;
+                }
+            }
+
+            if !buffer_results {
+                print_file_results(&file_results)?;
+            }
+
+            for result in &file_results {
+                // 更新统计信息
+                let mut summary = summary_clone.lock().unwrap();
+                let mut matched_paths = matched_files_clone.lock().unwrap();
+
+                summary.total_matches += 1;
+                if let Some(index) = &mut match_index {
+                    index.record(result);
+                }
+                if result.matches_truncated {
+                    summary.truncated_lines += 1;
+                }
+
+                // 记录匹配到的文件
+                let new_file = matched_paths.insert(result.path.clone());
+                if let Some(stats) = &mut summary.extension_stats {
+                    stats.record(&result.path, new_file);
+                }
+                if new_file {
+                    summary.matched_files += 1;
+
+                    // 记录到日志
+                    if logger_clone.is_enabled() {
+                        logger_clone.log_message(&format!("找到匹配: {}", result.path))?;
+                    }
                 }
             }
+
+            if buffer_results {
+                buffered.extend(file_results);
+            }
         }
-        
+
+        if buffer_results {
+            let total = buffered.len();
+            let mut output = match diverse_sample {
+                Some(limit) => presentation::diverse_sample(buffered, limit),
+                None => buffered,
+            };
+            if let Some(order) = sort_order {
+                output = presentation::sort_results(output, order);
+            }
+
+            for file_results in output.chunk_by(|a, b| a.path == b.path) {
+                print_file_results(file_results)?;
+            }
+            if let Some(limit) = diverse_sample {
+                if total > limit && !json_output && !locations && !files_with_matches {
+                    writeln!(
+                        presentation::LineEndingWriter::new(std::io::stdout().lock(), line_ending),
+                        "\n多样化抽样: 显示 {} 条，共 {} 条匹配",
+                        output.len(),
+                        total
+                    )?;
+                }
+            }
+        }
+
+        sink.finish()?;
+        if let (Some(index), Some(path)) = (match_index, &index_path) {
+            index.write_atomic(path)?;
+        }
+
         Ok(())
     });
     
-    // 开始搜索
-    println!("在 {} 中搜索: {}", search_path.display(), args.pattern);
-    if let Some(min) = &args.min_size {
-        println!("最小文件大小: {}", min);
-    }
-    if let Some(max) = &args.max_size {
-        println!("最大文件大小: {}", max);
-    }
-    println!("使用正则表达式: {}", args.regex);
-    println!("使用十六进制搜索: {}", args.hex);
-    println!("并行搜索: {}", !args.no_parallel);
-    println!("启用日志记录: {}", args.log);
-    println!("遵循 .gitignore 规则: {}", config.search.respect_gitignore);
-    println!("配置文件: {}", config_path.display());
-    println!();
+    // 开始搜索（JSON、--locations 和 -l 模式下标准输出只包含结果）
+    if !args.json && !args.locations && !args.files_with_matches {
+        let mut out = presentation::LineEndingWriter::new(std::io::stdout().lock(), line_ending);
+        if let Some(list) = args.files_from.as_deref().filter(|list| *list != std::path::Path::new("-")) {
+            writeln!(out, "在 {} 列出的文件中搜索: {}", list.display(), pattern_display)?;
+        } else if path_list_mode {
+            writeln!(out, "在标准输入提供的文件中搜索: {}", pattern_display)?;
+        } else if search_stdin {
+            writeln!(out, "在标准输入中搜索: {}", pattern_display)?;
+        } else {
+            writeln!(out, "在 {} 中搜索: {}", search_path.display(), pattern_display)?;
+        }
+        if let Some(min) = &args.min_size {
+            writeln!(out, "最小文件大小: {}", min)?;
+        }
+        if let Some(max) = &args.max_size {
+            writeln!(out, "最大文件大小: {}", max)?;
+        }
+        writeln!(out, "使用正则表达式: {}", args.regex)?;
+        writeln!(out, "使用十六进制搜索: {}", args.hex)?;
+        writeln!(out, "并行搜索: {}", !args.no_parallel)?;
+        if let Some(threads) = args.threads {
+            writeln!(out, "搜索线程数: {}", threads)?;
+        }
+        writeln!(out, "启用日志记录: {}", args.log)?;
+        writeln!(out, "遵循 .gitignore 规则: {}", config.search.respect_gitignore)?;
+        if args.incremental {
+            match modified_after {
+                Some(time) => writeln!(
+                    out,
+                    "增量扫描: 只搜索 {} 之后修改的文件",
+                    chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M:%S")
+                )?,
+                None => writeln!(out, "增量扫描: 未找到上次扫描记录，搜索全部文件")?,
+            }
+        }
+        if args.no_config {
+            writeln!(out, "配置文件: 未使用 (--no-config)")?;
+        } else {
+            writeln!(out, "配置文件: {}", config_path.display())?;
+        }
+        writeln!(out)?;
+    }
 
     // 记录搜索参数到日志
     if logger.is_enabled() {
-        logger.log_message(&format!("搜索模式: {}", args.pattern))?;
+        logger.log_message(&format!("搜索模式: {}", pattern_display))?;
         logger.log_message(&format!("目标目录: {}", search_path.display()))?;
         logger.log_message(&format!("使用正则表达式: {}", args.regex))?;
         logger.log_message(&format!("使用十六进制搜索: {}", args.hex))?;
@@ -212,7 +1218,7 @@ fn main() -> Result<()> {
         if let Some(max) = &args.max_size {
             logger.log_message(&format!("最大文件大小: {}", max))?;
         }
-        logger.log_message(&format!("并行搜索: {}", !args.no_parallel))?;
+        logger.log_message(&format!("并行搜索: {} ({} 个线程)", !args.no_parallel, walk_options.thread_count()))?;
         logger.log_message(&format!("遵循 .gitignore 规则: {}", config.search.respect_gitignore))?;
     }
     
@@ -220,47 +1226,306 @@ fn main() -> Result<()> {
     let tx_clone = tx.clone();
     let logger_clone = Arc::clone(&logger);
     let error_logger_clone = Arc::clone(&error_logger);
-    let matcher_clone = matcher.clone();
     let cpu_monitor_clone = Arc::clone(&cpu_monitor);
-    let context_lines = config.search.context_lines;
+    let max_matches_per_line = config.search.max_matches_per_line;
 
-    let start_time = std::time::Instant::now();
-    let (total_files, _) = domain::file_walker::scan_directory(
-        &search_path,
-        filter,
-        !args.no_parallel,
-        config.search.respect_gitignore,
-        logger_clone,
-        move |entry| {
-            // 应用CPU性能控制
-            cpu_monitor_clone.apply_throttle();
-
-            // 在文件中搜索，捕获错误
-            match domain::search::search_in_file(entry.path(), &matcher_clone, context_lines) {
-                Ok(results) => {
-                    // 发送结果
-                    for result in results {
-                        if tx_clone.send(result).is_err() {
-                            break;
-                        }
+    let line_range = args.lines.unwrap_or_default();
+    let line_count_filter = LineCountFilter {
+        min_lines: args.min_lines,
+        max_lines: args.max_lines,
+    };
+    if let (Some(min), Some(max)) = (args.min_lines, args.max_lines) {
+        if min > max {
+            anyhow::bail!("--min-lines ({}) 不能大于 --max-lines ({})", min, max);
+        }
+    }
+
+    let search_archives = args.search_archives;
+    let max_archive_member_size = match &args.max_archive_member_size {
+        Some(size) => parse_size(size)?,
+        None => domain::archive::DEFAULT_MAX_MEMBER_SIZE,
+    };
+    let no_messages = args.no_messages;
+    let block_context = args.block_context;
+    let decoding = if args.utf8_only { domain::search::Decoding::Strict } else { args.encoding };
+
+    // -l 只需要每个文件的第一个匹配
+    let max_count = if args.files_with_matches { Some(1) } else { args.max_count };
+    // 显示总行数时，提前停止匹配后仍需数完文件的行数
+    let count_all_lines = args.position || args.file_header;
+
+    // 单个文件的搜索：指定 --name/--path 时只匹配路径，行数不在 --min-lines/--max-lines 范围内的文件不搜索，
+    // 指定 --json-path 时只搜索 .json 文件中选中的值，指定 --sequence 时查找配对的两行
+    let path_target = if args.match_name {
+        Some(domain::path_match::PathTarget::Name)
+    } else if args.match_path {
+        Some(domain::path_match::PathTarget::Path)
+    } else {
+        None
+    };
+
+    let stdin_matcher = search_stdin.then(|| matcher.clone());
+    let search_one = Arc::new(move |path: &std::path::Path| {
+        // --name / --path 只匹配路径，不读取文件内容
+        if let Some(target) = path_target {
+            let result = domain::path_match::match_path(path, &matcher, target, max_matches_per_line);
+            return Ok(domain::search::FileSearch { results: result.into_iter().collect(), lines_scanned: 0 });
+        }
+        match (&json_path, &sequence) {
+            _ if line_count_filter.is_active() && !line_count_filter.matches_file(path)? => {
+                Ok(domain::search::FileSearch::default())
+            }
+            (_, Some(sequence)) => {
+                domain::sequence::search_sequence_file(path, sequence, decoding, context, max_matches_per_line, max_count)
+            }
+            (Some(json_path), None) => {
+                let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+                if !is_json {
+                    return Ok(domain::search::FileSearch::default());
+                }
+                domain::json_search::search_json_file(path, &matcher, json_path, context, max_matches_per_line, max_count)
+            }
+            (None, None) if search_archives && domain::archive::is_tar_gz(path) => {
+                let archive = domain::archive::search_tar_gz(
+                    path,
+                    &matcher,
+                    &line_range,
+                    context,
+                    max_matches_per_line,
+                    max_archive_member_size,
+                    max_count,
+                )?;
+                if !no_messages {
+                    for member in &archive.skipped_members {
+                        eprintln!("跳过压缩包成员（超过大小上限或不是文本）: {}", member);
                     }
                 }
-                Err(err) => {
-                    // 记录搜索错误到错误日志
-                    let _ = error_logger_clone.log_error(
-                        ErrorType::FileRead,
-                        Some(&entry.path().to_string_lossy()),
-                        "文件搜索失败",
-                        Some(&err.to_string()),
-                    );
-
-                    // 不再向控制台输出错误，只记录到错误日志
+                Ok(archive.search)
+            }
+            (None, None) => match block_context {
+                Some(max_block_lines) => domain::block_context::search_file_with_block_context(
+                    path,
+                    &matcher,
+                    &line_range,
+                    decoding,
+                    context,
+                    max_block_lines,
+                    max_matches_per_line,
+                    max_count,
+                ),
+                None => domain::search::search_file_in_range(
+                    path,
+                    &matcher,
+                    &line_range,
+                    decoding,
+                    context,
+                    max_matches_per_line,
+                    max_count,
+                    count_all_lines,
+                ),
+            },
+        }
+    });
+    let max_runtime_per_file = args.max_runtime_per_file;
+    // 扫描的总行数，各工作线程直接累加，无需加锁
+    let lines_scanned = Arc::new(AtomicU64::new(0));
+    let lines_scanned_clone = Arc::clone(&lines_scanned);
+    let max_errors = Some(args.max_errors.unwrap_or(config.search.max_errors)).filter(|&max| max > 0);
+    // --fail-fast 时记录第一个出错的文件和原因
+    let fail_fast = args.fail_fast;
+    let first_failure: Arc<Mutex<Option<(PathBuf, String)>>> = Arc::new(Mutex::new(None));
+    let first_failure_clone = Arc::clone(&first_failure);
+    let utf8_only = args.utf8_only;
+    let non_utf8_skipped = Arc::new(AtomicU64::new(0));
+    let non_utf8_skipped_clone = Arc::clone(&non_utf8_skipped);
+    let skip_non_utf8 = move |path: &std::path::Path| {
+        non_utf8_skipped_clone.fetch_add(1, Ordering::Relaxed);
+        if !no_messages {
+            eprintln!("跳过非 UTF-8 文件: {}", path.display());
+        }
+    };
+
+    let name_filter = args.name_filter.as_deref()
+        .map(|pattern| regex::Regex::new(pattern).context("无效的 --name-filter 正则表达式"))
+        .transpose()?;
+
+    // 限制同时读取的大文件数
+    let max_concurrent_large_files = args.max_concurrent_large_files
+        .unwrap_or(config.performance.max_concurrent_large_files);
+    let large_file_threshold = match &args.large_file_threshold {
+        Some(size) => parse_size(size)?,
+        None => config.performance.large_file_threshold_mb * 1024 * 1024,
+    };
+    let large_file_limiter = (max_concurrent_large_files > 0)
+        .then(|| Arc::new(infrastructure::LargeFileLimiter::new(large_file_threshold, max_concurrent_large_files)));
+    let large_file_limiter_clone = large_file_limiter.clone();
+
+    let search_file = move |path: &std::path::Path| {
+        // 文件名不匹配 --name-filter 的文件不搜索内容
+        if let Some(name_filter) = &name_filter {
+            let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            if !name_filter.is_match(&name) {
+                return Ok(());
+            }
+        }
+
+        // --utf8-only 时带 BOM 的 UTF-16 文件不解码，直接跳过
+        if utf8_only && domain::search::has_utf16_bom(path).unwrap_or(false) {
+            skip_non_utf8(path);
+            return Ok(());
+        }
+
+        // 应用CPU性能控制
+        cpu_monitor_clone.apply_throttle();
+
+        // 大文件需要先取得读取许可，搜索结束后归还
+        let large_file_permit = large_file_limiter_clone.as_ref().and_then(|limiter| {
+            let size = std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+            limiter.acquire_owned(size)
+        });
+
+        // 在文件中搜索，捕获错误
+        let search_result = match max_runtime_per_file {
+            Some(timeout) => {
+                let path = path.to_path_buf();
+                let search_one = Arc::clone(&search_one);
+                // 许可随搜索线程移动：超时后该线程仍在读取文件，直到它真正结束才归还
+                domain::search::run_with_timeout(timeout, move || {
+                    let _large_file_permit = large_file_permit;
+                    search_one(&path)
+                })
+            }
+            None => {
+                let _large_file_permit = large_file_permit;
+                search_one(path)
+            }
+        };
+        // --in-place 时改写有匹配的文件，结果仍作为替换预览输出。写回在限时的搜索线程之外进行，
+        // 超时后仍在运行的搜索线程不会再改动文件
+        let search_result = search_result.and_then(|search| {
+            if let Some(replacement) = replacement_in_place.as_ref().filter(|_| !search.results.is_empty()) {
+                let edit = replacement.edit_file(path)?;
+                if edit.changed_lines > 0 {
+                    edit.write_to(path)?;
+                }
+            }
+            Ok(search)
+        });
+
+        match search_result {
+            Ok(search) => {
+                lines_scanned_clone.fetch_add(search.lines_scanned, Ordering::Relaxed);
+
+                // 发送结果
+                if !search.results.is_empty() {
+                    let _ = tx_clone.send(search.results);
                 }
             }
+            Err(err) if utf8_only && err.is::<domain::search::NotUtf8>() => skip_non_utf8(path),
+            Err(err) => {
+                // 记录搜索错误到错误日志
+                let error_type = if err.is::<domain::search::SearchTimeout>() {
+                    ErrorType::Timeout
+                } else if err.is::<domain::search::NotUtf8>() {
+                    ErrorType::InvalidUtf8
+                } else {
+                    ErrorType::classify(&err, ErrorType::FileRead)
+                };
+                let _ = error_logger_clone.log_error(
+                    error_type,
+                    Some(&path.to_string_lossy()),
+                    "文件搜索失败",
+                    Some(&err.to_string()),
+                );
 
-            Ok(())
-        },
-    )?;
+                // 不再向控制台输出错误，只记录到错误日志
+
+                if fail_fast {
+                    first_failure_clone.lock().unwrap()
+                        .get_or_insert_with(|| (path.to_path_buf(), format!("{:#}", err)));
+                    return Err(ScanAborted(format!("文件搜索失败: {}", path.display())).into());
+                }
+
+                if let Some(max) = max_errors {
+                    if error_logger_clone.get_total_errors() >= max {
+                        return Err(ScanAborted(format!("错误数达到上限 {}", max)).into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    };
+
+    // 遍历错误、不存在的路径和无法读取的元数据也记入错误日志
+    let error_logger_clone = Arc::clone(&error_logger);
+    let report_walk_error = move |error_type: ErrorType, path: &std::path::Path, message: &str| {
+        let path = path.to_string_lossy();
+        let _ = error_logger_clone.log_error(error_type, (!path.is_empty()).then_some(&*path), message, None);
+    };
+
+    let start_time = std::time::Instant::now();
+    // 被忽略规则排除的路径在主遍历中记录可见路径，搜索结束后再对比
+    let show_excluded = args.show_excluded && walk_options.respect_gitignore
+        && !(search_stdin || path_list_mode);
+    let mut visible_paths = None;
+    let (total_files, _) = if let Some(matcher) = &stdin_matcher {
+        // 标准输入的内容作为一个文件搜索
+        let search = domain::search::search_reader(
+            domain::search::STDIN_PATH,
+            std::io::stdin().lock(),
+            matcher,
+            context,
+            max_matches_per_line,
+            max_count,
+            count_all_lines,
+        )?;
+        lines_scanned.fetch_add(search.lines_scanned, Ordering::Relaxed);
+        if !search.results.is_empty() {
+            let _ = tx.send(search.results);
+        }
+        // 不搜索文件，释放其中的发送端，结果通道才能关闭
+        drop(search_file);
+        (1, 0)
+    } else if path_list_mode {
+        let (reader, separator): (Box<dyn std::io::BufRead>, u8) = match &args.files_from {
+            Some(list) if list != std::path::Path::new("-") => {
+                let file = std::fs::File::open(list)
+                    .with_context(|| format!("无法读取文件列表: {}", list.display()))?;
+                (Box::new(std::io::BufReader::new(file)), b'\n')
+            }
+            _ => (Box::new(std::io::stdin().lock()), if args.stdin_paths0 { b'\0' } else { b'\n' }),
+        };
+        domain::file_walker::scan_paths(
+            reader,
+            separator,
+            filter,
+            walk_options.thread_count(),
+            logger_clone,
+            search_file,
+            report_walk_error,
+        )?
+    } else if show_excluded {
+        let (counts, visible) = domain::file_walker::scan_directory_recording_visible(
+            &search_path,
+            filter,
+            &walk_options,
+            logger_clone,
+            search_file,
+            report_walk_error,
+        )?;
+        visible_paths = visible;
+        counts
+    } else {
+        domain::file_walker::scan_directory(
+            &search_path,
+            filter,
+            &walk_options,
+            logger_clone,
+            search_file,
+            report_walk_error,
+        )?
+    };
     
     // 关闭发送通道
     drop(tx);
@@ -273,6 +1538,9 @@ fn main() -> Result<()> {
     // 更新最终统计信息
     let mut summary = summary.lock().unwrap();
     summary.total_files = total_files;
+    summary.total_lines = lines_scanned.load(Ordering::Relaxed);
+    summary.non_utf8_skipped = non_utf8_skipped.load(Ordering::Relaxed);
+    summary.peak_concurrent_large_reads = large_file_limiter.as_ref().map(|limiter| limiter.peak());
     
     // 计算总时间
     let duration = start_time.elapsed();
@@ -280,18 +1548,68 @@ fn main() -> Result<()> {
     // 停止CPU监控
     cpu_monitor.stop();
 
+    let first_failure = first_failure.lock().unwrap().take();
+
+    // 记录本次扫描时间，供下次增量扫描使用（--fail-fast 中止时有文件未搜索，不记录）
+    if let (Some(mut state), None) = (scan_state, &first_failure) {
+        state.record_scan(&search_path, scan_started_at);
+        state.save(&state_path)?;
+    }
+
     // 完成错误日志记录
     error_logger.finalize()?;
 
-    // 打印摘要
-    summary.print()?;
+    if let Some(max) = max_errors {
+        if error_logger.get_total_errors() >= max {
+            eprintln!("错误过多（已达 {} 个），搜索已中止，以下为部分结果的统计", max);
+        }
+    }
+    if first_failure.is_some() {
+        eprintln!("--fail-fast: 遇到无法搜索的文件，搜索已中止，以下为部分结果的统计");
+    }
 
-    // 显示CPU监控状态
     let monitor_status = cpu_monitor.get_status();
-    println!("性能监控: {}", monitor_status.format());
 
-    // 显示错误摘要（如果有错误）
-    error_logger.print_error_summary();
+    let mut stdout = presentation::LineEndingWriter::new(std::io::stdout().lock(), line_ending);
+    if args.json {
+        // 即使没有匹配也总是输出摘要，消费方据此判断输出已完整
+        presentation::write_json_summary(&mut stdout, &summary, error_logger.get_total_errors())?;
+        if args.json_done {
+            presentation::write_json_done(&mut stdout, summary.total_matches)?;
+        }
+    } else if args.locations || args.files_with_matches {
+        if error_logger.has_errors() && !args.no_messages {
+            eprintln!("警告: 搜索过程中发现 {} 个错误", error_logger.get_total_errors());
+        }
+    } else {
+        // 打印摘要
+        presentation::write_summary(
+            &mut stdout,
+            &summary,
+            args.summary_format,
+            error_logger.get_total_errors(),
+        )?;
+
+        // 显示CPU监控状态
+        if args.summary_format != SummaryFormat::Json {
+            writeln!(stdout, "性能监控: {}", monitor_status.format())?;
+        }
+
+        // 显示被忽略规则排除的路径
+        if let Some(visible) = &visible_paths {
+            print_excluded_entries(&mut stdout, &domain::ignore_report::find_excluded(
+                &search_path,
+                &walk_options,
+                &excluded_filter,
+                visible,
+            )?)?;
+        }
+
+        // 显示错误摘要（如果有错误），JSON 摘要中已包含错误数
+        if args.summary_format != SummaryFormat::Json {
+            error_logger.write_error_summary(&mut stdout)?;
+        }
+    }
 
     // 完成调试日志记录
     if logger.is_enabled() {
@@ -299,6 +1617,184 @@ fn main() -> Result<()> {
         logger.log_message(&format!("最终CPU状态: {}", monitor_status.format()))?;
         logger.log_message(&format!("错误统计: {} 个错误", error_logger.get_total_errors()))?;
     }
+
+    if let Some((path, detail)) = first_failure {
+        anyhow::bail!("无法搜索文件 {}: {}", path.display(), detail);
+    }
+
+    if let Some(message) = match_policy_violation(summary.total_matches, args.max_allowed) {
+        stdout.flush()?;
+        eprintln!("{}", message);
+        std::process::exit(POLICY_VIOLATION_EXIT_CODE);
+    }
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_format_json_is_the_same_as_json() {
+        let mut args = Args::parse_from(os_args(&["fe", "--format", "json", "--json-done", "needle"]));
+        apply_output_format(&mut args).unwrap();
+        assert!(args.json);
+
+        let mut args = Args::parse_from(os_args(&["fe", "--format", "json", "--blame", "needle"]));
+        assert!(apply_output_format(&mut args).unwrap_err().to_string().contains("--blame"));
+
+        // 与 --json 冲突的选项同样与 --format json 冲突
+        assert!(Args::try_parse_from(os_args(&["fe", "--json", "--summary-format", "table", "needle"])).is_err());
+        let mut args = Args::parse_from(os_args(&["fe", "--format", "json", "--summary-format", "table", "needle"]));
+        assert!(apply_output_format(&mut args).unwrap_err().to_string().contains("--summary-format"));
+
+        let mut args = Args::parse_from(os_args(&["fe", "--json-done", "needle"]));
+        assert!(apply_output_format(&mut args).is_err());
+
+        assert!(Args::try_parse_from(os_args(&["fe", "--format", "json", "--json", "needle"])).is_err());
+        assert!(Args::try_parse_from(os_args(&["fe", "--format", "yaml", "needle"])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_root_over_system_path_warning() {
+        let root = std::path::Path::new("/");
+        let warning = root_over_system_path_warning(root, true, false).unwrap();
+        assert!(warning.contains("--one-file-system"), "{}", warning);
+        // 路径先规范化，`/tmp/..` 也是根目录
+        assert!(root_over_system_path_warning(std::path::Path::new("/tmp/.."), true, false).is_some());
+
+        assert!(root_over_system_path_warning(root, false, false).is_none());
+        assert!(root_over_system_path_warning(root, true, true).is_none());
+        let dir = tempdir().unwrap();
+        assert!(root_over_system_path_warning(dir.path(), true, false).is_none());
+    }
+
+    #[test]
+    fn test_in_place_rejects_partial_selections() {
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["fe", "--replace", "x", "--in-place"];
+            args.extend_from_slice(extra);
+            args.extend(["needle", "."]);
+            Args::try_parse_from(os_args(&args))
+        };
+        assert!(parse(&[]).is_ok());
+        for extra in [&["--lines", "1:2"][..], &["--json-path", "a"], &["--search-archives"], &["--encoding", "gbk"], &["-m", "1"]] {
+            assert!(parse(extra).is_err(), "{:?}", extra);
+        }
+    }
+
+    #[test]
+    fn test_check_search_path() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "hello").unwrap();
+        assert!(check_search_path(dir.path()).is_ok());
+        assert!(check_search_path(&file).is_ok());
+
+        let missing = dir.path().join("missing");
+        let err = check_search_path(&missing).unwrap_err();
+        assert_eq!(err.to_string(), format!("搜索路径不存在: {}", missing.display()));
+
+        #[cfg(unix)]
+        {
+            let fifo = dir.path().join("pipe");
+            let c_path = std::ffi::CString::new(fifo.to_string_lossy().as_bytes()).unwrap();
+            // SAFETY: c_path 是有效的以 NUL 结尾的路径
+            assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+            let err = check_search_path(&fifo).unwrap_err();
+            assert!(err.to_string().contains("不是目录或普通文件"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_args_file_matches_direct_args() {
+        let temp_dir = tempdir().unwrap();
+        let args_file = temp_dir.path().join("args.txt");
+        std::fs::write(
+            &args_file,
+            "# 常用参数\n--regex\n\n--min-size\n1K\n  --exclude-dir  \ntarget,build\n",
+        ).unwrap();
+
+        let from_file = expand_args_files(os_args(&[
+            "FindEverything",
+            &format!("@{}", args_file.display()),
+            "foo.*bar",
+        ])).unwrap();
+        let direct = os_args(&[
+            "FindEverything",
+            "--regex",
+            "--min-size",
+            "1K",
+            "--exclude-dir",
+            "target,build",
+            "foo.*bar",
+        ]);
+
+        assert_eq!(from_file, direct);
+        assert_eq!(
+            format!("{:?}", Args::parse_from(from_file)),
+            format!("{:?}", Args::parse_from(direct))
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("3d").unwrap(), Duration::from_secs(3 * 86400));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+
+    #[test]
+    fn test_filename_shown_by_default_for_directories() {
+        assert!(should_show_filename(false, false, false));
+        assert!(!should_show_filename(false, true, false));
+    }
+
+    #[test]
+    fn test_filename_omitted_by_default_for_single_file() {
+        assert!(!should_show_filename(false, false, true));
+        assert!(should_show_filename(true, false, true));
+
+        let args = Args::parse_from(["FindEverything", "-h", "pattern", "file.txt"]);
+        assert!(args.no_filename);
+        let args = Args::parse_from(["FindEverything", "-H", "pattern", "file.txt"]);
+        assert!(args.with_filename);
+    }
+
+    #[test]
+    fn test_match_policy_violation() {
+        assert_eq!(match_policy_violation(12, None), None);
+        assert_eq!(match_policy_violation(0, Some(0)), None);
+        assert_eq!(match_policy_violation(3, Some(3)), None);
+        assert_eq!(
+            match_policy_violation(12, Some(0)).unwrap(),
+            "policy violation: 12 matches exceed allowed 0"
+        );
+
+        // 不带值时上限为 0
+        let args = Args::parse_from(["FindEverything", "TODO", "--max-allowed"]);
+        assert_eq!(args.max_allowed, Some(0));
+        let args = Args::parse_from(["FindEverything", "--max-allowed", "5", "TODO"]);
+        assert_eq!(args.max_allowed, Some(5));
+    }
+
+    #[test]
+    fn test_missing_args_file_kept_as_argument() {
+        let args = os_args(&["FindEverything", "@no_such_user"]);
+        assert_eq!(expand_args_files(args.clone()).unwrap(), args);
+    }
+}
@@ -1,75 +1,516 @@
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
 use std::time::Instant;
 
 use anyhow::Result;
 
 use crate::domain::search::SearchResult;
 
-/// 格式化持续时间
-pub fn format_duration(duration: std::time::Duration) -> String {
-    let total_secs = duration.as_secs();
-    let hours = total_secs / 3600;
-    let mins = (total_secs % 3600) / 60;
-    let secs = total_secs % 60;
-    
-    if hours > 0 {
-        format!("{}h {}m {}s", hours, mins, secs)
-    } else if mins > 0 {
-        format!("{}m {}s", mins, secs)
-    } else {
-        format!("{}.{:03}s", secs, duration.subsec_millis())
+/// 结果的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 带颜色的文本（默认）
+    #[default]
+    Text,
+    /// 每行一个 JSON 事件，不含任何颜色转义，与 `--json` 相同（按块缓冲输出）
+    Json,
+    /// 与 `json` 相同的事件，但每个匹配输出后立即刷新，适合边搜索边消费结果
+    Ndjson,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            other => anyhow::bail!("未知的输出格式: {}（可选 text、json、ndjson）", other),
+        }
     }
 }
 
-/// 输出搜索结果
-pub fn print_search_result(result: &SearchResult) -> Result<()> {
-    let mut stdout = io::stdout().lock();
+/// 何时输出颜色
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// 输出到终端时使用颜色，重定向到文件或管道时不使用（默认）
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => anyhow::bail!("未知的颜色选项: {}（可选 auto、always、never）", other),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// 输出目标是否为终端时，是否应该使用颜色
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            Self::Auto => is_terminal,
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+
+    /// 输出到标准输出时是否应该使用颜色
+    pub fn for_stdout(self) -> bool {
+        self.enabled(io::stdout().is_terminal())
+    }
+}
+
+/// 输出中使用的颜色转义，不使用颜色时全部为空字符串
+#[derive(Debug, Clone, Copy)]
+struct Palette {
+    path: &'static str,
+    line_number: &'static str,
+    matched: &'static str,
+    dim: &'static str,
+    annotation: &'static str,
+    removed: &'static str,
+    added: &'static str,
+    reset: &'static str,
+}
+
+impl Palette {
+    const COLORED: Palette = Palette {
+        path: "\x1b[1;32m",
+        line_number: "\x1b[1;34m",
+        matched: "\x1b[1;31m",
+        dim: "\x1b[2;37m",
+        annotation: "\x1b[2;36m",
+        removed: "\x1b[31m",
+        added: "\x1b[32m",
+        reset: "\x1b[0m",
+    };
+
+    const PLAIN: Palette = Palette {
+        path: "",
+        line_number: "",
+        matched: "",
+        dim: "",
+        annotation: "",
+        removed: "",
+        added: "",
+        reset: "",
+    };
+
+    fn new(color: bool) -> Self {
+        if color { Self::COLORED } else { Self::PLAIN }
+    }
+}
+
+/// 输出搜索结果，超过 `max_line_length` 个字符的行只显示匹配附近的部分，
+/// 按 `color` 和标准输出是否为终端决定是否输出颜色转义
+pub fn print_search_result(result: &SearchResult, max_line_length: usize, color: ColorChoice) -> Result<()> {
+    write_search_result(&mut io::stdout().lock(), result, max_line_length, color.for_stdout())
+}
 
+/// 将搜索结果写入指定输出
+pub fn write_search_result<W: Write>(stdout: &mut W, result: &SearchResult, max_line_length: usize, color: bool) -> Result<()> {
+    let colors = Palette::new(color);
     // 输出文件路径和行号
-    writeln!(stdout, "\x1b[1;32m{}\x1b[0m:\x1b[1;34m{}\x1b[0m", result.path, result.line_number)?;
+    write_result_header(stdout, result, colors, true, false, false, false)?;
+    write_result_lines(stdout, result, colors, None, Some(max_line_length))
+}
+
+/// 输出结果头部（文件路径和行号），`show_path` 为 false 时省略路径，如 `12:5`、`12(3)`、`12 (87%)`；
+/// `show_column` 为 true 时附加第一个匹配的列（从 1 开始，按字节计算），
+/// 如 `path:12:5`，`show_match_count` 为 true 时附加该行匹配次数，如 `path:12(3)`，
+/// `show_position` 为 true 时附加匹配行在文件中的相对位置，如 `path:12 (87%)`
+fn write_result_header<W: Write>(
+    stdout: &mut W,
+    result: &SearchResult,
+    colors: Palette,
+    show_path: bool,
+    show_column: bool,
+    show_match_count: bool,
+    show_position: bool,
+) -> Result<()> {
+    if show_path {
+        write!(stdout, "{}{}{}:", colors.path, result.path, colors.reset)?;
+    }
+    write!(stdout, "{}{}{}", colors.line_number, result.line_number, colors.reset)?;
+    if let Some(start) = result.match_start().filter(|_| show_column) {
+        write!(stdout, ":{}", start + 1)?;
+    }
+    if show_match_count {
+        let suffix = if result.matches_truncated { "+" } else { "" };
+        write!(stdout, "({}{})", result.matches_on_line(), suffix)?;
+    }
+    if let Some(percent) = result.position_percent().filter(|_| show_position) {
+        write!(stdout, " ({}%)", percent)?;
+    }
+    writeln!(stdout)?;
+    Ok(())
+}
 
+/// 输出上下文行和匹配行，`annotation` 输出在匹配行之后（如 git blame 信息）
+///
+/// `max_line_length` 不为 None 时，超长的行只显示其中一段（匹配行保留匹配附近的部分，上下文行保留开头），
+/// 被省略的部分用 `…` 表示；长度按字符计算，不包括颜色转义。
+fn write_result_lines<W: Write>(
+    stdout: &mut W,
+    result: &SearchResult,
+    colors: Palette,
+    annotation: Option<&str>,
+    max_line_length: Option<usize>,
+) -> Result<()> {
     // 输出上下文行（之前）
     for (i, context_line) in result.context_before.iter().enumerate() {
         let line_num = result.line_number - (result.context_before.len() - i) as u64;
-        writeln!(stdout, "\x1b[2;37m{:>6}:\x1b[0m  {}", line_num, context_line)?;
+        write_context_line(stdout, colors, line_num, context_line, max_line_length)?;
     }
 
-    // 输出匹配行内容，高亮匹配部分
-    let line = &result.line;
-    let matched_text = &result.matched_text;
-
-    write!(stdout, "\x1b[1;34m{:>6}:\x1b[0m  ", result.line_number)?;
-    if let Some(idx) = line.find(matched_text) {
-        let before = &line[..idx];
-        let after = &line[idx + matched_text.len()..];
-        
-        write!(stdout, "{}", before)?;
-        write!(stdout, "\x1b[1;31m{}\x1b[0m", matched_text)?;
-        writeln!(stdout, "{}", after)?;
-    } else {
-        writeln!(stdout, "{}", line)?;
+    write!(stdout, "{}{:>6}:{}  ", colors.line_number, result.line_number, colors.reset)?;
+    write_matched_line(stdout, result, colors, max_line_length)?;
+    writeln!(stdout)?;
+    if let Some(annotation) = annotation {
+        writeln!(stdout, "{}{:>6}   ↳ {}{}", colors.annotation, "", annotation, colors.reset)?;
     }
 
     // 输出上下文行（之后）
     for (i, context_line) in result.context_after.iter().enumerate() {
         let line_num = result.line_number + (i + 1) as u64;
-        writeln!(stdout, "\x1b[2;37m{:>6}:\x1b[0m  {}", line_num, context_line)?;
+        write_context_line(stdout, colors, line_num, context_line, max_line_length)?;
     }
 
-    // 如果有上下文行，添加分隔符
-    if !result.context_before.is_empty() || !result.context_after.is_empty() {
-        writeln!(stdout, "\x1b[2;37m--\x1b[0m")?;
+    Ok(())
+}
+
+/// 输出匹配行内容（不含行号和换行），按匹配的字节范围高亮，超长时只显示匹配附近的部分
+fn write_matched_line<W: Write>(
+    stdout: &mut W,
+    result: &SearchResult,
+    colors: Palette,
+    max_line_length: Option<usize>,
+) -> Result<()> {
+    let line = &result.line;
+    let focus = result.matches.first().map_or((0, 0), |&(start, end)| char_boundary_range(line, start, end));
+    let (from, to) = match max_line_length {
+        Some(max) => visible_window(line, focus, max),
+        None => (0, line.len()),
+    };
+
+    if from > 0 {
+        write!(stdout, "{}{}{}", colors.dim, ELLIPSIS, colors.reset)?;
+    }
+    // 依次高亮行内的每个匹配，只输出显示范围内的部分；
+    // 调整到字符边界后与上一个匹配重叠的部分不重复输出
+    let mut written = from;
+    for &(start, end) in &result.matches {
+        let (start, end) = char_boundary_range(line, start, end);
+        let visible = if start == end { (written..=to).contains(&start) } else { start < to && end > written };
+        if !visible {
+            continue;
+        }
+        let (start, end) = (start.max(written), end.min(to));
+        write!(stdout, "{}", &line[written..start])?;
+        if start == end {
+            // 零宽匹配（如 `^`、`$`、环视）没有可高亮的文本，在匹配位置插入标记
+            write!(stdout, "{}{}{}", colors.matched, ZERO_WIDTH_MARKER, colors.reset)?;
+        } else {
+            write!(stdout, "{}{}{}", colors.matched, &line[start..end], colors.reset)?;
+        }
+        written = end;
     }
+    write!(stdout, "{}", &line[written..to])?;
+    if to < line.len() {
+        write!(stdout, "{}{}{}", colors.dim, ELLIPSIS, colors.reset)?;
+    }
+    Ok(())
+}
 
+/// 输出一行上下文，超长时只保留开头
+fn write_context_line<W: Write>(
+    stdout: &mut W,
+    colors: Palette,
+    line_num: u64,
+    line: &str,
+    max_line_length: Option<usize>,
+) -> Result<()> {
+    let (_, to) = match max_line_length {
+        Some(max) => visible_window(line, (0, 0), max),
+        None => (0, line.len()),
+    };
+    write!(stdout, "{}{:>6}:{}  {}", colors.dim, line_num, colors.reset, &line[..to])?;
+    if to < line.len() {
+        write!(stdout, "{}{}{}", colors.dim, ELLIPSIS, colors.reset)?;
+    }
+    writeln!(stdout)?;
     Ok(())
 }
 
+/// 零宽匹配位置的标记
+const ZERO_WIDTH_MARKER: char = '‸';
+
+/// 超长行中被省略部分的标记
+const ELLIPSIS: char = '…';
+
+/// 计算超长行中要显示的字节范围，尽量让 `focus`（字符边界上的字节范围）位于中间
+///
+/// 显示的字符数（包括两端的省略标记）不超过 `max`；`focus` 本身超长时从它的开头显示。
+/// 不超过 `max` 个字符的行原样显示。
+fn visible_window(line: &str, focus: (usize, usize), max: usize) -> (usize, usize) {
+    let boundaries: Vec<usize> = line.char_indices().map(|(i, _)| i).collect();
+    let char_count = boundaries.len();
+    if char_count <= max {
+        return (0, line.len());
+    }
+
+    // 为两端的省略标记各留一个字符
+    let budget = max.saturating_sub(2).max(1);
+    let focus_start = boundaries.partition_point(|&i| i < focus.0);
+    let focus_end = boundaries.partition_point(|&i| i < focus.1);
+
+    let slack = budget.saturating_sub(focus_end - focus_start);
+    let mut first = focus_start.saturating_sub(slack / 2);
+    let mut last = first + budget;
+    if first == 0 {
+        // 开头没有省略标记，多显示一个字符
+        last += 1;
+    }
+    if last >= char_count {
+        last = char_count;
+        first = char_count - budget - 1;
+    }
+
+    let byte_at = |index: usize| boundaries.get(index).copied().unwrap_or(line.len());
+    (byte_at(first), byte_at(last))
+}
+
+/// 将字节范围调整到字符边界（起点向前、终点向后），保证切片不会因多字节字符而 panic
+fn char_boundary_range(line: &str, start: usize, end: usize) -> (usize, usize) {
+    let mut start = start.min(line.len());
+    let mut end = end.clamp(start, line.len());
+
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !line.is_char_boundary(end) {
+        end += 1;
+    }
+
+    (start, end)
+}
+
+/// 分组输出搜索结果，保证两组结果之间恰好有一个分隔符，最后一组之后没有分隔符
+///
+/// 同一文件中与上一个结果首尾相接的结果（上下文已合并）会延续上一组输出，
+/// 不再重复输出头部和分隔符。
+pub struct GroupedPrinter {
+    separator: Option<String>,
+    colors: Palette,
+    max_line_length: Option<usize>,
+    show_line_match_count: bool,
+    show_filename: bool,
+    show_column: bool,
+    show_position: bool,
+    printed_any: bool,
+    /// 上一组输出的文件路径和最后一行的行号
+    last_printed: Option<(String, u64)>,
+}
+
+impl GroupedPrinter {
+    /// 创建分组输出器，`separator` 为 None 时不输出分隔符
+    pub fn new(separator: Option<String>) -> Self {
+        Self {
+            separator,
+            colors: Palette::COLORED,
+            max_line_length: None,
+            show_line_match_count: false,
+            show_filename: true,
+            show_column: false,
+            show_position: false,
+            printed_any: false,
+            last_printed: None,
+        }
+    }
+
+    /// 是否输出 `路径:行号` 头部（不输出时匹配行仍带行号）
+    pub fn with_filename(mut self, show: bool) -> Self {
+        self.show_filename = show;
+        self
+    }
+
+    /// 是否输出颜色转义（路径、行号、匹配高亮等），关闭时输出适合重定向的纯文本
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.colors = Palette::new(color);
+        self
+    }
+
+    /// 超过 `max` 个字符的行只显示匹配附近的部分
+    pub fn with_max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// 在结果头部附加每行的匹配次数
+    pub fn with_line_match_count(mut self, show: bool) -> Self {
+        self.show_line_match_count = show;
+        self
+    }
+
+    /// 在结果头部附加第一个匹配的列（从 1 开始，按字节计算），如 `path:12:5`
+    pub fn with_column(mut self, show: bool) -> Self {
+        self.show_column = show;
+        self
+    }
+
+    /// 在结果头部附加匹配行在文件中的相对位置（百分比）
+    pub fn with_position(mut self, show: bool) -> Self {
+        self.show_position = show;
+        self
+    }
+
+    /// 输出文件头部 `路径 (N 行, M 处匹配)`，`results` 为该文件的全部结果，M 为各行匹配次数之和
+    pub fn write_file_header<W: Write>(&mut self, writer: &mut W, results: &[SearchResult]) -> Result<()> {
+        let Some(first) = results.first() else {
+            return Ok(());
+        };
+        let match_count: usize = results.iter().map(SearchResult::matches_on_line).sum();
+
+        self.write_separator(writer)?;
+        writeln!(
+            writer,
+            "{}{}{} ({} 行, {} 处匹配)",
+            self.colors.path, first.path, self.colors.reset, first.total_lines, match_count
+        )?;
+
+        // 头部之后的第一组结果不需要分隔符
+        self.printed_any = true;
+        self.last_printed = None;
+        Ok(())
+    }
+
+    fn write_separator<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if let (Some(separator), true) = (&self.separator, self.printed_any) {
+            writeln!(writer, "{}{}{}", self.colors.dim, separator, self.colors.reset)?;
+        }
+        Ok(())
+    }
+
+    /// 输出一组结果，必要时先输出分隔符
+    pub fn write<W: Write>(&mut self, writer: &mut W, result: &SearchResult) -> Result<()> {
+        self.write_annotated(writer, result, None)
+    }
+
+    /// 输出一组结果，并在匹配行之后附加一行注释
+    pub fn write_annotated<W: Write>(&mut self, writer: &mut W, result: &SearchResult, annotation: Option<&str>) -> Result<()> {
+        // 路径匹配（--name/--path，行号为 0）没有行内容，只输出高亮的路径
+        if result.line_number == 0 {
+            write_matched_line(writer, result, self.colors, self.max_line_length)?;
+            writeln!(writer)?;
+            self.printed_any = true;
+            return Ok(());
+        }
+
+        let first_line = result.line_number - result.context_before.len() as u64;
+        let continues_block = self.last_printed.as_ref().is_some_and(|(path, last_line)| {
+            *path == result.path && first_line == last_line + 1
+        });
+
+        if !continues_block {
+            if self.last_printed.is_some() {
+                self.write_separator(writer)?;
+            }
+            // 不显示路径时仍输出行号、列、匹配次数和相对位置，否则这些信息会丢失
+            if self.show_filename || self.show_column || self.show_line_match_count || self.show_position {
+                write_result_header(
+                    writer,
+                    result,
+                    self.colors,
+                    self.show_filename,
+                    self.show_column,
+                    self.show_line_match_count,
+                    self.show_position,
+                )?;
+            }
+        }
+        write_result_lines(writer, result, self.colors, annotation, self.max_line_length)?;
+
+        self.printed_any = true;
+        let last_line = result.line_number + result.context_after.len() as u64;
+        self.last_printed = Some((result.path.clone(), last_line));
+        Ok(())
+    }
+
+    /// 以差异的形式预览一个匹配行的替换：`-` 行为原内容，`+` 行为替换后的内容
+    pub fn write_replacement<W: Write>(&mut self, writer: &mut W, result: &SearchResult, replaced: &str) -> Result<()> {
+        if self.show_filename {
+            write_result_header(writer, result, self.colors, true, false, false, false)?;
+        }
+        let colors = self.colors;
+        writeln!(writer, "{}-{:>6}:  {}{}", colors.removed, result.line_number, result.line, colors.reset)?;
+        writeln!(writer, "{}+{:>6}:  {}{}", colors.added, result.line_number, replaced, colors.reset)?;
+        self.printed_any = true;
+        Ok(())
+    }
+}
+
+/// 以 `path:line` 格式逐个输出结果位置（无颜色、无行内容），供编辑器跳转列表等导航工具使用
+///
+/// 连续的相同位置只输出一次。
+#[derive(Debug, Default)]
+pub struct LocationPrinter {
+    last: Option<(String, u64)>,
+}
+
+impl LocationPrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 该结果的位置是否与上一个输出的位置相同
+    pub fn is_duplicate(&self, result: &SearchResult) -> bool {
+        self.last.as_ref().is_some_and(|(path, line)| *path == result.path && *line == result.line_number)
+    }
+
+    /// 输出一个结果的位置，重复的位置被忽略
+    pub fn write<W: Write>(&mut self, writer: &mut W, result: &SearchResult) -> Result<()> {
+        if self.is_duplicate(result) {
+            return Ok(());
+        }
+        writeln!(writer, "{}:{}", result.path, result.line_number)?;
+        self.last = Some((result.path.clone(), result.line_number));
+        Ok(())
+    }
+}
+
 /// 搜索摘要
 pub struct SearchSummary {
     pub start_time: Instant,
     pub total_files: u64,
     pub matched_files: u64,
     pub total_matches: u64,
+    pub truncated_lines: u64,
+    /// 扫描的总行数
+    pub total_lines: u64,
+    /// 被 --dedup-results 省略的重复结果数
+    pub duplicates_suppressed: u64,
+    /// 同时读取的大文件数的峰值（限制大文件并发时才统计）
+    pub peak_concurrent_large_reads: Option<usize>,
+    /// 被 --utf8-only 跳过的非 UTF-8 文件数
+    pub non_utf8_skipped: u64,
+    /// 按扩展名的匹配统计（指定 --stats 时才统计）
+    pub extension_stats: Option<super::ExtensionStats>,
+}
+
+impl Default for SearchSummary {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SearchSummary {
@@ -79,19 +520,393 @@ impl SearchSummary {
             total_files: 0,
             matched_files: 0,
             total_matches: 0,
+            truncated_lines: 0,
+            total_lines: 0,
+            duplicates_suppressed: 0,
+            peak_concurrent_large_reads: None,
+            non_utf8_skipped: 0,
+            extension_stats: None,
+        }
+    }
+
+    /// 合并另一个摘要（用于多路径或多消费者的部分统计）
+    ///
+    /// 计数器相加，开始时间取两者中较早的一个，以保证总用时覆盖整个搜索过程
+    pub fn merge(&mut self, other: &SearchSummary) {
+        self.start_time = self.start_time.min(other.start_time);
+        self.total_files += other.total_files;
+        self.matched_files += other.matched_files;
+        self.total_matches += other.total_matches;
+        self.truncated_lines += other.truncated_lines;
+        self.total_lines += other.total_lines;
+        self.duplicates_suppressed += other.duplicates_suppressed;
+        self.non_utf8_skipped += other.non_utf8_skipped;
+        self.peak_concurrent_large_reads = self.peak_concurrent_large_reads.max(other.peak_concurrent_large_reads);
+        match (&mut self.extension_stats, &other.extension_stats) {
+            (Some(stats), Some(other_stats)) => stats.merge(other_stats),
+            (None, Some(other_stats)) => self.extension_stats = Some(other_stats.clone()),
+            _ => {}
         }
     }
 
     pub fn print(&self) -> Result<()> {
-        let duration = self.start_time.elapsed();
-        
-        println!("\n搜索摘要:");
-        println!("----------------------------");
-        println!("总用时: {}", format_duration(duration));
-        println!("扫描文件: {}", self.total_files);
-        println!("匹配文件: {}", self.matched_files);
-        println!("匹配项数: {}", self.total_matches);
-        
-        Ok(())
+        super::summary::write_summary(&mut io::stdout().lock(), self, super::summary::SummaryFormat::Plain, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sample_result(line_number: u64) -> SearchResult {
+        SearchResult {
+            path: "a.txt".to_string(),
+            line_number,
+            line: "hello world".to_string(),
+            matched_text: "hello".to_string(),
+            context_before: vec!["before".to_string()],
+            context_after: vec!["after".to_string()],
+            matches: vec![(0, 5)],
+            matches_truncated: false,
+            total_lines: 42,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    #[test]
+    fn test_adjacent_results_continue_block() {
+        let mut first = sample_result(10);
+        first.context_after = vec!["11".to_string(), "12".to_string()];
+        let mut second = sample_result(13);
+        second.context_before.clear();
+
+        let mut printer = GroupedPrinter::new(Some("--".to_string()));
+        let mut output = Vec::new();
+        printer.write(&mut output, &first).unwrap();
+        printer.write(&mut output, &second).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("a.txt").count(), 1);
+        assert!(!output.contains("--"));
+    }
+
+    #[test]
+    fn test_locations_are_plain_and_deduplicated() {
+        let results = vec![sample_result(2), sample_result(2), sample_result(10)];
+        let mut printer = LocationPrinter::new();
+        let mut output = Vec::new();
+        for result in &results {
+            printer.write(&mut output, result).unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt:2\na.txt:10\n");
+    }
+
+    #[test]
+    fn test_position_in_header() {
+        let mut printer = GroupedPrinter::new(None).with_position(true);
+        let mut output = Vec::new();
+        printer.write(&mut output, &sample_result(37)).unwrap();
+        assert!(String::from_utf8(output).unwrap().lines().next().unwrap().ends_with(" (88%)"));
+
+        // 总行数未知时不显示
+        let mut result = sample_result(37);
+        result.total_lines = 0;
+        let mut output = Vec::new();
+        GroupedPrinter::new(None).with_position(true).write(&mut output, &result).unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains('%'));
+
+        // 不显示路径时只输出行号和相对位置
+        let mut output = Vec::new();
+        GroupedPrinter::new(None)
+            .with_color(false)
+            .with_filename(false)
+            .with_position(true)
+            .write(&mut output, &sample_result(37))
+            .unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("37 (88%)\n"));
+    }
+
+    #[test]
+    fn test_file_header() {
+        let mut results = vec![sample_result(2), sample_result(10), sample_result(20)];
+        // 一行两处匹配按两处计数
+        results[1].matches = vec![(0, 5), (6, 11)];
+        let mut printer = GroupedPrinter::new(Some("--".to_string()));
+        let mut output = Vec::new();
+        printer.write_file_header(&mut output, &results).unwrap();
+        for result in &results {
+            printer.write(&mut output, result).unwrap();
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        let first_line = output.lines().next().unwrap();
+        assert_eq!(first_line, "\x1b[1;32ma.txt\x1b[0m (42 行, 4 处匹配)");
+        assert_eq!(output.matches("--").count(), 2);
+    }
+
+    #[test]
+    fn test_line_match_count_annotation() {
+        let mut result = sample_result(7);
+        result.matches = vec![(0, 5), (6, 8), (9, 11)];
+
+        let mut output = Vec::new();
+        GroupedPrinter::new(None).write(&mut output, &result).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("\x1b[1;32ma.txt\x1b[0m:\x1b[1;34m7\x1b[0m\n"));
+
+        let mut output = Vec::new();
+        GroupedPrinter::new(None)
+            .with_line_match_count(true)
+            .write(&mut output, &result)
+            .unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("\x1b[1;32ma.txt\x1b[0m:\x1b[1;34m7\x1b[0m(3)\n"));
+
+        // 不显示路径时匹配次数跟在行号之后
+        let mut output = Vec::new();
+        GroupedPrinter::new(None)
+            .with_color(false)
+            .with_filename(false)
+            .with_line_match_count(true)
+            .write(&mut output, &result)
+            .unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("7(3)\n"));
+    }
+
+    #[test]
+    fn test_highlight_every_match_on_line() {
+        let mut result = sample_result(1);
+        result.line = "cat, cat and cat".to_string();
+        result.matches = vec![(0, 3), (5, 8), (13, 16)];
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output.contains("\x1b[1;31mcat\x1b[0m, \x1b[1;31mcat\x1b[0m and \x1b[1;31mcat\x1b[0m\n"),
+            "{}",
+            output
+        );
+
+        // 超长行中只高亮显示范围内的匹配
+        result.line = format!("cat{}cat", "-".repeat(300));
+        result.matches = vec![(0, 3), (303, 306)];
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 60, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("\x1b[1;31m").count(), 1);
+
+        // 零宽匹配在每个位置插入标记
+        result.line = "ab".to_string();
+        result.matches = vec![(0, 0), (1, 1), (2, 2)];
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, false).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("  ‸a‸b‸\n"));
+    }
+
+    #[test]
+    fn test_column_in_header() {
+        let mut result = sample_result(7);
+        result.line = "前缀 hello".to_string();
+        result.matches = vec![(7, 12)];
+
+        let mut output = Vec::new();
+        let mut printer = GroupedPrinter::new(None).with_color(false).with_column(true).with_line_match_count(true);
+        printer.write(&mut output, &result).unwrap();
+        // 反向匹配选中的行没有列
+        result.matches.clear();
+        result.line_number = 20;
+        printer.write(&mut output, &result).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("a.txt:7:8(1)\n"), "{}", output);
+        assert!(output.contains("\na.txt:20(0)\n"), "{}", output);
+
+        // 不显示路径时输出 `行:列`
+        result.matches = vec![(7, 12)];
+        let mut output = Vec::new();
+        GroupedPrinter::new(None).with_color(false).with_filename(false).with_column(true).write(&mut output, &result).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("20:8\n"));
+    }
+
+    #[test]
+    fn test_highlight_multibyte_line() {
+        let mut result = sample_result(1);
+        result.line = "前缀 héllo 后缀".to_string();
+        result.matched_text = "héllo".to_string();
+        result.matches = vec![(7, 13)];
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("前缀 \x1b[1;31mhéllo\x1b[0m 后缀"));
+
+        // 不在字符边界上的范围（例如十六进制搜索匹配到半个字符）不能 panic
+        result.matches = vec![(1, 8)];
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[1;31m前缀 h\x1b[0m"));
+    }
+
+    #[test]
+    fn test_zero_width_match_marker() {
+        let mut result = sample_result(1);
+        result.matched_text = String::new();
+
+        result.matches = vec![(0, 0)];
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, true).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("  \x1b[1;31m‸\x1b[0mhello world\n"));
+
+        result.matches = vec![(11, 11)];
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, true).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("hello world\x1b[1;31m‸\x1b[0m\n"));
+    }
+
+    /// 去掉颜色转义后的文本
+    fn strip_ansi(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+
+    #[test]
+    fn test_long_line_keeps_match_near_end_visible() {
+        let mut result = sample_result(1);
+        result.line = format!("{}needle{}", "a".repeat(1000), "b".repeat(10));
+        result.matched_text = "needle".to_string();
+        result.matches = vec![(1000, 1006)];
+        result.context_before = vec!["c".repeat(500)];
+        result.context_after = vec!["短行".to_string()];
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 80, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[1;31mneedle\x1b[0mbbbbbbbbbb\n"), "{}", output);
+
+        let lines: Vec<String> = output.lines().map(strip_ansi).collect();
+        let content = |line: &str| line.split_once(":  ").unwrap().1.to_string();
+        let context = content(&lines[1]);
+        let matched = content(&lines[2]);
+        assert_eq!(context, format!("{}…", "c".repeat(79)));
+        assert_eq!(matched.chars().count(), 80);
+        assert!(matched.starts_with("…aaa"));
+        assert!(matched.ends_with("needlebbbbbbbbbb"));
+        assert_eq!(content(&lines[3]), "短行");
+    }
+
+    #[test]
+    fn test_long_line_window_around_multibyte_match() {
+        let mut result = sample_result(1);
+        result.line = format!("{}目标{}", "前".repeat(100), "后".repeat(100));
+        result.matched_text = "目标".to_string();
+        result.matches = vec![(300, 306)];
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 50, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let matched = strip_ansi(output.lines().nth(2).unwrap());
+        let matched = matched.split_once(":  ").unwrap().1;
+        assert_eq!(matched.chars().count(), 50);
+        assert!(matched.starts_with('…') && matched.ends_with('…'));
+        assert!(output.contains("\x1b[1;31m目标\x1b[0m"));
+    }
+
+    #[test]
+    fn test_no_escapes_without_color() {
+        let mut result = sample_result(7);
+        result.line = format!("{}hello", "x".repeat(300));
+        result.matches = vec![(300, 305)];
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &result, 200, false).unwrap();
+        let mut printer = GroupedPrinter::new(Some("--".to_string())).with_color(false).with_line_match_count(true);
+        printer.write_file_header(&mut output, &[result.clone()]).unwrap();
+        printer.write_annotated(&mut output, &result, Some("blame")).unwrap();
+        printer.write(&mut output, &sample_result(20)).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains('\x1b'), "{}", output);
+        assert!(output.starts_with("a.txt:7\n     6:  before\n     7:  …"));
+        assert!(output.contains("\n--\na.txt:20(1)\n"));
+    }
+
+    #[test]
+    fn test_color_choice() {
+        assert!(ColorChoice::Auto.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert_eq!("NEVER".parse::<ColorChoice>().unwrap(), ColorChoice::Never);
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+
+        let mut output = Vec::new();
+        write_search_result(&mut output, &sample_result(3), 200, ColorChoice::Never.enabled(true)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt:3\n     2:  before\n     3:  hello world\n     4:  after\n");
+    }
+
+    #[test]
+    fn test_without_filename() {
+        let mut output = Vec::new();
+        GroupedPrinter::new(None)
+            .with_filename(false)
+            .write(&mut output, &sample_result(3))
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("a.txt"));
+        assert!(output.contains("     3:"));
+    }
+
+    #[test]
+    fn test_group_separator_between_groups_only() {
+        let mut printer = GroupedPrinter::new(Some("==".to_string()));
+        let mut output = Vec::new();
+        for line_number in [2, 10, 20] {
+            printer.write(&mut output, &sample_result(line_number)).unwrap();
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        let separator = "\x1b[2;37m==\x1b[0m\n";
+        assert_eq!(output.matches(separator).count(), 2);
+        assert!(!output.ends_with(separator));
+        assert!(!output.starts_with(separator));
+    }
+
+    #[test]
+    fn test_summary_merge() {
+        let mut first = SearchSummary::new();
+        first.total_files = 10;
+        first.matched_files = 2;
+        first.total_matches = 5;
+        first.total_lines = 1000;
+
+        std::thread::sleep(Duration::from_millis(5));
+        let mut second = SearchSummary::new();
+        second.total_files = 3;
+        second.matched_files = 1;
+        second.total_matches = 4;
+        second.truncated_lines = 1;
+        second.total_lines = 200;
+
+        let earliest = first.start_time;
+        second.merge(&first);
+
+        assert_eq!(second.start_time, earliest);
+        assert_eq!(second.total_files, 13);
+        assert_eq!(second.matched_files, 3);
+        assert_eq!(second.total_matches, 9);
+        assert_eq!(second.truncated_lines, 1);
+        assert_eq!(second.total_lines, 1200);
     }
 }
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::summary::display_width;
+
+/// 没有扩展名的文件的分组名
+const NO_EXTENSION: &str = "(无扩展名)";
+/// 超出前 N 名的扩展名汇总到这一组
+const OTHER: &str = "其他";
+
+/// 按文件扩展名统计的匹配数和匹配文件数
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionStats {
+    /// 扩展名（小写，带 `.`） -> (匹配数, 匹配文件数)
+    counts: HashMap<String, (u64, u64)>,
+    /// 最多单独列出的扩展名数，其余汇总为“其他”
+    top: usize,
+}
+
+/// 统计表中的一行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtensionRow {
+    pub extension: String,
+    pub matches: u64,
+    pub files: u64,
+}
+
+impl ExtensionStats {
+    /// 最多单独列出 `top` 个扩展名
+    pub fn new(top: usize) -> Self {
+        Self {
+            counts: HashMap::new(),
+            top,
+        }
+    }
+
+    /// 记录一个匹配，`new_file` 表示这是该文件的第一个匹配
+    pub fn record(&mut self, path: &str, new_file: bool) {
+        let extension = Path::new(path)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+            .unwrap_or_else(|| NO_EXTENSION.to_string());
+        let entry = self.counts.entry(extension).or_default();
+        entry.0 += 1;
+        if new_file {
+            entry.1 += 1;
+        }
+    }
+
+    /// 合并另一份统计
+    pub fn merge(&mut self, other: &ExtensionStats) {
+        for (extension, &(matches, files)) in &other.counts {
+            let entry = self.counts.entry(extension.clone()).or_default();
+            entry.0 += matches;
+            entry.1 += files;
+        }
+        self.top = self.top.max(other.top);
+    }
+
+    /// 按匹配数从多到少排列（相同时按扩展名），超出前 N 名的合并为最后一行“其他”
+    pub fn rows(&self) -> Vec<ExtensionRow> {
+        let mut rows: Vec<ExtensionRow> = self.counts
+            .iter()
+            .map(|(extension, &(matches, files))| ExtensionRow {
+                extension: extension.clone(),
+                matches,
+                files,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.extension.cmp(&b.extension)));
+
+        if rows.len() > self.top {
+            let rest = rows.split_off(self.top);
+            rows.push(ExtensionRow {
+                extension: OTHER.to_string(),
+                matches: rest.iter().map(|row| row.matches).sum(),
+                files: rest.iter().map(|row| row.files).sum(),
+            });
+        }
+        rows
+    }
+
+    /// 输出对齐的文本统计表，没有匹配时不输出
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let width = rows.iter().map(|row| display_width(&row.extension)).max().unwrap_or(0);
+        writeln!(w, "\n按扩展名统计:")?;
+        writeln!(w, "----------------------------")?;
+        for row in rows {
+            let padding = " ".repeat(width - display_width(&row.extension));
+            writeln!(w, "{}{}  {} 处匹配, {} 个文件", row.extension, padding, row.matches, row.files)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(top: usize, matches: &[(&str, bool)]) -> ExtensionStats {
+        let mut stats = ExtensionStats::new(top);
+        for &(path, new_file) in matches {
+            stats.record(path, new_file);
+        }
+        stats
+    }
+
+    fn row(extension: &str, matches: u64, files: u64) -> ExtensionRow {
+        ExtensionRow {
+            extension: extension.to_string(),
+            matches,
+            files,
+        }
+    }
+
+    #[test]
+    fn test_rows_sorted_by_match_count() {
+        let stats = stats(10, &[
+            ("src/a.rs", true),
+            ("README.md", true),
+            ("src/a.rs", false),
+            ("src/B.RS", true),
+            ("Makefile", true),
+        ]);
+
+        assert_eq!(stats.rows(), vec![row(".rs", 3, 2), row(NO_EXTENSION, 1, 1), row(".md", 1, 1)]);
+    }
+
+    #[test]
+    fn test_rows_beyond_top_rolled_up_into_other() {
+        let stats = stats(2, &[
+            ("a.rs", true),
+            ("a.rs", false),
+            ("a.rs", false),
+            ("b.md", true),
+            ("b.md", false),
+            ("c.toml", true),
+            ("d.txt", true),
+            ("e.txt", true),
+        ]);
+
+        assert_eq!(stats.rows(), vec![row(".rs", 3, 1), row(".md", 2, 1), row(OTHER, 3, 3)]);
+    }
+
+    #[test]
+    fn test_write_aligns_columns() {
+        let stats = stats(10, &[("a.rs", true), ("b.toml", true)]);
+        let mut output = Vec::new();
+        stats.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(".rs    1 处匹配, 1 个文件"), "{}", output);
+        assert!(output.contains(".toml  1 处匹配, 1 个文件"), "{}", output);
+
+        let mut empty = Vec::new();
+        ExtensionStats::new(10).write(&mut empty).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::domain::search::SearchResult;
+
+/// 索引文件的第一行，标识格式版本和各列含义
+pub const INDEX_HEADER: &str = "# FindEverything index v1\tpath\tline\tbyte_offset\tcolumn";
+
+/// 数字列的固定宽度（足以容纳任意 u64），补零后按字节比较与按数值比较一致
+const NUMBER_WIDTH: usize = 20;
+
+/// 匹配位置索引，供其他工具随机访问
+///
+/// 格式为 UTF-8 的 TSV，第一行是 [`INDEX_HEADER`]，之后每个匹配一行：
+///
+/// ```text
+/// path<TAB>line<TAB>byte_offset<TAB>column<LF>
+/// ```
+///
+/// - `path`：结果中显示的路径，其中的 `\` 、制表符、换行符和回车符分别转义为 `\\`、`\t`、`\n`、`\r`
+/// - `line`：行号，从 1 开始
+/// - `byte_offset`：第一个匹配在文件中的字节偏移，从 0 开始，不是有效 UTF-8 的文件按解码后的文本计算
+/// - `column`：第一个匹配在行内的字节列，从 1 开始
+///
+/// 数字列都补零到 20 位，因此除第一行外的所有行按字节顺序排列，同时也就是按路径、行号、列排列，
+/// 可以直接在文件上二分查找。
+#[derive(Debug, Default)]
+pub struct MatchIndex {
+    records: Vec<String>,
+}
+
+impl MatchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一个匹配
+    pub fn record(&mut self, result: &SearchResult) {
+        self.records.push(format!(
+            "{}\t{:0width$}\t{:0width$}\t{:0width$}",
+            escape_path(&result.path),
+            result.line_number,
+            result.byte_offset(),
+            result.column() + 1,
+            width = NUMBER_WIDTH
+        ));
+    }
+
+    /// 排序后写入索引文件
+    ///
+    /// 先写入同目录下的临时文件再重命名，读取索引的工具不会看到写了一半的文件。
+    pub fn write_atomic(mut self, path: &Path) -> Result<()> {
+        self.records.sort_unstable();
+        self.records.dedup();
+
+        let mut content = String::with_capacity(self.records.iter().map(|r| r.len() + 1).sum::<usize>() + 64);
+        content.push_str(INDEX_HEADER);
+        content.push('\n');
+        for record in &self.records {
+            content.push_str(record);
+            content.push('\n');
+        }
+
+        let file_name = path.file_name()
+            .with_context(|| format!("无效的索引文件路径: {}", path.display()))?;
+        let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+
+        let written = fs::write(&temp_path, content)
+            .with_context(|| format!("无法写入临时索引文件: {}", temp_path.display()))
+            .and_then(|()| {
+                fs::rename(&temp_path, path).with_context(|| format!("无法写入索引文件: {}", path.display()))
+            });
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        written
+    }
+}
+
+/// 转义路径中会破坏行和列结构的字符
+fn escape_path(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, line_number: u64, line_offset: u64, column: usize) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            line_number,
+            line: String::new(),
+            matched_text: String::new(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![(column, column + 1)],
+            matches_truncated: false,
+            total_lines: 100,
+            line_offset,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    #[test]
+    fn test_index_is_sorted_and_fixed_width() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let index_path = temp_dir.path().join("matches.idx");
+
+        let mut index = MatchIndex::new();
+        index.record(&result("b.rs", 2, 10, 4));
+        index.record(&result("a.rs", 10, 200, 0));
+        index.record(&result("a.rs", 9, 150, 3));
+        index.record(&result("tab\there", 1, 0, 0));
+        index.write_atomic(&index_path).unwrap();
+
+        let content = fs::read_to_string(&index_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], INDEX_HEADER);
+        assert_eq!(
+            lines[1],
+            "a.rs\t00000000000000000009\t00000000000000000153\t00000000000000000004"
+        );
+        assert!(lines[2].starts_with("a.rs\t00000000000000000010\t"));
+        assert!(lines[3].starts_with("b.rs\t"));
+        assert!(lines[4].starts_with("tab\\there\t"));
+        assert!(lines[1..].windows(2).all(|pair| pair[0] < pair[1]));
+
+        // 没有留下临时文件
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::display::SearchSummary;
+use crate::domain::search::SearchResult;
+
+/// `--json` 模式下每行输出的事件
+///
+/// 事件按以下顺序输出，每个事件占一行：
+/// 1. 零个或多个 `match` 事件，每个对应一个匹配行；
+/// 2. 恰好一个 `summary` 事件，即使没有任何匹配也会输出；
+/// 3. 启用 `--json-done` 时，最后输出一个 `done` 事件。
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonEvent<'a> {
+    Match(JsonMatch<'a>),
+    Summary(JsonSummary),
+    Done { matches: u64 },
+}
+
+#[derive(Debug, Serialize)]
+struct JsonMatch<'a> {
+    path: &'a str,
+    line_number: u64,
+    line: &'a str,
+    matched_text: &'a str,
+    /// 第一个匹配在行内的字节范围，反向匹配选中的行没有这两个字段
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    match_end: Option<usize>,
+    /// 行内所有匹配的字节范围
+    matches: Vec<JsonRange>,
+    context_before: &'a [String],
+    context_after: &'a [String],
+    /// 文件不是 UTF-8 时解码所用的编码，此时文本和字节范围都针对解码后的 UTF-8 内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+    /// 只在文件被识别为二进制文件时出现
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_binary: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRange {
+    start: usize,
+    end: usize,
+}
+
+/// 摘要的 JSON 形式，`--json` 的 `summary` 事件和 `--summary-format json` 共用
+#[derive(Debug, Serialize)]
+pub(super) struct JsonSummary {
+    elapsed_ms: u128,
+    files_scanned: u64,
+    files_matched: u64,
+    matches: u64,
+    lines_scanned: u64,
+    truncated_lines: u64,
+    errors: usize,
+    /// 只在启用 --dedup-results 且省略了结果时出现
+    #[serde(skip_serializing_if = "is_zero")]
+    duplicates_suppressed: u64,
+    /// 只在启用 --utf8-only 且跳过了文件时出现
+    #[serde(skip_serializing_if = "is_zero")]
+    non_utf8_skipped: u64,
+    /// 只在指定 --stats 时出现，按匹配数从多到少排列
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extensions: Option<Vec<super::extension_stats::ExtensionRow>>,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn write_event<W: Write>(w: &mut W, event: &JsonEvent) -> Result<()> {
+    serde_json::to_writer(&mut *w, event)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+/// 输出一个 `match` 事件
+pub fn write_json_result<W: Write>(w: &mut W, result: &SearchResult) -> Result<()> {
+    write_event(w, &JsonEvent::Match(JsonMatch {
+        path: &result.path,
+        line_number: result.line_number,
+        line: &result.line,
+        matched_text: &result.matched_text,
+        match_start: result.match_start(),
+        match_end: result.match_end(),
+        matches: result.matches.iter().map(|&(start, end)| JsonRange { start, end }).collect(),
+        context_before: &result.context_before,
+        context_after: &result.context_after,
+        encoding: result.encoding,
+        is_binary: result.is_binary,
+    }))
+}
+
+impl JsonSummary {
+    pub(super) fn new(summary: &SearchSummary, errors: usize) -> Self {
+        Self {
+            elapsed_ms: summary.start_time.elapsed().as_millis(),
+            files_scanned: summary.total_files,
+            files_matched: summary.matched_files,
+            matches: summary.total_matches,
+            lines_scanned: summary.total_lines,
+            truncated_lines: summary.truncated_lines,
+            errors,
+            duplicates_suppressed: summary.duplicates_suppressed,
+            non_utf8_skipped: summary.non_utf8_skipped,
+            extensions: summary.extension_stats.as_ref().map(|stats| stats.rows()),
+        }
+    }
+}
+
+/// 输出 `summary` 事件
+pub fn write_json_summary<W: Write>(w: &mut W, summary: &SearchSummary, errors: usize) -> Result<()> {
+    write_event(w, &JsonEvent::Summary(JsonSummary::new(summary, errors)))
+}
+
+/// 输出结束标记 `{"type":"done","matches":N}`
+pub fn write_json_done<W: Write>(w: &mut W, matches: u64) -> Result<()> {
+    write_event(w, &JsonEvent::Done { matches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn parse_lines(output: &[u8]) -> Vec<Value> {
+        String::from_utf8(output.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_match_event() {
+        let result = SearchResult {
+            path: "src/main.rs".to_string(),
+            line_number: 3,
+            line: "let x = \"needle\";".to_string(),
+            matched_text: "needle".to_string(),
+            context_before: vec!["fn main() {".to_string()],
+            context_after: vec![],
+            matches: vec![(9, 15)],
+            matches_truncated: false,
+            total_lines: 5,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        };
+
+        let mut output = Vec::new();
+        write_json_result(&mut output, &result).unwrap();
+        let events = parse_lines(&output);
+
+        assert_eq!(events[0]["type"], "match");
+        assert_eq!(events[0]["line"], "let x = \"needle\";");
+        assert_eq!(events[0]["matches"][0]["start"], 9);
+        assert_eq!(events[0]["match_start"], 9);
+        assert_eq!(events[0]["match_end"], 15);
+        assert_eq!(events[0]["context_before"][0], "fn main() {");
+        assert!(!String::from_utf8(output).unwrap().contains('\x1b'));
+        // UTF-8 文本文件不输出编码和二进制字段
+        assert!(events[0].get("encoding").is_none());
+        assert!(events[0].get("is_binary").is_none());
+    }
+
+    #[test]
+    fn test_match_event_encoding_and_binary() {
+        let mut result = SearchResult {
+            path: "data.bin".to_string(),
+            line_number: 1,
+            line: "\0needle".to_string(),
+            matched_text: "needle".to_string(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![(1, 7)],
+            matches_truncated: false,
+            total_lines: 1,
+            line_offset: 0,
+            encoding: None,
+            is_binary: true,
+        };
+
+        let mut output = Vec::new();
+        write_json_result(&mut output, &result).unwrap();
+        result.is_binary = false;
+        result.encoding = Some("UTF-16LE");
+        write_json_result(&mut output, &result).unwrap();
+        let events = parse_lines(&output);
+
+        assert_eq!(events[0]["is_binary"], true);
+        assert!(events[0].get("encoding").is_none());
+        assert_eq!(events[1]["encoding"], "UTF-16LE");
+        assert!(events[1].get("is_binary").is_none());
+    }
+
+    #[test]
+    fn test_summary_and_done_without_matches() {
+        let mut summary = SearchSummary::new();
+        summary.total_files = 4;
+
+        let mut output = Vec::new();
+        write_json_summary(&mut output, &summary, 1).unwrap();
+        write_json_done(&mut output, summary.total_matches).unwrap();
+        let events = parse_lines(&output);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["type"], "summary");
+        assert_eq!(events[0]["files_scanned"], 4);
+        assert_eq!(events[0]["matches"], 0);
+        assert_eq!(events[0]["errors"], 1);
+        assert_eq!(events[1], serde_json::json!({"type": "done", "matches": 0}));
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use anyhow::Result;
+
+/// 输出使用的换行符
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`（默认，与之前的输出相同）
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// 输出到文件时使用平台默认换行符（Windows 为 `\r\n`），输出到管道或终端时为 `\n`
+    Auto,
+}
+
+impl FromStr for LineEnding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::Crlf),
+            "auto" => Ok(Self::Auto),
+            other => anyhow::bail!("未知的换行符: {}（可选 lf、crlf、auto）", other),
+        }
+    }
+}
+
+impl LineEnding {
+    /// 按标准输出的去向确定实际使用的换行符，结果只会是 `Lf` 或 `Crlf`
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto if cfg!(windows) && stdout_is_file() => Self::Crlf,
+            Self::Auto => Self::Lf,
+            other => other,
+        }
+    }
+}
+
+/// 标准输出是否被重定向到普通文件（而不是管道或终端）
+fn stdout_is_file() -> bool {
+    use std::mem::ManuallyDrop;
+
+    // 借用标准输出的句柄查询文件类型，ManuallyDrop 保证不会关闭它
+    #[cfg(unix)]
+    let file = {
+        use std::os::unix::io::{AsRawFd, FromRawFd};
+        // SAFETY: 句柄在进程生命周期内有效，且不会被关闭
+        ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(io::stdout().as_raw_fd()) })
+    };
+    #[cfg(windows)]
+    let file = {
+        use std::os::windows::io::{AsRawHandle, FromRawHandle};
+        // SAFETY: 句柄在进程生命周期内有效，且不会被关闭
+        ManuallyDrop::new(unsafe { std::fs::File::from_raw_handle(io::stdout().as_raw_handle()) })
+    };
+
+    file.metadata().is_ok_and(|metadata| metadata.is_file())
+}
+
+/// 按指定换行符输出的写入器：`Crlf` 时把单独的 `\n` 转换为 `\r\n`，已有的 `\r\n` 保持不变
+pub struct LineEndingWriter<W: Write> {
+    inner: W,
+    crlf: bool,
+    last_byte: Option<u8>,
+}
+
+impl<W: Write> LineEndingWriter<W> {
+    /// `line_ending` 为 `Auto` 时按标准输出的去向确定
+    pub fn new(inner: W, line_ending: LineEnding) -> Self {
+        Self {
+            inner,
+            crlf: line_ending.resolve() == LineEnding::Crlf,
+            last_byte: None,
+        }
+    }
+}
+
+impl<W: Write> Write for LineEndingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.crlf {
+            return self.inner.write(buf);
+        }
+
+        let mut start = 0;
+        for newline in memchr::memchr_iter(b'\n', buf) {
+            let previous = if newline > 0 { Some(buf[newline - 1]) } else { self.last_byte };
+            if previous != Some(b'\r') {
+                self.inner.write_all(&buf[start..newline])?;
+                self.inner.write_all(b"\r")?;
+                start = newline;
+            }
+        }
+        self.inner.write_all(&buf[start..])?;
+        if let Some(&last) = buf.last() {
+            self.last_byte = Some(last);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_with(line_ending: LineEnding, chunks: &[&str]) -> String {
+        let mut writer = LineEndingWriter::new(Vec::new(), line_ending);
+        for chunk in chunks {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        String::from_utf8(writer.inner).unwrap()
+    }
+
+    #[test]
+    fn test_parse_line_ending() {
+        assert_eq!("LF".parse::<LineEnding>().unwrap(), LineEnding::Lf);
+        assert_eq!("crlf".parse::<LineEnding>().unwrap(), LineEnding::Crlf);
+        assert_eq!("auto".parse::<LineEnding>().unwrap(), LineEnding::Auto);
+        assert!("cr".parse::<LineEnding>().is_err());
+    }
+
+    #[test]
+    fn test_crlf_translation() {
+        assert_eq!(write_with(LineEnding::Crlf, &["a:1\n", "b\n\nc"]), "a:1\r\nb\r\n\r\nc");
+        // 已有的 \r\n 不会变成 \r\r\n，跨越两次写入时也一样
+        assert_eq!(write_with(LineEnding::Crlf, &["x\r\n", "y\r", "\n"]), "x\r\ny\r\n");
+        assert_eq!(write_with(LineEnding::Lf, &["a\nb\n"]), "a\nb\n");
+    }
+}
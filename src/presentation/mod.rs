@@ -1,3 +1,23 @@
 pub mod display;
+pub mod template;
+pub mod sample;
+pub mod json;
+pub mod summary;
+pub mod pacing;
+pub mod sort;
+pub mod line_ending;
+pub mod extension_stats;
+pub mod index;
+pub mod sink;
 
-pub use display::{SearchSummary, print_search_result};
+pub use display::{SearchSummary, GroupedPrinter, LocationPrinter, OutputFormat, ColorChoice, print_search_result, write_search_result};
+pub use template::OutputTemplate;
+pub use sample::diverse_sample;
+pub use json::{write_json_result, write_json_summary, write_json_done};
+pub use summary::{SummaryFormat, write_summary};
+pub use pacing::OutputPacer;
+pub use sort::{SortOrder, sort_results};
+pub use line_ending::{LineEnding, LineEndingWriter};
+pub use extension_stats::ExtensionStats;
+pub use index::MatchIndex;
+pub use sink::{ResultSink, TextSink, LocationSink, FileListSink, CountSink, TemplateSink, ReplaceSink, JsonSink, Annotator};
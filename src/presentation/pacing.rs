@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+/// 按固定速率输出结果，在两次输出之间按需休眠
+///
+/// 只影响结果的输出速度，与 CPU 限流无关；搜索线程照常运行并填充结果通道，
+/// 通道满后才会被阻塞。
+#[derive(Debug)]
+pub struct OutputPacer {
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl OutputPacer {
+    /// 每秒最多输出 `per_second` 个结果，`per_second` 为 0 时不限速
+    pub fn new(per_second: u32) -> Option<Self> {
+        (per_second > 0).then(|| Self {
+            interval: Duration::from_secs(1) / per_second,
+            next: None,
+        })
+    }
+
+    /// 等到可以输出下一个结果为止
+    ///
+    /// 空闲（如等待搜索结果）的时间不会累积成额度，恢复输出后仍按原速率进行。
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        let start = match self.next {
+            Some(next) if next > now => {
+                std::thread::sleep(next - now);
+                next
+            }
+            _ => now,
+        };
+        self.next = Some(start + self.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_rate_disables_pacing() {
+        assert!(OutputPacer::new(0).is_none());
+    }
+
+    #[test]
+    fn test_pacer_limits_rate() {
+        let mut pacer = OutputPacer::new(100).unwrap();
+        let started = Instant::now();
+        for _ in 0..6 {
+            pacer.wait();
+        }
+        // 第一个立即输出，之后每个间隔 10ms
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_idle_time_does_not_build_up_burst() {
+        let mut pacer = OutputPacer::new(50).unwrap();
+        pacer.wait();
+        std::thread::sleep(Duration::from_millis(60));
+
+        let started = Instant::now();
+        pacer.wait();
+        pacer.wait();
+        assert!(started.elapsed() >= Duration::from_millis(15));
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::domain::search::SearchResult;
+
+/// 从结果中按文件扩展名轮流抽取最多 `limit` 条，使输出覆盖尽可能多的文件类型
+///
+/// 每种扩展名内部按到达顺序抽取，扩展名之间按首次出现的顺序轮转。结果不超过 `limit` 条时原样返回。
+/// 返回的结果按路径和行号排序，便于分组输出。
+pub fn diverse_sample(results: Vec<SearchResult>, limit: usize) -> Vec<SearchResult> {
+    if results.len() <= limit {
+        return results;
+    }
+
+    // 按扩展名分组，保持首次出现的顺序
+    let mut buckets: Vec<Vec<SearchResult>> = Vec::new();
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    for result in results {
+        let extension = Path::new(&result.path)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let index = *group_index.entry(extension).or_insert_with(|| {
+            buckets.push(Vec::new());
+            buckets.len() - 1
+        });
+        buckets[index].push(result);
+    }
+    let mut groups: Vec<_> = buckets.into_iter().map(Vec::into_iter).collect();
+
+    let mut sample = Vec::with_capacity(limit);
+    while sample.len() < limit {
+        for group in groups.iter_mut() {
+            if sample.len() == limit {
+                break;
+            }
+            if let Some(result) = group.next() {
+                sample.push(result);
+            }
+        }
+    }
+
+    sample.sort();
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, line_number: u64) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            line_number,
+            line: String::new(),
+            matched_text: String::new(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![],
+            matches_truncated: false,
+            total_lines: 100,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    fn picked(sample: &[SearchResult]) -> Vec<(&str, u64)> {
+        sample.iter().map(|r| (r.path.as_str(), r.line_number)).collect()
+    }
+
+    #[test]
+    fn test_round_robin_across_extensions() {
+        let results = vec![
+            result("a.rs", 1),
+            result("a.rs", 2),
+            result("b.rs", 1),
+            result("c.md", 1),
+            result("d.RS", 1),
+            result("Makefile", 1),
+            result("e.md", 1),
+        ];
+
+        let sample = diverse_sample(results, 4);
+        assert_eq!(
+            picked(&sample),
+            vec![("Makefile", 1), ("a.rs", 1), ("a.rs", 2), ("c.md", 1)]
+        );
+    }
+
+    #[test]
+    fn test_fewer_results_than_limit() {
+        let sample = diverse_sample(vec![result("b.rs", 3), result("a.rs", 1)], 5);
+        assert_eq!(picked(&sample), vec![("b.rs", 3), ("a.rs", 1)]);
+    }
+}
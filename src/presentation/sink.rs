@@ -0,0 +1,361 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+
+use super::display::{GroupedPrinter, LocationPrinter};
+use super::json::write_json_result;
+use super::template::OutputTemplate;
+use crate::domain::replace::Replacement;
+use crate::domain::search::SearchResult;
+
+/// 为匹配行生成附加注释（如 git blame），返回 None 时不输出注释
+pub type Annotator = Box<dyn FnMut(&SearchResult) -> Option<String> + Send>;
+
+/// 搜索结果的输出目标
+///
+/// 消费线程对每个文件的一批结果依次调用 `begin_file`、`skips`/`write`，结束时调用 `finish`，
+/// 不关心具体格式；新增输出格式只需要新的实现。
+pub trait ResultSink {
+    /// 开始输出同一文件的一批结果，`results` 为这一批的全部结果
+    fn begin_file(&mut self, _results: &[SearchResult]) -> Result<()> {
+        Ok(())
+    }
+
+    /// 该结果是否不会产生任何输出（这样的结果不占用输出速率）
+    fn skips(&self, _result: &SearchResult) -> bool {
+        false
+    }
+
+    /// 输出一个结果
+    fn write(&mut self, result: &SearchResult) -> Result<()>;
+
+    /// 全部结果输出完毕，刷新缓冲的内容
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 彩色文本输出，可选文件头部和匹配行注释
+pub struct TextSink<W: Write> {
+    writer: W,
+    printer: GroupedPrinter,
+    file_header: bool,
+    annotator: Option<Annotator>,
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(writer: W, printer: GroupedPrinter) -> Self {
+        Self {
+            writer,
+            printer,
+            file_header: false,
+            annotator: None,
+        }
+    }
+
+    /// 在每个文件的结果之前输出 `路径 (N 行, M 处匹配)` 头部
+    pub fn with_file_header(mut self, file_header: bool) -> Self {
+        self.file_header = file_header;
+        self
+    }
+
+    /// 在每个匹配行之后输出注释
+    pub fn with_annotator(mut self, annotator: Option<Annotator>) -> Self {
+        self.annotator = annotator;
+        self
+    }
+}
+
+impl<W: Write> ResultSink for TextSink<W> {
+    fn begin_file(&mut self, results: &[SearchResult]) -> Result<()> {
+        if self.file_header {
+            self.printer.write_file_header(&mut self.writer, results)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        let annotation = self.annotator.as_mut().and_then(|annotate| annotate(result));
+        self.printer.write_annotated(&mut self.writer, result, annotation.as_deref())
+    }
+}
+
+/// `--locations` 输出：每个匹配一行 `路径:行号`，连续的相同位置只输出一次
+pub struct LocationSink<W: Write> {
+    writer: W,
+    printer: LocationPrinter,
+}
+
+impl<W: Write> LocationSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            printer: LocationPrinter::new(),
+        }
+    }
+}
+
+impl<W: Write> ResultSink for LocationSink<W> {
+    fn skips(&self, result: &SearchResult) -> bool {
+        self.printer.is_duplicate(result)
+    }
+
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        self.printer.write(&mut self.writer, result)
+    }
+}
+
+/// `-l/--files-with-matches` 输出：只收集包含匹配的文件路径，结束时按路径排序，每个路径输出一行
+pub struct FileListSink<W: Write> {
+    writer: W,
+    paths: BTreeSet<String>,
+}
+
+impl<W: Write> FileListSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            paths: BTreeSet::new(),
+        }
+    }
+}
+
+impl<W: Write> ResultSink for FileListSink<W> {
+    fn skips(&self, result: &SearchResult) -> bool {
+        self.paths.contains(&result.path)
+    }
+
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        if !self.paths.contains(&result.path) {
+            self.paths.insert(result.path.clone());
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        for path in &self.paths {
+            writeln!(self.writer, "{}", path)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// `-c/--count` 输出：统计每个文件的匹配行数，结束时按路径排序，每个文件输出一行 `路径:行数`
+pub struct CountSink<W: Write> {
+    writer: W,
+    counts: HashMap<String, u64>,
+}
+
+impl<W: Write> CountSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<W: Write> ResultSink for CountSink<W> {
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        *self.counts.entry(result.path.clone()).or_insert(0) += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let mut counts: Vec<_> = self.counts.iter().collect();
+        counts.sort_unstable();
+        for (path, count) in counts {
+            writeln!(self.writer, "{}:{}", path, count)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// `--output-template` 输出：每个匹配按模板输出一行
+pub struct TemplateSink<W: Write> {
+    writer: W,
+    template: OutputTemplate,
+}
+
+impl<W: Write> TemplateSink<W> {
+    pub fn new(writer: W, template: OutputTemplate) -> Self {
+        Self { writer, template }
+    }
+}
+
+impl<W: Write> ResultSink for TemplateSink<W> {
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        writeln!(self.writer, "{}", self.template.render(result))?;
+        Ok(())
+    }
+}
+
+/// `--replace` 的文件模式：以差异形式预览每个匹配行替换前后的内容
+pub struct ReplaceSink<W: Write> {
+    writer: W,
+    printer: GroupedPrinter,
+    replacement: Replacement,
+}
+
+impl<W: Write> ReplaceSink<W> {
+    pub fn new(writer: W, printer: GroupedPrinter, replacement: Replacement) -> Self {
+        Self { writer, printer, replacement }
+    }
+}
+
+impl<W: Write> ResultSink for ReplaceSink<W> {
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        let replaced = self.replacement.replace_all(&result.line);
+        self.printer.write_replacement(&mut self.writer, result, &replaced)
+    }
+}
+
+/// JSON 输出：每个匹配一行 `match` 事件
+///
+/// `json` 按块缓冲，吞吐量高；`ndjson` 每个匹配输出后立即刷新，适合边搜索边消费结果。
+pub struct JsonSink<W: Write> {
+    writer: BufWriter<W>,
+    flush_each: bool,
+}
+
+impl<W: Write> JsonSink<W> {
+    /// 缓冲输出，结束时刷新
+    pub fn json(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            flush_each: false,
+        }
+    }
+
+    /// 每个匹配输出后立即刷新
+    pub fn ndjson(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            flush_each: true,
+        }
+    }
+}
+
+impl<W: Write> ResultSink for JsonSink<W> {
+    fn write(&mut self, result: &SearchResult) -> Result<()> {
+        write_json_result(&mut self.writer, result)?;
+        if self.flush_each {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn result(path: &str, line_number: u64) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            line_number,
+            line: format!("needle {}", line_number),
+            matched_text: "needle".to_string(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![(0, 6)],
+            matches_truncated: false,
+            total_lines: 10,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    /// 记录每次 flush 时已写入内容的写入器
+    #[derive(Clone, Default)]
+    struct FlushLog {
+        written: Arc<Mutex<Vec<u8>>>,
+        flushed_at: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Write for FlushLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            let len = self.written.lock().unwrap().len();
+            self.flushed_at.lock().unwrap().push(len);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_ndjson_flushes_each_line() {
+        let log = FlushLog::default();
+        let mut sink = JsonSink::ndjson(log.clone());
+        for line_number in 1..=3 {
+            sink.write(&result("a.txt", line_number)).unwrap();
+            // 每个匹配之后输出的都是完整的行
+            let written = log.written.lock().unwrap().clone();
+            assert!(written.ends_with(b"\n"));
+            assert_eq!(written.iter().filter(|&&b| b == b'\n').count(), line_number as usize);
+        }
+
+        let written = String::from_utf8(log.written.lock().unwrap().clone()).unwrap();
+        for (index, line) in written.lines().enumerate() {
+            let event: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(event["type"], "match");
+            assert_eq!(event["line_number"], index as u64 + 1);
+        }
+        assert_eq!(log.flushed_at.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_location_sink_skips_repeated_locations() {
+        let mut output = Vec::new();
+        {
+            let mut sink = LocationSink::new(&mut output);
+            for result in [result("a.txt", 1), result("a.txt", 1), result("a.txt", 2)] {
+                if !sink.skips(&result) {
+                    sink.write(&result).unwrap();
+                }
+            }
+            assert!(sink.skips(&result("a.txt", 2)));
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt:1\na.txt:2\n");
+    }
+
+    #[test]
+    fn test_count_sink_counts_matching_lines_per_file() {
+        let mut output = Vec::new();
+        {
+            let mut sink = CountSink::new(&mut output);
+            for result in [result("b.txt", 1), result("a.txt", 3), result("b.txt", 7), result("b.txt", 9)] {
+                sink.write(&result).unwrap();
+            }
+            sink.finish().unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt:1\nb.txt:3\n");
+    }
+
+    #[test]
+    fn test_file_list_sink_prints_each_path_once_at_finish() {
+        let mut output = Vec::new();
+        {
+            let mut sink = FileListSink::new(&mut output);
+            for result in [result("b.txt", 1), result("a.txt", 3), result("b.txt", 7)] {
+                sink.write(&result).unwrap();
+            }
+            assert!(sink.skips(&result("a.txt", 9)));
+            sink.finish().unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "a.txt\nb.txt\n");
+    }
+}
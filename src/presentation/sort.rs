@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use crate::domain::search::SearchResult;
+
+/// 结果的输出顺序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// 按路径、行号和列（`SearchResult` 的默认顺序）
+    Path,
+    /// 按文件的匹配数从多到少，同一文件的结果按行号排列
+    Count,
+}
+
+impl FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "path" => Ok(Self::Path),
+            "count" => Ok(Self::Count),
+            other => anyhow::bail!("未知的排序方式: {}（可选 path、count）", other),
+        }
+    }
+}
+
+/// 按指定顺序排列全部结果，同一文件的结果总是相邻，便于分组输出
+pub fn sort_results(mut results: Vec<SearchResult>, order: SortOrder) -> Vec<SearchResult> {
+    match order {
+        SortOrder::Path => {
+            results.sort();
+        }
+        SortOrder::Count => {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for result in &results {
+                *counts.entry(result.path.clone()).or_default() += 1;
+            }
+            // 匹配数相同的文件按结果的默认顺序（路径、行号、列）排列，保证输出稳定
+            results.sort_by(|a, b| counts[&b.path].cmp(&counts[&a.path]).then_with(|| a.cmp(b)));
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, line_number: u64) -> SearchResult {
+        SearchResult {
+            path: path.to_string(),
+            line_number,
+            line: String::new(),
+            matched_text: String::new(),
+            context_before: vec![],
+            context_after: vec![],
+            matches: vec![],
+            matches_truncated: false,
+            total_lines: 100,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    fn order(results: &[SearchResult]) -> Vec<(&str, u64)> {
+        results.iter().map(|r| (r.path.as_str(), r.line_number)).collect()
+    }
+
+    #[test]
+    fn test_parse_sort_order() {
+        assert_eq!("path".parse::<SortOrder>().unwrap(), SortOrder::Path);
+        assert_eq!("Count".parse::<SortOrder>().unwrap(), SortOrder::Count);
+        assert!("size".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn test_sort_by_count_puts_noisiest_file_first() {
+        // 结果按到达顺序交错
+        let results = vec![
+            result("one.txt", 4),
+            result("three.txt", 9),
+            result("two.txt", 2),
+            result("three.txt", 1),
+            result("two.txt", 7),
+            result("three.txt", 5),
+        ];
+
+        let sorted = sort_results(results, SortOrder::Count);
+        assert_eq!(
+            order(&sorted),
+            vec![
+                ("three.txt", 1),
+                ("three.txt", 5),
+                ("three.txt", 9),
+                ("two.txt", 2),
+                ("two.txt", 7),
+                ("one.txt", 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_path() {
+        let results = vec![result("b.txt", 1), result("a.txt", 9), result("a.txt", 3)];
+        let sorted = sort_results(results, SortOrder::Path);
+        assert_eq!(order(&sorted), vec![("a.txt", 3), ("a.txt", 9), ("b.txt", 1)]);
+    }
+}
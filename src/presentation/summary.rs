@@ -0,0 +1,223 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use super::display::SearchSummary;
+use super::json::JsonSummary;
+use crate::util::format_duration;
+
+/// 搜索摘要的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// 每项一行的纯文本（默认）
+    #[default]
+    Plain,
+    /// 列对齐的表格
+    Table,
+    /// 单个 JSON 对象，占一行
+    Json,
+}
+
+impl FromStr for SummaryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            other => anyhow::bail!("未知的摘要格式: {}（可选 plain、table、json）", other),
+        }
+    }
+}
+
+/// 按指定格式输出搜索摘要
+///
+/// `errors` 只出现在 JSON 格式中，文本格式的错误统计由错误日志单独输出。
+pub fn write_summary<W: Write>(w: &mut W, summary: &SearchSummary, format: SummaryFormat, errors: usize) -> Result<()> {
+    match format {
+        SummaryFormat::Plain => {
+            writeln!(w, "\n搜索摘要:")?;
+            writeln!(w, "----------------------------")?;
+            for (label, value) in summary_rows(summary) {
+                writeln!(w, "{}: {}", label, value)?;
+            }
+            if let Some(stats) = &summary.extension_stats {
+                stats.write(w)?;
+            }
+        }
+        SummaryFormat::Table => {
+            write_table(w, &summary_rows(summary))?;
+            if let Some(stats) = &summary.extension_stats {
+                stats.write(w)?;
+            }
+        }
+        SummaryFormat::Json => {
+            serde_json::to_writer(&mut *w, &JsonSummary::new(summary, errors))?;
+            writeln!(w)?;
+        }
+    }
+    Ok(())
+}
+
+/// 文本格式摘要的各项，没有意义的项（如未截断任何行）不输出
+fn summary_rows(summary: &SearchSummary) -> Vec<(&'static str, String)> {
+    let duration = summary.start_time.elapsed();
+    let mut rows = vec![
+        ("总用时", format_duration(duration)),
+        ("扫描文件", summary.total_files.to_string()),
+    ];
+
+    if summary.total_lines > 0 {
+        let seconds = duration.as_secs_f64();
+        let value = if seconds > 0.0 {
+            format!("{} ({:.0} 行/秒)", summary.total_lines, summary.total_lines as f64 / seconds)
+        } else {
+            summary.total_lines.to_string()
+        };
+        rows.push(("扫描行数", value));
+    }
+    rows.push(("匹配文件", summary.matched_files.to_string()));
+    rows.push(("匹配项数", summary.total_matches.to_string()));
+    if summary.truncated_lines > 0 {
+        rows.push(("截断行数", format!("{} (单行匹配数超过上限)", summary.truncated_lines)));
+    }
+    if let Some(peak) = summary.peak_concurrent_large_reads {
+        rows.push(("大文件并发读取峰值", peak.to_string()));
+    }
+    if summary.non_utf8_skipped > 0 {
+        rows.push(("跳过非 UTF-8 文件", summary.non_utf8_skipped.to_string()));
+    }
+    if summary.duplicates_suppressed > 0 {
+        rows.push(("省略重复", format!("{} (与已输出的结果相同)", summary.duplicates_suppressed)));
+    }
+
+    rows
+}
+
+fn write_table<W: Write>(w: &mut W, rows: &[(&str, String)]) -> Result<()> {
+    const HEADER: (&str, &str) = ("搜索摘要", "");
+    let label_width = rows.iter().map(|(label, _)| display_width(label)).chain([display_width(HEADER.0)]).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, value)| display_width(value)).max().unwrap_or(0);
+    let border = format!("+-{}-+-{}-+", "-".repeat(label_width), "-".repeat(value_width));
+
+    writeln!(w)?;
+    writeln!(w, "{}", border)?;
+    writeln!(w, "| {} | {} |", pad(HEADER.0, label_width), pad(HEADER.1, value_width))?;
+    writeln!(w, "{}", border)?;
+    for (label, value) in rows {
+        // 数值右对齐
+        writeln!(w, "| {} | {}{} |", pad(label, label_width), " ".repeat(value_width - display_width(value)), value)?;
+    }
+    writeln!(w, "{}", border)?;
+    Ok(())
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width - display_width(text)))
+}
+
+/// 终端中的显示宽度：中日韩文字等全角字符占两列
+pub(super) fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn sample_summary() -> SearchSummary {
+        let mut summary = SearchSummary::new();
+        summary.total_files = 12;
+        summary.matched_files = 3;
+        summary.total_matches = 7;
+        summary.total_lines = 400;
+        summary
+    }
+
+    fn render(format: SummaryFormat, errors: usize) -> String {
+        let mut output = Vec::new();
+        write_summary(&mut output, &sample_summary(), format, errors).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_summary_format() {
+        assert_eq!("plain".parse::<SummaryFormat>().unwrap(), SummaryFormat::Plain);
+        assert_eq!("TABLE".parse::<SummaryFormat>().unwrap(), SummaryFormat::Table);
+        assert_eq!("json".parse::<SummaryFormat>().unwrap(), SummaryFormat::Json);
+        assert!("yaml".parse::<SummaryFormat>().is_err());
+        assert_eq!(SummaryFormat::default(), SummaryFormat::Plain);
+    }
+
+    #[test]
+    fn test_json_summary_shape() {
+        let output = render(SummaryFormat::Json, 2);
+        assert_eq!(output.lines().count(), 1);
+
+        let value: Value = serde_json::from_str(&output).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            ["elapsed_ms", "errors", "files_matched", "files_scanned", "lines_scanned", "matches", "truncated_lines"]
+        );
+        assert_eq!(value["files_scanned"], 12);
+        assert_eq!(value["files_matched"], 3);
+        assert_eq!(value["matches"], 7);
+        assert_eq!(value["lines_scanned"], 400);
+        assert_eq!(value["errors"], 2);
+        // 与 --json 的事件不同，没有 type 字段
+        assert!(value.get("type").is_none());
+    }
+
+    #[test]
+    fn test_extension_breakdown() {
+        let mut summary = sample_summary();
+        let mut stats = super::super::ExtensionStats::new(10);
+        stats.record("src/main.rs", true);
+        stats.record("src/main.rs", false);
+        stats.record("README.md", true);
+        summary.extension_stats = Some(stats);
+
+        let mut output = Vec::new();
+        write_summary(&mut output, &summary, SummaryFormat::Json, 0).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value["extensions"][0]["extension"], ".rs");
+        assert_eq!(value["extensions"][0]["matches"], 2);
+        assert_eq!(value["extensions"][1]["files"], 1);
+
+        let mut output = Vec::new();
+        write_summary(&mut output, &summary, SummaryFormat::Plain, 0).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("按扩展名统计:"));
+    }
+
+    #[test]
+    fn test_plain_and_table_summary() {
+        let plain = render(SummaryFormat::Plain, 0);
+        assert!(plain.contains("搜索摘要:"));
+        assert!(plain.contains("匹配项数: 7"));
+        assert!(!plain.contains("截断行数"));
+        assert!(!plain.contains("省略重复"));
+
+        let mut summary = sample_summary();
+        summary.duplicates_suppressed = 4;
+        let mut output = Vec::new();
+        write_summary(&mut output, &summary, SummaryFormat::Plain, 0).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("省略重复: 4"));
+
+        let table = render(SummaryFormat::Table, 0);
+        let lines: Vec<&str> = table.lines().filter(|line| !line.is_empty()).collect();
+        // 所有行的显示宽度一致
+        let width = display_width(lines[0]);
+        assert!(lines.iter().all(|line| display_width(line) == width), "{}", table);
+        assert!(lines.iter().any(|line| line.starts_with("| 匹配项数") && line.ends_with(" 7 |")));
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt::Write as _;
+
+use anyhow::Result;
+
+use crate::domain::search::SearchResult;
+
+/// 模板中的占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateField {
+    /// `{path}` 文件路径
+    Path,
+    /// `{line}` 行号（1 基）
+    Line,
+    /// `{col}` 第一个匹配的起始列（1 基，按字节计算）
+    Column,
+    /// `{match}` 第一个匹配的文本
+    Match,
+    /// `{text}` 匹配行的完整内容
+    Text,
+    /// `{before}` 匹配行之前的上下文行，以换行符连接
+    Before,
+    /// `{after}` 匹配行之后的上下文行，以换行符连接
+    After,
+}
+
+impl TemplateField {
+    const ALL: [(&'static str, TemplateField); 7] = [
+        ("path", TemplateField::Path),
+        ("line", TemplateField::Line),
+        ("col", TemplateField::Column),
+        ("match", TemplateField::Match),
+        ("text", TemplateField::Text),
+        ("before", TemplateField::Before),
+        ("after", TemplateField::After),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|(n, _)| *n == name).map(|(_, field)| *field)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(TemplateField),
+}
+
+/// 自定义输出模板，如 `{path}:{line}:{col}: {match}`
+///
+/// 支持的占位符见 [`TemplateField`]，`{{` 和 `}}` 分别输出字面量 `{` 和 `}`。
+/// 模板在启动时解析一次，未知占位符直接报错。
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    segments: Vec<Segment>,
+}
+
+impl OutputTemplate {
+    /// 解析模板字符串
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => anyhow::bail!("输出模板中的 {{{} 缺少右花括号", name),
+                        }
+                    }
+
+                    let field = TemplateField::from_name(&name).ok_or_else(|| {
+                        let available = TemplateField::ALL
+                            .iter()
+                            .map(|(n, _)| format!("{{{}}}", n))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        anyhow::anyhow!("未知的模板占位符: {{{}}}，可用的占位符: {}", name, available)
+                    })?;
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => anyhow::bail!("输出模板中有多余的右花括号，字面量请使用 }}}}"),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// 按模板格式化一个搜索结果（不含结尾换行）
+    pub fn render(&self, result: &SearchResult) -> String {
+        let mut output = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => output.push_str(text),
+                Segment::Field(field) => {
+                    let _ = match field {
+                        TemplateField::Path => write!(output, "{}", result.path),
+                        TemplateField::Line => write!(output, "{}", result.line_number),
+                        TemplateField::Column => {
+                            let start = result.matches.first().map_or(0, |m| m.0);
+                            write!(output, "{}", start + 1)
+                        }
+                        TemplateField::Match => write!(output, "{}", result.matched_text),
+                        TemplateField::Text => write!(output, "{}", result.line),
+                        TemplateField::Before => write!(output, "{}", result.context_before.join("\n")),
+                        TemplateField::After => write!(output, "{}", result.context_after.join("\n")),
+                    };
+                }
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> SearchResult {
+        SearchResult {
+            path: "src/main.rs".to_string(),
+            line_number: 12,
+            line: "    let value = 42;".to_string(),
+            matched_text: "value".to_string(),
+            context_before: vec!["fn main() {".to_string()],
+            context_after: vec!["}".to_string()],
+            matches: vec![(8, 13)],
+            matches_truncated: false,
+            total_lines: 20,
+            line_offset: 0,
+            encoding: None,
+            is_binary: false,
+        }
+    }
+
+    #[test]
+    fn test_vimgrep_like_template() {
+        let template = OutputTemplate::parse("{path}:{line}:{col}: {match}").unwrap();
+        assert_eq!(template.render(&sample_result()), "src/main.rs:12:9: value");
+    }
+
+    #[test]
+    fn test_context_and_escaped_braces() {
+        let template = OutputTemplate::parse("{{{line}}} {before}|{text}|{after}").unwrap();
+        assert_eq!(
+            template.render(&sample_result()),
+            "{12} fn main() {|    let value = 42;|}"
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        let err = OutputTemplate::parse("{path}:{column}").unwrap_err();
+        assert!(err.to_string().contains("{column}"));
+        assert!(OutputTemplate::parse("{path").is_err());
+        assert!(OutputTemplate::parse("path}").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! 命令行参数解析和输出格式化共用的小工具函数
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+const SIZE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+/// 解析文件大小字符串为字节数
+///
+/// 支持 `K`、`M`、`G`、`T` 后缀（按 1024 进位，不区分大小写，可带 `B`，如 `1.5KB`），
+/// 没有后缀或只有 `B` 时按字节计算。
+pub fn parse_size(size_str: &str) -> Result<u64> {
+    let size_str = size_str.trim().to_lowercase();
+    let without_b = size_str.strip_suffix('b').unwrap_or(&size_str);
+
+    let (numeric_part, multiplier) = match without_b.char_indices().last() {
+        Some((i, unit @ ('k' | 'm' | 'g' | 't'))) => {
+            let power = "kmgt".find(unit).unwrap() as i32 + 1;
+            (&without_b[..i], 1024f64.powi(power))
+        }
+        _ => (without_b, 1.0),
+    };
+
+    let value = numeric_part
+        .trim()
+        .parse::<f64>()
+        .with_context(|| format!("无效的大小值: {}", size_str))?;
+    if !value.is_finite() || value < 0.0 {
+        anyhow::bail!("无效的大小值: {}", size_str);
+    }
+
+    Ok((value * multiplier) as u64)
+}
+
+/// 格式化文件大小，如 `512 B`、`1.5 KB`、`2.0 GB`
+pub fn format_file_size(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, SIZE_UNITS[unit])
+    }
+}
+
+/// 格式化持续时间
+pub fn format_duration(duration: Duration) -> String {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / 3600;
+    let mins = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, mins, secs)
+    } else if mins > 0 {
+        format!("{}m {}s", mins, secs)
+    } else {
+        format!("{}.{:03}s", secs, duration.subsec_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("100b").unwrap(), 100);
+        assert_eq!(parse_size("1K").unwrap(), 1024);
+        assert_eq!(parse_size("1kb").unwrap(), 1024);
+        assert_eq!(parse_size("1.5KB").unwrap(), 1536);
+        assert_eq!(parse_size("2M").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_size(" 1 G ").unwrap(), 1024 * 1024 * 1024);
+        assert_eq!(parse_size("1t").unwrap(), 1024u64.pow(4));
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("1X").is_err());
+        assert!(parse_size("-1K").is_err());
+    }
+
+    #[test]
+    fn test_format_file_size() {
+        assert_eq!(format_file_size(0), "0 B");
+        assert_eq!(format_file_size(1023), "1023 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+        assert_eq!(format_file_size(parse_size("2T").unwrap()), "2.0 TB");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(1234)), "1.234s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1h 2m 5s");
+    }
+}
@@ -0,0 +1,561 @@
+//! 命令行端到端测试：运行编译好的可执行文件
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use tempfile::tempdir;
+
+/// 在 `dir` 中运行可执行文件，参数前自动加上 `--no-config`，不受默认配置文件影响
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_FindEverything"))
+        .arg("--no-config")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+/// 同 [`run`]，要求成功退出，返回标准输出
+fn run_ok(dir: &Path, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// 同 [`run`]，`input` 写入标准输入
+fn run_with_stdin(dir: &Path, args: &[&str], input: &[u8]) -> Output {
+    use std::io::Write;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_FindEverything"))
+        .arg("--no-config")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn no_config_neither_reads_nor_creates_config_file() {
+    // 默认配置文件与程序同级，复制到临时目录后才能检查它是否被创建
+    let bin_dir = tempdir().unwrap();
+    let source = Path::new(env!("CARGO_BIN_EXE_FindEverything"));
+    let binary = bin_dir.path().join(source.file_name().unwrap());
+    fs::copy(source, &binary).unwrap();
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "needle\n").unwrap();
+
+    let run_isolated = || {
+        Command::new(&binary)
+            .args(["--no-config", "needle", "notes.txt"])
+            .current_dir(work_dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = run_isolated();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("needle"));
+    assert!(!bin_dir.path().join("config.toml").exists());
+
+    // 存在无效的配置文件时也不受影响
+    fs::write(bin_dir.path().join("config.toml"), "not = [valid").unwrap();
+    let output = run_isolated();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn skip_starts_output_after_the_first_n_matches() {
+    let work_dir = tempdir().unwrap();
+    let content: String = (1..=6).map(|i| format!("needle {}\n", i)).collect();
+    fs::write(work_dir.path().join("notes.txt"), content).unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--locations", "--skip", "4", "needle", "notes.txt"]);
+    assert_eq!(stdout, "notes.txt:5\nnotes.txt:6\n");
+}
+
+#[test]
+fn name_filter_limits_content_search_to_matching_file_names() {
+    let work_dir = tempdir().unwrap();
+    fs::create_dir(work_dir.path().join("src.rs.d")).unwrap();
+    fs::write(work_dir.path().join("main.rs"), "// TODO: rust\n").unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "TODO: text\n").unwrap();
+    // 目录名匹配不算，只看文件名
+    fs::write(work_dir.path().join("src.rs.d").join("readme.md"), "TODO: markdown\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--locations", "--name-filter", r"\.rs$", "TODO", "."]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", stdout);
+    assert!(lines[0].ends_with("main.rs:1"));
+}
+
+#[test]
+fn files_from_searches_listed_files_and_logs_missing_ones() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("a.txt"), "needle a\n").unwrap();
+    fs::write(work_dir.path().join("b.txt"), "needle b\n").unwrap();
+    // 不在列表中的文件不搜索
+    fs::write(work_dir.path().join("c.txt"), "needle c\n").unwrap();
+    fs::write(work_dir.path().join("list.txt"), "a.txt\nmissing.txt\nb.txt\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--json", "--files-from", "list.txt", "needle"]);
+    assert!(stdout.contains("needle a") && stdout.contains("needle b"), "{}", stdout);
+    assert!(!stdout.contains("needle c"), "{}", stdout);
+    assert!(stdout.contains("\"files_scanned\":2"), "{}", stdout);
+    assert!(stdout.contains("\"errors\":1"), "{}", stdout);
+}
+
+#[test]
+fn dedup_results_prints_identical_results_once() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("a.js"), "// generated: needle\nneedle in a\n").unwrap();
+    fs::write(work_dir.path().join("b.js"), "// generated: needle\n").unwrap();
+
+    let stdout = run_ok(
+        work_dir.path(),
+        &["--dedup-results", "--sort", "path", "--summary-format", "json", "needle", "."],
+    );
+    assert_eq!(stdout.matches("generated").count(), 1, "{}", stdout);
+    assert!(stdout.contains(" in a"));
+    assert!(stdout.contains(r#""duplicates_suppressed":1"#), "{}", stdout);
+}
+
+#[test]
+fn exclude_pattern_drops_lines_matching_both_patterns() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("settings.py"), "password = 1\npassword_hash = 2\nPASSWORD_HASH = 3\n").unwrap();
+
+    let stdout = run_ok(
+        work_dir.path(),
+        &["--locations", "--exclude-pattern", "password_hash", "password", "settings.py"],
+    );
+    assert_eq!(stdout, "settings.py:1\n");
+}
+
+#[test]
+fn sequence_reports_pairs_within_the_window() {
+    let work_dir = tempdir().unwrap();
+    fs::write(
+        work_dir.path().join("app.log"),
+        "connect a\nok\ntimeout a\nconnect b\nok\nok\nok\ntimeout b\n",
+    )
+    .unwrap();
+
+    let stdout = run_ok(
+        work_dir.path(),
+        &["--locations", "--sequence", "connect", "timeout", "--within", "3", "app.log"],
+    );
+    // 第二对相隔 4 行，超出窗口
+    assert_eq!(stdout, "app.log:1\napp.log:3\n");
+
+    let output = run(
+        work_dir.path(),
+        &["--locations", "--debug-matcher", "--sequence", "connect", "timeout", "app.log"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[匹配器] 正则: connect "), "{}", stderr);
+    assert!(stderr.contains("[匹配器] 序列第二行: timeout"), "{}", stderr);
+}
+
+#[test]
+fn line_ending_controls_terminator_in_file_output() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "needle 1\nhay\nneedle 2\n").unwrap();
+
+    let locations = |line_ending: &str| {
+        run_ok(work_dir.path(), &["--locations", "--line-ending", line_ending, "needle", "notes.txt"])
+    };
+
+    assert_eq!(locations("crlf"), "notes.txt:1\r\nnotes.txt:3\r\n");
+    assert_eq!(locations("lf"), "notes.txt:1\nnotes.txt:3\n");
+    let expected_auto = if cfg!(windows) { locations("crlf") } else { locations("lf") };
+    assert_eq!(locations("auto"), expected_auto);
+
+    // 普通输出的横幅、结果和摘要都使用同一种换行符
+    let full = run_ok(work_dir.path(), &["--line-ending", "crlf", "needle", "notes.txt"]);
+    assert!(full.contains("\r\n"));
+    assert_eq!(full.matches('\n').count(), full.matches("\r\n").count(), "{:?}", full);
+}
+
+#[test]
+fn replace_filters_stdin_to_stdout() {
+    let work_dir = tempdir().unwrap();
+    let input = "host = example.org\n  keep   spacing\t\n\nfoo foo\nno newline foo";
+
+    let output = run_with_stdin(work_dir.path(), &["foo", "--replace", "bar"], input.as_bytes());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "host = example.org\n  keep   spacing\t\n\nbar bar\nno newline bar"
+    );
+}
+
+#[test]
+fn dash_path_searches_stdin_content() {
+    let work_dir = tempdir().unwrap();
+
+    let output = run_with_stdin(work_dir.path(), &["--json", "-C", "1", "needle", "-"], b"one\nneedle\nthree\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\"path\":\"<stdin>\""), "{}", stdout);
+    assert!(stdout.contains("\"line_number\":2"), "{}", stdout);
+    assert!(stdout.contains("\"three\""), "{}", stdout);
+
+    // 标准输入不支持的选项报错，而不是被忽略
+    for extra in [&["--lines", "1:2"][..], &["--encoding", "gbk"], &["--block-context", "5"]] {
+        let mut args = extra.to_vec();
+        args.extend(["needle", "-"]);
+        let output = run_with_stdin(work_dir.path(), &args, b"");
+        assert!(!output.status.success(), "{:?}", extra);
+        assert!(String::from_utf8_lossy(&output.stderr).contains(extra[0]), "{:?}", extra);
+    }
+}
+
+#[test]
+fn replace_previews_files_and_in_place_rewrites_them() {
+    let work_dir = tempdir().unwrap();
+    let path = work_dir.path().join("app.conf");
+    fs::write(&path, "port=80\nhost=a\nport=443\n").unwrap();
+
+    let replace = |extra: &[&str]| {
+        let mut args = vec!["--color", "never", "-r", r"port=(\d+)", "--replace", "port=8$1", "app.conf"];
+        args.extend_from_slice(extra);
+        run_ok(work_dir.path(), &args)
+    };
+
+    // 默认只预览，不修改文件
+    let preview = replace(&[]);
+    assert!(preview.contains("\n-     1:  port=80\n+     1:  port=880\n"), "{}", preview);
+    assert!(preview.contains("\n-     3:  port=443\n+     3:  port=8443\n"), "{}", preview);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "port=80\nhost=a\nport=443\n");
+
+    replace(&["--in-place"]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "port=880\nhost=a\nport=8443\n");
+    // 临时文件已被重命名
+    let leftovers: Vec<_> = fs::read_dir(work_dir.path()).unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
+
+#[test]
+fn fail_fast_aborts_with_the_offending_path() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("broken.txt"), b"needle \xff\xfe\n").unwrap();
+
+    let output = run(work_dir.path(), &["--fail-fast", "--encoding", "utf8", "needle", "."]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("broken.txt") && stderr.contains("UTF-8"), "{}", stderr);
+    // 部分结果的摘要仍然输出
+    assert!(String::from_utf8_lossy(&output.stdout).contains("搜索摘要"));
+
+    // 不指定时只记录错误并继续
+    assert!(run(work_dir.path(), &["--encoding", "utf8", "needle", "."]).status.success());
+}
+
+#[test]
+fn utf8_only_skips_and_counts_invalid_files() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("clean.txt"), "needle\n").unwrap();
+    fs::write(work_dir.path().join("latin1.txt"), b"caf\xe9 needle\n").unwrap();
+    fs::write(work_dir.path().join("utf16.txt"), b"\xff\xfen\x00e\x00").unwrap();
+
+    let output = run(work_dir.path(), &["--utf8-only", "--summary-format", "json", "needle", "."]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""non_utf8_skipped":2"#), "{}", stdout);
+    assert!(stdout.contains(r#""errors":0"#), "{}", stdout);
+    assert!(stdout.contains(r#""matches":1"#), "{}", stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("latin1.txt") && stderr.contains("utf16.txt"), "{}", stderr);
+}
+
+#[test]
+fn max_allowed_fails_when_matches_exceed_threshold() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("main.rs"), "// TODO:FIXME a\n// TODO:FIXME b\n").unwrap();
+
+    let with_max_allowed = |max_allowed: &str| {
+        run(work_dir.path(), &["--locations", &format!("--max-allowed={}", max_allowed), "TODO:FIXME", "."])
+    };
+
+    let output = with_max_allowed("0");
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr).trim_end(),
+        "policy violation: 2 matches exceed allowed 0"
+    );
+    // 结果仍然全部输出
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 2);
+
+    assert!(with_max_allowed("2").status.success());
+}
+
+#[test]
+fn int_searches_for_the_encoded_bytes() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("le.txt"), b"id=\x78\x56\x34\x12\n").unwrap();
+    fs::write(work_dir.path().join("be.txt"), b"id=\x12\x34\x56\x78\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--locations", "--int", "305419896:32:le", "."]);
+    assert!(stdout.contains("le.txt:1"), "{}", stdout);
+    assert!(!stdout.contains("be.txt"), "{}", stdout);
+
+    // 含有 0x80 以上字节的值按原始字节匹配：1000 为 e8 03 00 00
+    fs::write(work_dir.path().join("high.bin"), b"id=\xe8\x03\x00\x00\n").unwrap();
+    fs::write(work_dir.path().join("utf8.txt"), "caf\u{e9}\n").unwrap();
+    let stdout = run_ok(work_dir.path(), &["--locations", "--int", "1000:32:le", "."]);
+    assert!(stdout.contains("high.bin:1"), "{}", stdout);
+
+    // --hex e9 不匹配 UTF-8 编码的 é（c3 a9）
+    let output = run(work_dir.path(), &["--locations", "--hex", "e9", "."]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("utf8.txt"));
+}
+
+#[test]
+fn block_context_shows_the_enclosing_block() {
+    let work_dir = tempdir().unwrap();
+    fs::write(
+        work_dir.path().join("lib.rs"),
+        "fn other() {}\n\nfn target() {\n    let a = 1;\n    needle(a);\n    let b = 2;\n}\n\nfn tail() {}\n",
+    )
+    .unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--no-context", "needle", "lib.rs", "--block-context"]);
+    assert!(stdout.contains("fn target() {") && stdout.contains("let b = 2;"), "{}", stdout);
+    assert!(!stdout.contains("fn other") && !stdout.contains("fn tail"), "{}", stdout);
+}
+
+#[test]
+fn index_records_match_locations() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "ab\nxx needle\nneedle\n").unwrap();
+
+    run_ok(work_dir.path(), &["--index", "matches.idx", "needle", "notes.txt"]);
+    let index = fs::read_to_string(work_dir.path().join("matches.idx")).unwrap();
+    let lines: Vec<&str> = index.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", index);
+    assert_eq!(lines[1], "notes.txt\t00000000000000000002\t00000000000000000006\t00000000000000000004");
+    assert_eq!(lines[2], "notes.txt\t00000000000000000003\t00000000000000000013\t00000000000000000001");
+}
+
+#[test]
+fn missing_search_path_fails_with_a_clear_message() {
+    let work_dir = tempdir().unwrap();
+
+    let output = run(work_dir.path(), &["needle", "no_such_dir"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("搜索路径不存在: no_such_dir"));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn ignore_case_matches_other_cases() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "HELLO\nbye\n").unwrap();
+
+    assert_eq!(run_ok(work_dir.path(), &["--locations", "-i", "hello", "notes.txt"]), "notes.txt:1\n");
+    assert!(run(work_dir.path(), &["--locations", "hello", "notes.txt"]).stdout.is_empty());
+}
+
+#[test]
+fn color_follows_color_option() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "one needle\n").unwrap();
+
+    let with_color = |color: Option<&str>| {
+        let mut args = vec!["--no-context", "needle", "notes.txt"];
+        if let Some(color) = color {
+            args.extend_from_slice(&["--color", color]);
+        }
+        run_ok(work_dir.path(), &args)
+    };
+
+    // 输出到管道时 auto 不使用颜色
+    for color in [None, Some("auto"), Some("never")] {
+        let stdout = with_color(color);
+        assert!(!stdout.contains('\x1b'), "{:?}: {}", color, stdout);
+        assert!(stdout.contains("\n     1:  one needle\n"), "{}", stdout);
+    }
+    assert!(with_color(Some("always")).contains("\x1b[1;31mneedle\x1b[0m"));
+}
+
+#[test]
+fn files_with_matches_lists_each_file_once() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("b.txt"), "needle\nneedle\nneedle\n").unwrap();
+    fs::write(work_dir.path().join("a.txt"), "one needle\n").unwrap();
+    fs::write(work_dir.path().join("c.txt"), "nothing here\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["-l", "needle", "."]);
+    let paths: Vec<&str> = stdout.lines().collect();
+    assert_eq!(paths.len(), 2, "{}", stdout);
+    assert!(paths[0].ends_with("a.txt") && paths[1].ends_with("b.txt"), "{}", stdout);
+}
+
+#[test]
+fn count_prints_matching_lines_per_file() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "needle\nhay\nneedle needle\nhay\nlast needle\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--no-parallel", "-c", "needle", "notes.txt"]);
+    assert!(stdout.contains("\nnotes.txt:3\n"), "{}", stdout);
+    // 摘要仍然输出全局统计
+    assert!(stdout.contains("匹配项数: 3"), "{}", stdout);
+}
+
+#[test]
+fn repeated_e_patterns_match_any_of_them() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "a cat\nbird\ndog here\nfish\n").unwrap();
+
+    // 位置参数是搜索路径
+    let stdout = run_ok(work_dir.path(), &["--no-parallel", "-c", "-e", "cat", "-e", "dog", "notes.txt"]);
+    assert!(stdout.contains("\nnotes.txt:2\n"), "{}", stdout);
+
+    let output = run(work_dir.path(), &["-c", "--debug-matcher", "-e", "a.b", "-e", "dog", "notes.txt"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[匹配器] 模式 1: a\\.b\n"), "{}", stderr);
+    assert!(stderr.contains("[匹配器] 模式 2: dog\n"), "{}", stderr);
+}
+
+#[test]
+fn before_and_after_context_are_independent() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "one\ntwo\nneedle\nfour\nfive\nsix\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--color", "never", "-A", "2", "-B", "0", "needle", "notes.txt"]);
+    assert!(stdout.contains("\n     3:  needle\n     4:  four\n     5:  five\n"), "{}", stdout);
+    assert!(!stdout.contains("two") && !stdout.contains("six"), "{}", stdout);
+}
+
+#[test]
+fn include_and_exclude_globs_filter_files() {
+    let work_dir = tempdir().unwrap();
+    fs::create_dir_all(work_dir.path().join("target/debug")).unwrap();
+    fs::create_dir(work_dir.path().join("src")).unwrap();
+    fs::write(work_dir.path().join("src/lib.rs"), "needle\n").unwrap();
+    fs::write(work_dir.path().join("src/notes.txt"), "needle\n").unwrap();
+    fs::write(work_dir.path().join("target/debug/gen.rs"), "needle\n").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["-l", "--include", "*.rs", "--exclude", "target/**", "needle", "."]);
+    let paths: Vec<&str> = stdout.lines().collect();
+    assert_eq!(paths.len(), 1, "{}", stdout);
+    assert!(paths[0].ends_with("lib.rs"), "{}", stdout);
+}
+
+#[test]
+fn format_json_emits_only_json_lines() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("notes.txt"), "one needle\ntwo\nneedle three\n").unwrap();
+
+    for format in ["json", "ndjson"] {
+        let stdout = run_ok(work_dir.path(), &["--format", format, "needle", "notes.txt"]);
+        assert!(!stdout.contains('\x1b'), "{}", stdout);
+        // 每一行都是独立的 JSON 对象
+        let events: Vec<serde_json::Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0]["type"], "match");
+        assert_eq!(events[1]["line_number"], 3);
+        assert_eq!(events[2]["type"], "summary");
+    }
+}
+
+#[test]
+fn name_and_path_match_file_paths_instead_of_contents() {
+    let work_dir = tempdir().unwrap();
+    fs::create_dir(work_dir.path().join("foo")).unwrap();
+    fs::write(work_dir.path().join("foo.rs"), "fn main() {}\n").unwrap();
+    fs::write(work_dir.path().join("foo").join("bar.rs"), "fn bar() {}\n").unwrap();
+    fs::write(work_dir.path().join("other.rs"), "// foo\n").unwrap();
+
+    let matched_paths = |mode: &str| {
+        let stdout = run_ok(work_dir.path(), &["--no-parallel", "--color", "never", mode, "foo", "."]);
+        let mut paths: Vec<String> = stdout
+            .lines()
+            .filter(|line| line.ends_with(".rs"))
+            .map(str::to_string)
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    // 内容中含 foo 的 other.rs 不算匹配
+    let names = matched_paths("--name");
+    assert_eq!(names.len(), 1, "{:?}", names);
+    assert!(names[0].ends_with("foo.rs"));
+
+    let paths = matched_paths("--path");
+    assert_eq!(paths.len(), 2, "{:?}", paths);
+    assert!(paths.iter().any(|path| path.ends_with("bar.rs")), "{:?}", paths);
+}
+
+#[test]
+fn max_count_keeps_total_line_count_in_file_header() {
+    let work_dir = tempdir().unwrap();
+    let content: String = (1..=1000).map(|i| if i == 870 { "needle\n".to_string() } else { format!("line {}\n", i) }).collect();
+    fs::write(work_dir.path().join("a.txt"), content).unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--color", "never", "--file-header", "-m", "1", "needle", "a.txt"]);
+    assert!(stdout.contains("a.txt (1000 行, 1 处匹配)"), "{}", stdout);
+}
+
+#[test]
+fn line_range_keeps_total_line_count_for_position() {
+    let work_dir = tempdir().unwrap();
+    let content: String = (1..=1000).map(|i| if i == 870 { "needle\n".to_string() } else { format!("line {}\n", i) }).collect();
+    fs::write(work_dir.path().join("a.txt"), content).unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--color", "never", "--file-header", "--lines", "1:900", "needle", "a.txt"]);
+    assert!(stdout.contains("a.txt (1000 行, 1 处匹配)"), "{}", stdout);
+    let stdout = run_ok(work_dir.path(), &["--color", "never", "-H", "--position", "--lines", "1:900", "needle", "a.txt"]);
+    assert!(stdout.contains("(87%)"), "{}", stdout);
+}
+
+#[test]
+fn position_is_shown_when_filename_is_hidden() {
+    let work_dir = tempdir().unwrap();
+    let content: String = (1..=100).map(|i| if i == 87 { "needle\n".to_string() } else { format!("line {}\n", i) }).collect();
+    fs::write(work_dir.path().join("a.txt"), content).unwrap();
+
+    // 单个文件默认不显示文件名，-h 同样
+    for args in [&["--position", "needle", "a.txt"][..], &["-h", "--position", "needle", "."][..]] {
+        let mut args = args.to_vec();
+        args.extend_from_slice(&["--color", "never", "--no-context"]);
+        let stdout = run_ok(work_dir.path(), &args);
+        assert!(stdout.contains("\n87 (87%)\n    87:  needle\n"), "{}", stdout);
+    }
+}
+
+#[test]
+fn column_is_shown_when_filename_is_hidden() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("a.txt"), "one
+say needle
+").unwrap();
+
+    for args in [&["--column", "needle", "a.txt"][..], &["-h", "--column", "needle", "."][..]] {
+        let mut args = args.to_vec();
+        args.extend_from_slice(&["--color", "never", "--no-context"]);
+        let stdout = run_ok(work_dir.path(), &args);
+        assert!(stdout.contains("\n2:5\n     2:  say needle\n"), "{}", stdout);
+    }
+}
+
+#[test]
+fn line_match_count_is_shown_when_filename_is_hidden() {
+    let work_dir = tempdir().unwrap();
+    fs::write(work_dir.path().join("a.txt"), "one
+needle and needle
+").unwrap();
+
+    let stdout = run_ok(work_dir.path(), &["--color", "never", "--no-context", "--line-match-count", "needle", "a.txt"]);
+    assert!(stdout.contains("\n2(2)\n     2:  needle and needle\n"), "{}", stdout);
+}
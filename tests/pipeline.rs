@@ -0,0 +1,136 @@
+//! 端到端测试：在临时目录中构建文件树，经过遍历、匹配和结果收集的完整流程
+
+use std::fs;
+use std::path::Path;
+
+use tempfile::{tempdir, TempDir};
+
+use find_everything::domain::{
+    search_directory, ContextLines, FileFilter, SearchOptions, SearchPattern, SearchResult, WalkOptions,
+};
+
+/// 在临时目录中按 (相对路径, 内容) 创建文件
+fn build_tree(files: &[(&str, &[u8])]) -> TempDir {
+    let temp_dir = tempdir().unwrap();
+    for (relative, content) in files {
+        let path = temp_dir.path().join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn no_filter() -> FileFilter {
+    FileFilter::new(None, None, vec![".git".to_string()], vec![])
+}
+
+fn file_names(results: &[SearchResult]) -> Vec<String> {
+    results
+        .iter()
+        .map(|r| Path::new(&r.path).file_name().unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+#[test]
+fn search_reader_streams_input_with_context() {
+    use find_everything::domain::search::{search_reader, STDIN_PATH};
+
+    let input: &[u8] = b"alpha\nbeta\nneedle one\ngamma\ndelta\nneedle two\n";
+    let matcher = SearchPattern::from_input("needle", false, false).unwrap().get_line_matcher().unwrap();
+    let context = ContextLines { before: 1, after: 1 };
+
+    let search = search_reader(STDIN_PATH, input, &matcher, context, 1000, None, false).unwrap();
+
+    assert_eq!(search.lines_scanned, 6);
+    let lines: Vec<u64> = search.results.iter().map(|r| r.line_number).collect();
+    assert_eq!(lines, vec![3, 6]);
+    assert!(search.results.iter().all(|r| r.path == "<stdin>"));
+    assert_eq!(search.results[0].context_before, vec!["beta"]);
+    assert_eq!(search.results[0].context_after, vec!["gamma"]);
+    assert_eq!(search.results[1].context_before, vec!["delta"]);
+}
+
+#[test]
+fn basic_match_with_context() {
+    let tree = build_tree(&[("notes.txt", b"one\ntwo\nthe needle is here\nfour\nfive\n")]);
+    let pattern = SearchPattern::from_input("needle", false, false).unwrap();
+    let options = SearchOptions { context: ContextLines::symmetric(2), ..SearchOptions::default() };
+
+    let results = search_directory(tree.path(), &pattern, no_filter(), &options).unwrap();
+
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert_eq!(result.line_number, 3);
+    assert_eq!(result.line, "the needle is here");
+    assert_eq!(result.matched_text, "needle");
+    assert_eq!(result.matches, vec![(4, 10)]);
+    assert_eq!(result.context_before, vec!["one", "two"]);
+    assert_eq!(result.context_after, vec!["four", "five"]);
+}
+
+#[test]
+fn gitignored_file_is_skipped() {
+    let tree = build_tree(&[
+        (".gitignore", b"ignored.log\n"),
+        ("ignored.log", b"needle\n"),
+        ("kept.txt", b"needle\n"),
+    ]);
+    fs::create_dir(tree.path().join(".git")).unwrap();
+    let pattern = SearchPattern::from_input("needle", false, false).unwrap();
+
+    let results = search_directory(tree.path(), &pattern, no_filter(), &SearchOptions::default()).unwrap();
+    assert_eq!(file_names(&results), vec!["kept.txt"]);
+
+    let walk = WalkOptions { respect_gitignore: false, ..SearchOptions::default().walk };
+    let options = SearchOptions { walk, ..SearchOptions::default() };
+    let results = search_directory(tree.path(), &pattern, no_filter(), &options).unwrap();
+    assert_eq!(file_names(&results), vec!["ignored.log", "kept.txt"]);
+}
+
+#[test]
+fn size_filtered_file_is_skipped() {
+    let large = format!("needle\n{}", "x".repeat(4096));
+    let tree = build_tree(&[("small.txt", b"needle\n"), ("large.txt", large.as_bytes())]);
+    let pattern = SearchPattern::from_input("needle", false, false).unwrap();
+    let filter = FileFilter::new(None, Some(1024), vec![], vec![]);
+
+    let results = search_directory(tree.path(), &pattern, filter, &SearchOptions::default()).unwrap();
+
+    assert_eq!(file_names(&results), vec!["small.txt"]);
+}
+
+#[test]
+fn hex_match() {
+    let tree = build_tree(&[("data.txt", b"header\nvalue=ABC;\n")]);
+    let pattern = SearchPattern::from_input("41 42 43", false, true).unwrap();
+
+    let results = search_directory(tree.path(), &pattern, no_filter(), &SearchOptions::default()).unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].line_number, 2);
+    assert_eq!(results[0].matched_text, "ABC");
+    assert_eq!(results[0].matches, vec![(6, 9)]);
+}
+
+#[test]
+fn max_depth_limits_traversal() {
+    let tree = build_tree(&[
+        ("top.txt", b"needle\n"),
+        ("a/one.txt", b"needle\n"),
+        ("a/b/two.txt", b"needle\n"),
+        ("a/b/c/three.txt", b"needle\n"),
+    ]);
+    let pattern = SearchPattern::from_input("needle", false, false).unwrap();
+    let search = |max_depth: Option<usize>| {
+        let walk = WalkOptions { max_depth, ..SearchOptions::default().walk };
+        let options = SearchOptions { walk, ..SearchOptions::default() };
+        let mut names = file_names(&search_directory(tree.path(), &pattern, no_filter(), &options).unwrap());
+        names.sort();
+        names
+    };
+
+    // 深度 0 只有搜索目录下直接的文件
+    assert_eq!(search(Some(0)), vec!["top.txt"]);
+    assert_eq!(search(Some(1)), vec!["one.txt", "top.txt"]);
+    assert_eq!(search(None), vec!["one.txt", "three.txt", "top.txt", "two.txt"]);
+}
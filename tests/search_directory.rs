@@ -0,0 +1,37 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::tempdir;
+
+use find_everything::domain::{search_directory, FileFilter, SearchOptions, SearchPattern};
+
+#[test]
+fn search_directory_collects_sorted_results() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir(root.join("sub")).unwrap();
+    fs::write(root.join("b.txt"), "needle\nother\nneedle again\n").unwrap();
+    fs::write(root.join("sub").join("a.txt"), "no match here\n").unwrap();
+    fs::write(root.join("a.txt"), "x\nneedle\n").unwrap();
+
+    let pattern = SearchPattern::Text("needle".to_string());
+    let filter = FileFilter::new(None, None, vec![], vec![]);
+    let results = search_directory(root, &pattern, filter, &SearchOptions::default()).unwrap();
+
+    let found: Vec<(String, u64)> = results
+        .iter()
+        .map(|r| {
+            let name = Path::new(&r.path).file_name().unwrap().to_string_lossy().to_string();
+            (name, r.line_number)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("a.txt".to_string(), 2),
+            ("b.txt".to_string(), 1),
+            ("b.txt".to_string(), 3),
+        ]
+    );
+    assert_eq!(results[2].line, "needle again");
+}